@@ -1,2 +0,0 @@
-pub mod clear_database;
-pub mod make_request;
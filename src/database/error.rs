@@ -0,0 +1,33 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum StorageError {
+    Sqlite(rusqlite::Error),
+    /// The write succeeded but the transaction it was batched into did not commit.
+    Transaction(String),
+    /// The storage worker stopped before answering.
+    Unavailable,
+    /// Another monitor, with this id, already checks the same URL.
+    Duplicate(i32),
+}
+
+pub type StorageResult<T> = Result<T, StorageError>;
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Sqlite(e) => write!(f, "SQLite error: {}", e),
+            StorageError::Transaction(e) => write!(f, "Transaction failed to commit: {}", e),
+            StorageError::Unavailable => write!(f, "Storage is unavailable"),
+            StorageError::Duplicate(id) => write!(f, "URL already monitored by {}", id),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(e: rusqlite::Error) -> Self {
+        StorageError::Sqlite(e)
+    }
+}
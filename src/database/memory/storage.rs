@@ -0,0 +1,1230 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, TimeDelta};
+
+use crate::database::{
+    error::{StorageError, StorageResult},
+    models::{
+        Alert, AlertCondition, AlertMatcher, AlertRule, AlertSource, AlertState, ChannelConfig,
+        CheckOutcome, CheckResult, CoreSample, CpuSample, DeliveryResult, Escalation,
+        EscalationPolicy, EscalationSettings, EventKind, Heartbeat, Incident, IncidentEvent,
+        IncidentEventKind, MaintenanceSettings, MaintenanceWindow, MonitorStatus,
+        NotificationChannel, NotificationDelivery, Silence, UrlCertificate, UrlCheck, UrlData,
+        UrlSettings,
+    },
+    storage::Storage,
+    time::{format_timestamp, now},
+};
+
+const HISTORY_LIMIT: usize = 30;
+
+struct CpuRow {
+    id: i64,
+    total_cpus: u32,
+    total_cpu_usage: f32,
+    cores_usage: Vec<f32>,
+    created_at: NaiveDateTime,
+}
+
+struct UrlRow {
+    id: i32,
+    url: String,
+    settings: UrlSettings,
+    status_code: Option<i32>,
+    up: Option<bool>,
+    outcome: Option<CheckOutcome>,
+    unconfirmed_checks: u32,
+    flapping: bool,
+    certificate: Option<UrlCertificate>,
+    heartbeat: Option<Heartbeat>,
+    created_at: NaiveDateTime,
+}
+
+struct CheckRow {
+    id: i64,
+    url_id: i32,
+    result: CheckResult,
+    checked_at: NaiveDateTime,
+}
+
+struct RuleRow {
+    id: i64,
+    name: String,
+    condition: AlertCondition,
+    labels: BTreeMap<String, String>,
+    state: AlertState,
+    state_changed_at: Option<NaiveDateTime>,
+    value: Option<f64>,
+    created_at: NaiveDateTime,
+}
+
+struct AlertRow {
+    id: i64,
+    source: AlertSource,
+    name: String,
+    labels: BTreeMap<String, String>,
+    started_at: NaiveDateTime,
+    resolved_at: Option<NaiveDateTime>,
+    acknowledged_at: Option<NaiveDateTime>,
+    ack_comment: Option<String>,
+}
+
+struct IncidentRow {
+    id: i64,
+    alert_id: i64,
+    source: AlertSource,
+    name: String,
+    labels: BTreeMap<String, String>,
+    started_at: NaiveDateTime,
+    acknowledged_at: Option<NaiveDateTime>,
+    resolved_at: Option<NaiveDateTime>,
+}
+
+struct IncidentEventRow {
+    id: i64,
+    incident_id: i64,
+    kind: IncidentEventKind,
+    message: String,
+    created_at: NaiveDateTime,
+}
+
+struct SilenceRow {
+    id: i64,
+    matcher: AlertMatcher,
+    comment: String,
+    expires_at: NaiveDateTime,
+    created_at: NaiveDateTime,
+}
+
+struct PolicyRow {
+    id: i64,
+    settings: EscalationSettings,
+    created_at: NaiveDateTime,
+}
+
+struct EscalationRow {
+    id: i64,
+    alert_id: i64,
+    policy_id: i64,
+    step: u32,
+    due_at: NaiveDateTime,
+    notification: serde_json::Value,
+}
+
+struct WindowRow {
+    id: i64,
+    settings: MaintenanceSettings,
+    created_at: NaiveDateTime,
+}
+
+struct ChannelRow {
+    id: i64,
+    name: String,
+    config: ChannelConfig,
+    events: Vec<EventKind>,
+    enabled: bool,
+    created_at: NaiveDateTime,
+}
+
+struct DeliveryRow {
+    id: i64,
+    channel_id: i64,
+    result: DeliveryResult,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Default)]
+struct State {
+    cpu: Vec<CpuRow>,
+    urls: Vec<UrlRow>,
+    checks: Vec<CheckRow>,
+    rules: Vec<RuleRow>,
+    alerts: Vec<AlertRow>,
+    incidents: Vec<IncidentRow>,
+    incident_events: Vec<IncidentEventRow>,
+    silences: Vec<SilenceRow>,
+    policies: Vec<PolicyRow>,
+    escalations: Vec<EscalationRow>,
+    windows: Vec<WindowRow>,
+    channels: Vec<ChannelRow>,
+    deliveries: Vec<DeliveryRow>,
+    next_cpu_id: i64,
+    next_url_id: i32,
+    next_check_id: i64,
+    next_rule_id: i64,
+    next_alert_id: i64,
+    next_incident_id: i64,
+    next_incident_event_id: i64,
+    next_silence_id: i64,
+    next_policy_id: i64,
+    next_escalation_id: i64,
+    next_window_id: i64,
+    next_channel_id: i64,
+    next_delivery_id: i64,
+}
+
+/// Storage kept entirely in process memory. Rows are appended in insertion order, which is
+/// also creation order, so "latest first" queries simply walk the vectors backwards.
+#[derive(Default)]
+pub struct MemoryStorage {
+    state: Mutex<State>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> MemoryStorage {
+        MemoryStorage::default()
+    }
+}
+
+fn url_data(row: &UrlRow) -> UrlData {
+    UrlData {
+        id: row.id,
+        url: row.url.clone(),
+        settings: row.settings.clone(),
+        status_code: row.status_code,
+        up: row.up,
+        outcome: row.outcome,
+        unconfirmed_checks: row.unconfirmed_checks,
+        flapping: row.flapping,
+        certificate: row.certificate.clone(),
+        heartbeat: row.heartbeat.clone(),
+        created_at: format_timestamp(&row.created_at),
+    }
+}
+
+fn alert_rule(row: &RuleRow) -> AlertRule {
+    AlertRule {
+        id: row.id,
+        name: row.name.clone(),
+        condition: row.condition,
+        labels: row.labels.clone(),
+        state: row.state,
+        state_changed_at: row.state_changed_at.as_ref().map(format_timestamp),
+        value: row.value,
+        created_at: format_timestamp(&row.created_at),
+    }
+}
+
+fn alert(row: &AlertRow) -> Alert {
+    Alert {
+        id: row.id,
+        source: row.source,
+        name: row.name.clone(),
+        labels: row.labels.clone(),
+        started_at: format_timestamp(&row.started_at),
+        resolved_at: row.resolved_at.as_ref().map(format_timestamp),
+        acknowledged_at: row.acknowledged_at.as_ref().map(format_timestamp),
+        ack_comment: row.ack_comment.clone(),
+    }
+}
+
+fn silence(row: &SilenceRow) -> Silence {
+    Silence {
+        id: row.id,
+        matcher: row.matcher.clone(),
+        comment: row.comment.clone(),
+        expires_at: format_timestamp(&row.expires_at),
+        created_at: format_timestamp(&row.created_at),
+    }
+}
+
+fn policy(row: &PolicyRow) -> EscalationPolicy {
+    EscalationPolicy {
+        id: row.id,
+        settings: row.settings.clone(),
+        created_at: format_timestamp(&row.created_at),
+    }
+}
+
+fn escalation(row: &EscalationRow) -> Escalation {
+    Escalation {
+        id: row.id,
+        alert_id: row.alert_id,
+        policy_id: row.policy_id,
+        step: row.step,
+        due_at: format_timestamp(&row.due_at),
+        notification: row.notification.clone(),
+    }
+}
+
+fn window(row: &WindowRow) -> MaintenanceWindow {
+    MaintenanceWindow {
+        id: row.id,
+        settings: row.settings.clone(),
+        created_at: format_timestamp(&row.created_at),
+    }
+}
+
+fn incident(row: &IncidentRow) -> Incident {
+    Incident {
+        id: row.id,
+        alert_id: row.alert_id,
+        source: row.source,
+        name: row.name.clone(),
+        labels: row.labels.clone(),
+        started_at: format_timestamp(&row.started_at),
+        acknowledged_at: row.acknowledged_at.as_ref().map(format_timestamp),
+        resolved_at: row.resolved_at.as_ref().map(format_timestamp),
+    }
+}
+
+fn incident_event(row: &IncidentEventRow) -> IncidentEvent {
+    IncidentEvent {
+        id: row.id,
+        incident_id: row.incident_id,
+        kind: row.kind,
+        message: row.message.clone(),
+        created_at: format_timestamp(&row.created_at),
+    }
+}
+
+fn add_incident_event(
+    state: &mut State,
+    incident_id: i64,
+    kind: IncidentEventKind,
+    message: String,
+) -> Option<IncidentEvent> {
+    if !state.incidents.iter().any(|row| row.id == incident_id) {
+        return None;
+    }
+    state.next_incident_event_id += 1;
+    let row = IncidentEventRow {
+        id: state.next_incident_event_id,
+        incident_id,
+        kind,
+        message,
+        created_at: now(),
+    };
+    let event = incident_event(&row);
+    state.incident_events.push(row);
+    Some(event)
+}
+
+/// The incident of the alert with this id, updated by `update`, with the event to add to its
+/// timeline.
+fn update_alert_incident(
+    state: &mut State,
+    alert_id: i64,
+    kind: IncidentEventKind,
+    message: String,
+    update: impl FnOnce(&mut IncidentRow),
+) {
+    let Some(row) = state
+        .incidents
+        .iter_mut()
+        .rev()
+        .find(|row| row.alert_id == alert_id)
+    else {
+        return;
+    };
+    update(row);
+    let incident_id = row.id;
+    add_incident_event(state, incident_id, kind, message);
+}
+
+fn resolve_alert(state: &mut State, source: AlertSource, detail: &str) -> Option<Alert> {
+    let row = state
+        .alerts
+        .iter_mut()
+        .find(|row| row.source == source && row.resolved_at.is_none())?;
+    let resolved_at = now();
+    row.resolved_at = Some(resolved_at);
+    let resolved = alert(row);
+    update_alert_incident(
+        state,
+        resolved.id,
+        IncidentEventKind::Resolved,
+        detail.to_string(),
+        |row| row.resolved_at = Some(resolved_at),
+    );
+    Some(resolved)
+}
+
+fn channel(row: &ChannelRow) -> NotificationChannel {
+    NotificationChannel {
+        id: row.id,
+        name: row.name.clone(),
+        config: row.config.clone(),
+        events: row.events.clone(),
+        enabled: row.enabled,
+        created_at: format_timestamp(&row.created_at),
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn save_cpu_info(
+        &self,
+        total_cpus: u32,
+        total_cpu_usage: f32,
+        cores_usage: Vec<f32>,
+    ) -> StorageResult<()> {
+        let mut state = self.state.lock().unwrap();
+        state.next_cpu_id += 1;
+        let id = state.next_cpu_id;
+        state.cpu.push(CpuRow {
+            id,
+            total_cpus,
+            total_cpu_usage,
+            cores_usage,
+            created_at: now(),
+        });
+        Ok(())
+    }
+
+    async fn get_cpu_history(&self) -> StorageResult<Vec<CpuSample>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .cpu
+            .iter()
+            .rev()
+            .take(HISTORY_LIMIT)
+            .map(|row| CpuSample {
+                id: row.id,
+                total_cpus: row.total_cpus,
+                total_cpu_usage: row.total_cpu_usage,
+                cores_usage: row.cores_usage.clone(),
+                created_at: format_timestamp(&row.created_at),
+            })
+            .collect())
+    }
+
+    async fn get_core_history(&self, core: u32) -> StorageResult<Vec<CoreSample>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .cpu
+            .iter()
+            .rev()
+            .filter_map(|row| {
+                row.cores_usage.get(core as usize).map(|usage| CoreSample {
+                    cpu_id: row.id,
+                    core,
+                    usage: *usage,
+                    created_at: format_timestamp(&row.created_at),
+                })
+            })
+            .take(HISTORY_LIMIT)
+            .collect())
+    }
+
+    async fn flush_cpu_info(&self) -> StorageResult<()> {
+        let cutoff = now() - TimeDelta::minutes(5);
+        self.state
+            .lock()
+            .unwrap()
+            .cpu
+            .retain(|row| row.created_at >= cutoff);
+        Ok(())
+    }
+
+    async fn get_urls(&self) -> StorageResult<Vec<UrlData>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.urls.iter().map(url_data).collect())
+    }
+
+    async fn get_url(&self, id: i32) -> StorageResult<Option<UrlData>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.urls.iter().find(|row| row.id == id).map(url_data))
+    }
+
+    async fn get_url_by_heartbeat_token(&self, token: String) -> StorageResult<Option<UrlData>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .urls
+            .iter()
+            .find(|row| {
+                row.heartbeat
+                    .as_ref()
+                    .is_some_and(|heartbeat| heartbeat.token == token)
+            })
+            .map(url_data))
+    }
+
+    async fn create_url(
+        &self,
+        url: String,
+        settings: UrlSettings,
+        heartbeat: Option<Heartbeat>,
+    ) -> StorageResult<UrlData> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(existing) = state.urls.iter().find(|row| row.url == url) {
+            return Err(StorageError::Duplicate(existing.id));
+        }
+        state.next_url_id += 1;
+        let row = UrlRow {
+            id: state.next_url_id,
+            url,
+            settings,
+            status_code: None,
+            up: None,
+            outcome: None,
+            unconfirmed_checks: 0,
+            flapping: false,
+            certificate: None,
+            heartbeat,
+            created_at: now(),
+        };
+        let data = url_data(&row);
+        state.urls.push(row);
+        Ok(data)
+    }
+
+    async fn update_url(
+        &self,
+        id: i32,
+        url: String,
+        settings: UrlSettings,
+        heartbeat: Option<Heartbeat>,
+    ) -> StorageResult<Option<UrlData>> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(existing) = state.urls.iter().find(|row| row.id != id && row.url == url) {
+            return Err(StorageError::Duplicate(existing.id));
+        }
+        if state.urls.iter().any(|row| row.id == id && row.url != url) {
+            resolve_alert(
+                &mut state,
+                AlertSource::Monitor(id),
+                "URL do monitor alterada",
+            );
+        }
+        Ok(state.urls.iter_mut().find(|row| row.id == id).map(|row| {
+            if row.url != url {
+                row.url = url;
+                row.status_code = None;
+                row.up = None;
+                row.outcome = None;
+                row.unconfirmed_checks = 0;
+                row.flapping = false;
+                row.certificate = None;
+            }
+            row.settings = settings;
+            row.heartbeat = heartbeat;
+            url_data(row)
+        }))
+    }
+
+    async fn rename_url(&self, id: i32, url: String) -> StorageResult<bool> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(existing) = state.urls.iter().find(|row| row.id != id && row.url == url) {
+            return Err(StorageError::Duplicate(existing.id));
+        }
+        Ok(state
+            .urls
+            .iter_mut()
+            .find(|row| row.id == id)
+            .map(|row| row.url = url)
+            .is_some())
+    }
+
+    async fn delete_url(&self, id: i32) -> StorageResult<bool> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.urls.len();
+        state.urls.retain(|row| row.id != id);
+        state.checks.retain(|row| row.url_id != id);
+        resolve_alert(&mut state, AlertSource::Monitor(id), "Monitor removido");
+        for row in state.windows.iter_mut() {
+            row.settings.url_ids.retain(|url_id| *url_id != id);
+        }
+        state.silences.retain(|row| row.matcher.url_id != Some(id));
+        let State {
+            policies,
+            escalations,
+            ..
+        } = &mut *state;
+        policies.retain(|row| row.settings.matcher.url_id != Some(id));
+        escalations.retain(|escalation| policies.iter().any(|row| row.id == escalation.policy_id));
+        Ok(state.urls.len() < before)
+    }
+
+    async fn save_certificate(
+        &self,
+        url_id: i32,
+        certificate: UrlCertificate,
+    ) -> StorageResult<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(row) = state.urls.iter_mut().find(|row| row.id == url_id) {
+            row.certificate = Some(certificate);
+        }
+        Ok(())
+    }
+
+    async fn save_heartbeat(&self, url_id: i32, heartbeat: Option<Heartbeat>) -> StorageResult<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(row) = state.urls.iter_mut().find(|row| row.id == url_id) {
+            row.heartbeat = heartbeat;
+        }
+        Ok(())
+    }
+
+    async fn save_missed_heartbeat(
+        &self,
+        url_id: i32,
+        expected_at: String,
+        heartbeat: Heartbeat,
+    ) -> StorageResult<bool> {
+        let mut state = self.state.lock().unwrap();
+        let Some(current) = state
+            .urls
+            .iter_mut()
+            .filter(|row| row.id == url_id)
+            .find_map(|row| row.heartbeat.as_mut())
+            .filter(|current| current.expected_at == expected_at)
+        else {
+            return Ok(false);
+        };
+        current.started_at = heartbeat.started_at;
+        current.expected_at = heartbeat.expected_at;
+        Ok(true)
+    }
+
+    async fn save_check_result(
+        &self,
+        url_id: i32,
+        result: CheckResult,
+        status: MonitorStatus,
+    ) -> StorageResult<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(row) = state
+            .urls
+            .iter_mut()
+            .find(|row| row.id == url_id && !result.maintenance)
+        {
+            if status.up == Some(result.up) {
+                row.status_code = result.status_code;
+                row.outcome = Some(result.outcome);
+            }
+            row.up = status.up;
+            row.unconfirmed_checks = status.unconfirmed_checks;
+            row.flapping = status.flapping;
+        }
+        state.next_check_id += 1;
+        let id = state.next_check_id;
+        state.checks.push(CheckRow {
+            id,
+            url_id,
+            result,
+            checked_at: now(),
+        });
+        Ok(())
+    }
+
+    async fn get_url_checks(
+        &self,
+        url_id: i32,
+        from: Option<NaiveDateTime>,
+        to: Option<NaiveDateTime>,
+        limit: u32,
+    ) -> StorageResult<Vec<UrlCheck>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .checks
+            .iter()
+            .rev()
+            .filter(|row| row.url_id == url_id)
+            .filter(|row| from.is_none_or(|from| row.checked_at >= from))
+            .filter(|row| to.is_none_or(|to| row.checked_at <= to))
+            .take(limit as usize)
+            .map(|row| UrlCheck {
+                id: row.id,
+                url_id: row.url_id,
+                result: row.result.clone(),
+                checked_at: format_timestamp(&row.checked_at),
+            })
+            .collect())
+    }
+
+    async fn flush_url_checks(&self, before: NaiveDateTime) -> StorageResult<()> {
+        self.state
+            .lock()
+            .unwrap()
+            .checks
+            .retain(|row| row.checked_at >= before);
+        Ok(())
+    }
+
+    async fn get_alert_rules(&self) -> StorageResult<Vec<AlertRule>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.rules.iter().map(alert_rule).collect())
+    }
+
+    async fn get_alert_rule(&self, id: i64) -> StorageResult<Option<AlertRule>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.rules.iter().find(|row| row.id == id).map(alert_rule))
+    }
+
+    async fn create_alert_rule(
+        &self,
+        name: String,
+        condition: AlertCondition,
+        labels: BTreeMap<String, String>,
+    ) -> StorageResult<AlertRule> {
+        let mut state = self.state.lock().unwrap();
+        state.next_rule_id += 1;
+        let row = RuleRow {
+            id: state.next_rule_id,
+            name,
+            condition,
+            labels,
+            state: AlertState::Inactive,
+            state_changed_at: None,
+            value: None,
+            created_at: now(),
+        };
+        let rule = alert_rule(&row);
+        state.rules.push(row);
+        Ok(rule)
+    }
+
+    async fn update_alert_rule(
+        &self,
+        id: i64,
+        name: String,
+        condition: AlertCondition,
+        labels: BTreeMap<String, String>,
+    ) -> StorageResult<Option<AlertRule>> {
+        let mut state = self.state.lock().unwrap();
+        if state
+            .rules
+            .iter()
+            .any(|row| row.id == id && row.condition != condition)
+        {
+            resolve_alert(
+                &mut state,
+                AlertSource::Rule(id),
+                "Condição da regra alterada",
+            );
+        }
+        Ok(state.rules.iter_mut().find(|row| row.id == id).map(|row| {
+            if row.condition != condition {
+                row.condition = condition;
+                row.state = AlertState::Inactive;
+                row.state_changed_at = None;
+            }
+            row.name = name;
+            row.labels = labels;
+            alert_rule(row)
+        }))
+    }
+
+    async fn delete_alert_rule(&self, id: i64) -> StorageResult<bool> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.rules.len();
+        state.rules.retain(|row| row.id != id);
+        resolve_alert(&mut state, AlertSource::Rule(id), "Regra removida");
+        state.silences.retain(|row| row.matcher.rule_id != Some(id));
+        let State {
+            policies,
+            escalations,
+            ..
+        } = &mut *state;
+        policies.retain(|row| row.settings.matcher.rule_id != Some(id));
+        escalations.retain(|escalation| policies.iter().any(|row| row.id == escalation.policy_id));
+        Ok(state.rules.len() < before)
+    }
+
+    async fn save_alert_state(
+        &self,
+        id: i64,
+        alert_state: AlertState,
+        value: f64,
+    ) -> StorageResult<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(row) = state.rules.iter_mut().find(|row| row.id == id) {
+            if row.state != alert_state || row.state_changed_at.is_none() {
+                row.state = alert_state;
+                row.state_changed_at = Some(now());
+            }
+            row.value = Some(value);
+        }
+        Ok(())
+    }
+
+    async fn open_alert(
+        &self,
+        source: AlertSource,
+        name: String,
+        labels: BTreeMap<String, String>,
+        detail: String,
+    ) -> StorageResult<Option<Alert>> {
+        let mut state = self.state.lock().unwrap();
+        if state
+            .alerts
+            .iter()
+            .any(|row| row.source == source && row.resolved_at.is_none())
+        {
+            return Ok(None);
+        }
+        state.next_alert_id += 1;
+        let row = AlertRow {
+            id: state.next_alert_id,
+            source,
+            name,
+            labels,
+            started_at: now(),
+            resolved_at: None,
+            acknowledged_at: None,
+            ack_comment: None,
+        };
+        let opened = alert(&row);
+        state.next_incident_id += 1;
+        let incident_id = state.next_incident_id;
+        state.incidents.push(IncidentRow {
+            id: incident_id,
+            alert_id: row.id,
+            source: row.source,
+            name: row.name.clone(),
+            labels: row.labels.clone(),
+            started_at: row.started_at,
+            acknowledged_at: None,
+            resolved_at: None,
+        });
+        state.alerts.push(row);
+        add_incident_event(&mut state, incident_id, IncidentEventKind::Detected, detail);
+        Ok(Some(opened))
+    }
+
+    async fn resolve_alert(
+        &self,
+        source: AlertSource,
+        detail: String,
+    ) -> StorageResult<Option<Alert>> {
+        let mut state = self.state.lock().unwrap();
+        Ok(resolve_alert(&mut state, source, &detail))
+    }
+
+    async fn get_alerts(&self, active: Option<bool>, limit: u32) -> StorageResult<Vec<Alert>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .alerts
+            .iter()
+            .rev()
+            .filter(|row| active.is_none_or(|active| row.resolved_at.is_none() == active))
+            .take(limit as usize)
+            .map(alert)
+            .collect())
+    }
+
+    async fn get_alert(&self, id: i64) -> StorageResult<Option<Alert>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.alerts.iter().find(|row| row.id == id).map(alert))
+    }
+
+    async fn acknowledge_alert(
+        &self,
+        id: i64,
+        comment: Option<String>,
+    ) -> StorageResult<Option<Alert>> {
+        let mut state = self.state.lock().unwrap();
+        let Some(row) = state
+            .alerts
+            .iter_mut()
+            .find(|row| row.id == id && row.acknowledged_at.is_none() && row.resolved_at.is_none())
+        else {
+            return Ok(None);
+        };
+        let acknowledged_at = now();
+        row.acknowledged_at = Some(acknowledged_at);
+        row.ack_comment = comment;
+        let acknowledged = alert(row);
+        let message = acknowledged
+            .ack_comment
+            .clone()
+            .unwrap_or_else(|| "Alerta reconhecido".into());
+        update_alert_incident(
+            &mut state,
+            id,
+            IncidentEventKind::Acknowledged,
+            message,
+            |row| row.acknowledged_at = Some(acknowledged_at),
+        );
+        Ok(Some(acknowledged))
+    }
+
+    async fn flush_alerts(&self, before: NaiveDateTime) -> StorageResult<()> {
+        let mut state = self.state.lock().unwrap();
+        state.alerts.retain(|row| {
+            row.resolved_at
+                .is_none_or(|resolved_at| resolved_at >= before)
+        });
+        state.silences.retain(|row| row.expires_at >= before);
+        let State {
+            alerts,
+            escalations,
+            ..
+        } = &mut *state;
+        escalations.retain(|escalation| alerts.iter().any(|row| row.id == escalation.alert_id));
+        Ok(())
+    }
+
+    async fn get_incidents(
+        &self,
+        open: Option<bool>,
+        source: Option<AlertSource>,
+        limit: u32,
+    ) -> StorageResult<Vec<Incident>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .incidents
+            .iter()
+            .rev()
+            .filter(|row| open.is_none_or(|open| row.resolved_at.is_none() == open))
+            .filter(|row| source.is_none_or(|source| row.source == source))
+            .take(limit as usize)
+            .map(incident)
+            .collect())
+    }
+
+    async fn get_incident(&self, id: i64) -> StorageResult<Option<Incident>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .incidents
+            .iter()
+            .find(|row| row.id == id)
+            .map(incident))
+    }
+
+    async fn get_alert_incident(&self, alert_id: i64) -> StorageResult<Option<Incident>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .incidents
+            .iter()
+            .rev()
+            .find(|row| row.alert_id == alert_id)
+            .map(incident))
+    }
+
+    async fn get_incident_events(&self, incident_id: i64) -> StorageResult<Vec<IncidentEvent>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .incident_events
+            .iter()
+            .filter(|row| row.incident_id == incident_id)
+            .map(incident_event)
+            .collect())
+    }
+
+    async fn add_incident_event(
+        &self,
+        incident_id: i64,
+        kind: IncidentEventKind,
+        message: String,
+    ) -> StorageResult<Option<IncidentEvent>> {
+        let mut state = self.state.lock().unwrap();
+        Ok(add_incident_event(&mut state, incident_id, kind, message))
+    }
+
+    async fn flush_incidents(&self, before: NaiveDateTime) -> StorageResult<()> {
+        let mut state = self.state.lock().unwrap();
+        state.incidents.retain(|row| {
+            row.resolved_at
+                .is_none_or(|resolved_at| resolved_at >= before)
+        });
+        let State {
+            incidents,
+            incident_events,
+            ..
+        } = &mut *state;
+        incident_events.retain(|event| incidents.iter().any(|row| row.id == event.incident_id));
+        Ok(())
+    }
+
+    async fn get_silences(&self, at: Option<NaiveDateTime>) -> StorageResult<Vec<Silence>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .silences
+            .iter()
+            .filter(|row| at.is_none_or(|at| row.expires_at > at))
+            .map(silence)
+            .collect())
+    }
+
+    async fn get_silence(&self, id: i64) -> StorageResult<Option<Silence>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.silences.iter().find(|row| row.id == id).map(silence))
+    }
+
+    async fn create_silence(
+        &self,
+        matcher: AlertMatcher,
+        comment: String,
+        expires_at: NaiveDateTime,
+    ) -> StorageResult<Silence> {
+        let mut state = self.state.lock().unwrap();
+        state.next_silence_id += 1;
+        let row = SilenceRow {
+            id: state.next_silence_id,
+            matcher,
+            comment,
+            expires_at,
+            created_at: now(),
+        };
+        let created = silence(&row);
+        state.silences.push(row);
+        Ok(created)
+    }
+
+    async fn delete_silence(&self, id: i64) -> StorageResult<bool> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.silences.len();
+        state.silences.retain(|row| row.id != id);
+        Ok(state.silences.len() < before)
+    }
+
+    async fn get_escalation_policies(&self) -> StorageResult<Vec<EscalationPolicy>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.policies.iter().map(policy).collect())
+    }
+
+    async fn get_escalation_policy(&self, id: i64) -> StorageResult<Option<EscalationPolicy>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.policies.iter().find(|row| row.id == id).map(policy))
+    }
+
+    async fn create_escalation_policy(
+        &self,
+        settings: EscalationSettings,
+    ) -> StorageResult<EscalationPolicy> {
+        let mut state = self.state.lock().unwrap();
+        state.next_policy_id += 1;
+        let row = PolicyRow {
+            id: state.next_policy_id,
+            settings,
+            created_at: now(),
+        };
+        let created = policy(&row);
+        state.policies.push(row);
+        Ok(created)
+    }
+
+    async fn update_escalation_policy(
+        &self,
+        id: i64,
+        settings: EscalationSettings,
+    ) -> StorageResult<Option<EscalationPolicy>> {
+        let mut state = self.state.lock().unwrap();
+        let Some(row) = state.policies.iter_mut().find(|row| row.id == id) else {
+            return Ok(None);
+        };
+        row.settings = settings;
+        Ok(Some(policy(row)))
+    }
+
+    async fn delete_escalation_policy(&self, id: i64) -> StorageResult<bool> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.policies.len();
+        state.policies.retain(|row| row.id != id);
+        state.escalations.retain(|row| row.policy_id != id);
+        Ok(state.policies.len() < before)
+    }
+
+    async fn start_escalation(
+        &self,
+        alert_id: i64,
+        policy_id: i64,
+        due_at: NaiveDateTime,
+        notification: serde_json::Value,
+    ) -> StorageResult<Escalation> {
+        let mut state = self.state.lock().unwrap();
+        state.next_escalation_id += 1;
+        let row = EscalationRow {
+            id: state.next_escalation_id,
+            alert_id,
+            policy_id,
+            step: 0,
+            due_at,
+            notification,
+        };
+        let started = escalation(&row);
+        state.escalations.push(row);
+        Ok(started)
+    }
+
+    async fn get_escalations(&self, alert_id: Option<i64>) -> StorageResult<Vec<Escalation>> {
+        let state = self.state.lock().unwrap();
+        let mut rows = state
+            .escalations
+            .iter()
+            .filter(|row| alert_id.is_none_or(|alert_id| row.alert_id == alert_id))
+            .collect::<Vec<&EscalationRow>>();
+        rows.sort_by_key(|row| (row.due_at, row.id));
+        Ok(rows.into_iter().map(escalation).collect())
+    }
+
+    async fn advance_escalation(
+        &self,
+        id: i64,
+        step: u32,
+        due_at: NaiveDateTime,
+    ) -> StorageResult<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(row) = state.escalations.iter_mut().find(|row| row.id == id) {
+            row.step = step;
+            row.due_at = due_at;
+        }
+        Ok(())
+    }
+
+    async fn delete_escalation(&self, id: i64) -> StorageResult<bool> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.escalations.len();
+        state.escalations.retain(|row| row.id != id);
+        Ok(state.escalations.len() < before)
+    }
+
+    async fn get_maintenance_windows(&self) -> StorageResult<Vec<MaintenanceWindow>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.windows.iter().map(window).collect())
+    }
+
+    async fn get_maintenance_window(&self, id: i64) -> StorageResult<Option<MaintenanceWindow>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.windows.iter().find(|row| row.id == id).map(window))
+    }
+
+    async fn create_maintenance_window(
+        &self,
+        settings: MaintenanceSettings,
+    ) -> StorageResult<MaintenanceWindow> {
+        let mut state = self.state.lock().unwrap();
+        state.next_window_id += 1;
+        let row = WindowRow {
+            id: state.next_window_id,
+            settings,
+            created_at: now(),
+        };
+        let created = window(&row);
+        state.windows.push(row);
+        Ok(created)
+    }
+
+    async fn update_maintenance_window(
+        &self,
+        id: i64,
+        settings: MaintenanceSettings,
+    ) -> StorageResult<Option<MaintenanceWindow>> {
+        let mut state = self.state.lock().unwrap();
+        let Some(row) = state.windows.iter_mut().find(|row| row.id == id) else {
+            return Ok(None);
+        };
+        row.settings = settings;
+        Ok(Some(window(row)))
+    }
+
+    async fn delete_maintenance_window(&self, id: i64) -> StorageResult<bool> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.windows.len();
+        state.windows.retain(|row| row.id != id);
+        Ok(state.windows.len() < before)
+    }
+
+    async fn get_channels(&self) -> StorageResult<Vec<NotificationChannel>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.channels.iter().map(channel).collect())
+    }
+
+    async fn get_channel(&self, id: i64) -> StorageResult<Option<NotificationChannel>> {
+        let state = self.state.lock().unwrap();
+        Ok(state.channels.iter().find(|row| row.id == id).map(channel))
+    }
+
+    async fn create_channel(
+        &self,
+        name: String,
+        config: ChannelConfig,
+        events: Vec<EventKind>,
+        enabled: bool,
+    ) -> StorageResult<NotificationChannel> {
+        let mut state = self.state.lock().unwrap();
+        state.next_channel_id += 1;
+        let row = ChannelRow {
+            id: state.next_channel_id,
+            name,
+            config,
+            events,
+            enabled,
+            created_at: now(),
+        };
+        let created = channel(&row);
+        state.channels.push(row);
+        Ok(created)
+    }
+
+    async fn update_channel(
+        &self,
+        id: i64,
+        name: String,
+        config: ChannelConfig,
+        events: Vec<EventKind>,
+        enabled: bool,
+    ) -> StorageResult<Option<NotificationChannel>> {
+        let mut state = self.state.lock().unwrap();
+        Ok(state
+            .channels
+            .iter_mut()
+            .find(|row| row.id == id)
+            .map(|row| {
+                row.name = name;
+                row.config = config;
+                row.events = events;
+                row.enabled = enabled;
+                channel(row)
+            }))
+    }
+
+    async fn delete_channel(&self, id: i64) -> StorageResult<bool> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.channels.len();
+        state.channels.retain(|row| row.id != id);
+        state.deliveries.retain(|row| row.channel_id != id);
+        for row in state.policies.iter_mut() {
+            for step in row.settings.steps.iter_mut() {
+                step.channel_ids.retain(|channel_id| *channel_id != id);
+            }
+        }
+        Ok(state.channels.len() < before)
+    }
+
+    async fn save_delivery(&self, channel_id: i64, result: DeliveryResult) -> StorageResult<()> {
+        let mut state = self.state.lock().unwrap();
+        state.next_delivery_id += 1;
+        let id = state.next_delivery_id;
+        state.deliveries.push(DeliveryRow {
+            id,
+            channel_id,
+            result,
+            created_at: now(),
+        });
+        Ok(())
+    }
+
+    async fn get_deliveries(
+        &self,
+        channel_id: Option<i64>,
+        limit: u32,
+    ) -> StorageResult<Vec<NotificationDelivery>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .deliveries
+            .iter()
+            .rev()
+            .filter(|row| channel_id.is_none_or(|channel_id| row.channel_id == channel_id))
+            .take(limit as usize)
+            .map(|row| NotificationDelivery {
+                id: row.id,
+                channel_id: row.channel_id,
+                result: row.result.clone(),
+                created_at: format_timestamp(&row.created_at),
+            })
+            .collect())
+    }
+
+    async fn flush_deliveries(&self, before: NaiveDateTime) -> StorageResult<()> {
+        self.state
+            .lock()
+            .unwrap()
+            .deliveries
+            .retain(|row| row.created_at >= before);
+        Ok(())
+    }
+}
@@ -1 +1,6 @@
-pub mod sqlite;
\ No newline at end of file
+pub mod error;
+pub mod memory;
+pub mod models;
+pub mod sqlite;
+pub mod storage;
+pub mod time;
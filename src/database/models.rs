@@ -0,0 +1,1040 @@
+use std::collections::BTreeMap;
+
+pub struct CpuSample {
+    pub id: i64,
+    pub total_cpus: u32,
+    pub total_cpu_usage: f32,
+    pub cores_usage: Vec<f32>,
+    pub created_at: String,
+}
+
+pub struct CoreSample {
+    pub cpu_id: i64,
+    pub core: u32,
+    pub usage: f32,
+    pub created_at: String,
+}
+
+/// Protocol a monitor is checked with, derived from the URL scheme.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MonitorType {
+    Http,
+    /// `tcp://host:port`, a connect optionally followed by a payload and a banner read.
+    Tcp,
+    /// `heartbeat://name`, never checked: the monitored job pings its URL instead.
+    Heartbeat,
+}
+
+impl MonitorType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MonitorType::Http => "http",
+            MonitorType::Tcp => "tcp",
+            MonitorType::Heartbeat => "heartbeat",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<MonitorType> {
+        match value {
+            "http" => Some(MonitorType::Http),
+            "tcp" => Some(MonitorType::Tcp),
+            "heartbeat" => Some(MonitorType::Heartbeat),
+            _ => None,
+        }
+    }
+}
+
+/// How a monitored URL is checked.
+#[derive(Clone)]
+pub struct UrlSettings {
+    pub monitor_type: MonitorType,
+    /// Seconds between two checks, or the expected period between pings of heartbeat monitors.
+    pub interval: u32,
+    /// Seconds a heartbeat ping may be late, or a started run may last, before the monitor
+    /// is considered down.
+    pub grace: u32,
+    /// Seconds before a check is abandoned.
+    pub timeout: u32,
+    pub method: String,
+    pub headers: BTreeMap<String, String>,
+    /// Request body, or the payload written after connecting for TCP monitors.
+    pub body: Option<String>,
+    pub follow_redirects: bool,
+    /// Accepted status codes and ranges, e.g. `200-299,301`.
+    pub expected_status: String,
+    /// Checked in order once the status is accepted, the first failure marks the check down.
+    /// TCP monitors evaluate them against the bytes received after connecting.
+    pub assertions: Vec<Assertion>,
+    /// Free-form `name: value` pairs copied to the alerts of the monitor, for silences.
+    pub labels: BTreeMap<String, String>,
+    /// Consecutive failed checks needed before an up monitor is marked down.
+    pub confirmations_down: u32,
+    /// Consecutive successful checks needed before a down monitor is marked up.
+    pub confirmations_up: u32,
+    /// Seconds between checks while a status change waits for its confirmations.
+    pub retry_interval: u32,
+}
+
+impl Default for UrlSettings {
+    fn default() -> Self {
+        UrlSettings {
+            monitor_type: MonitorType::Http,
+            interval: 60,
+            grace: 60,
+            timeout: 30,
+            method: "GET".into(),
+            headers: BTreeMap::new(),
+            body: None,
+            follow_redirects: true,
+            expected_status: "200-399".into(),
+            assertions: Vec::new(),
+            labels: BTreeMap::new(),
+            confirmations_down: 1,
+            confirmations_up: 1,
+            retry_interval: 10,
+        }
+    }
+}
+
+pub struct UrlData {
+    pub id: i32,
+    pub url: String,
+    pub settings: UrlSettings,
+    /// Status of the latest check agreeing with `up`, `None` until the URL is checked for the
+    /// first time.
+    pub status_code: Option<i32>,
+    /// Confirmed status of the monitor, only changed once enough consecutive checks agree.
+    pub up: Option<bool>,
+    pub outcome: Option<CheckOutcome>,
+    /// Consecutive latest checks disagreeing with `up`, still waiting for confirmation.
+    pub unconfirmed_checks: u32,
+    /// Whether the checks changed between up and down too often lately.
+    pub flapping: bool,
+    /// Latest certificate chain seen for `https://` monitors.
+    pub certificate: Option<UrlCertificate>,
+    /// Ping state of heartbeat monitors.
+    pub heartbeat: Option<Heartbeat>,
+    pub created_at: String,
+}
+
+/// Pings received by a heartbeat monitor, timestamps in the storage format.
+#[derive(Clone)]
+pub struct Heartbeat {
+    /// Secret part of the ping URL, `/heartbeat/{token}`.
+    pub token: String,
+    pub last_ping_at: Option<String>,
+    /// Set by a `/start` ping until the run ends with a success or failure ping.
+    pub started_at: Option<String>,
+    /// Once passed without a ping the monitor is late, and the next miss is counted one
+    /// interval later.
+    pub expected_at: String,
+}
+
+/// One certificate of a peer chain, with validity dates in the storage timestamp format.
+#[derive(Clone)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub not_before: String,
+    pub not_after: String,
+}
+
+impl CertificateInfo {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "subject": self.subject,
+            "issuer": self.issuer,
+            "not_before": self.not_before,
+            "not_after": self.not_after,
+        })
+    }
+
+    pub fn from_json(json: &serde_json::Value) -> Result<CertificateInfo, String> {
+        let field = |name: &str| {
+            json.get(name)
+                .and_then(|value| value.as_str())
+                .map(str::to_string)
+                .ok_or(format!("Certificado sem o campo '{}'", name))
+        };
+        Ok(CertificateInfo {
+            subject: field("subject")?,
+            issuer: field("issuer")?,
+            not_before: field("not_before")?,
+            not_after: field("not_after")?,
+        })
+    }
+}
+
+/// Peer certificate chain of a monitor, leaf first.
+#[derive(Clone)]
+pub struct UrlCertificate {
+    pub chain: Vec<CertificateInfo>,
+    pub checked_at: String,
+}
+
+/// A condition the response must meet, besides its status code.
+#[derive(Clone, Debug)]
+pub enum Assertion {
+    BodyContains(String),
+    BodyNotContains(String),
+    /// Regular expression the body must match, compiled once when the assertion is read.
+    BodyMatches(regex::Regex),
+    JsonPointerEquals {
+        pointer: String,
+        value: serde_json::Value,
+    },
+    HeaderPresent(String),
+    HeaderEquals {
+        name: String,
+        value: String,
+    },
+    MaxResponseTime(u64),
+}
+
+impl Assertion {
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Assertion::BodyContains(value) => {
+                serde_json::json!({ "type": "body_contains", "value": value })
+            }
+            Assertion::BodyNotContains(value) => {
+                serde_json::json!({ "type": "body_not_contains", "value": value })
+            }
+            Assertion::BodyMatches(regex) => {
+                serde_json::json!({ "type": "body_matches", "value": regex.as_str() })
+            }
+            Assertion::JsonPointerEquals { pointer, value } => {
+                serde_json::json!({ "type": "json_pointer_equals", "pointer": pointer, "value": value })
+            }
+            Assertion::HeaderPresent(name) => {
+                serde_json::json!({ "type": "header_present", "name": name })
+            }
+            Assertion::HeaderEquals { name, value } => {
+                serde_json::json!({ "type": "header_equals", "name": name, "value": value })
+            }
+            Assertion::MaxResponseTime(milliseconds) => {
+                serde_json::json!({ "type": "max_response_time", "value": milliseconds })
+            }
+        }
+    }
+
+    /// Reads the format written by `to_json`, compiling regular expressions.
+    pub fn from_json(json: &serde_json::Value) -> Result<Assertion, String> {
+        let field = |name: &str| {
+            json.get(name)
+                .and_then(|value| value.as_str())
+                .map(str::to_string)
+                .ok_or(format!("Asserção sem o campo '{}'", name))
+        };
+        match json.get("type").and_then(|kind| kind.as_str()) {
+            Some("body_contains") => Ok(Assertion::BodyContains(field("value")?)),
+            Some("body_not_contains") => Ok(Assertion::BodyNotContains(field("value")?)),
+            Some("body_matches") => regex::Regex::new(&field("value")?)
+                .map(Assertion::BodyMatches)
+                .map_err(|e| format!("Expressão regular inválida: {}", e)),
+            Some("json_pointer_equals") => Ok(Assertion::JsonPointerEquals {
+                pointer: field("pointer")?,
+                value: json
+                    .get("value")
+                    .cloned()
+                    .ok_or("Asserção sem o campo 'value'")?,
+            }),
+            Some("header_present") => Ok(Assertion::HeaderPresent(field("name")?)),
+            Some("header_equals") => Ok(Assertion::HeaderEquals {
+                name: field("name")?,
+                value: field("value")?,
+            }),
+            Some("max_response_time") => json
+                .get("value")
+                .and_then(|value| value.as_u64())
+                .map(Assertion::MaxResponseTime)
+                .ok_or("Asserção 'max_response_time' exige 'value' em milissegundos".into()),
+            _ => Err("Tipo de asserção inválido".into()),
+        }
+    }
+}
+
+/// Why a probe succeeded or failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckOutcome {
+    Ok,
+    /// A response arrived but its status was not expected.
+    HttpError,
+    Timeout,
+    Dns,
+    Connect,
+    Tls,
+    InvalidUrl,
+    /// The status was expected but one of the monitor's assertions failed.
+    AssertionFailed,
+    /// No heartbeat ping arrived within the interval and grace time.
+    Late,
+    /// The monitored job reported a failure with a `/fail` ping.
+    Failed,
+}
+
+impl CheckOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckOutcome::Ok => "ok",
+            CheckOutcome::HttpError => "http_error",
+            CheckOutcome::Timeout => "timeout",
+            CheckOutcome::Dns => "dns",
+            CheckOutcome::Connect => "connect",
+            CheckOutcome::Tls => "tls",
+            CheckOutcome::InvalidUrl => "invalid_url",
+            CheckOutcome::AssertionFailed => "assertion_failed",
+            CheckOutcome::Late => "late",
+            CheckOutcome::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<CheckOutcome> {
+        match value {
+            "ok" => Some(CheckOutcome::Ok),
+            "http_error" => Some(CheckOutcome::HttpError),
+            "timeout" => Some(CheckOutcome::Timeout),
+            "dns" => Some(CheckOutcome::Dns),
+            "connect" => Some(CheckOutcome::Connect),
+            "tls" => Some(CheckOutcome::Tls),
+            "invalid_url" => Some(CheckOutcome::InvalidUrl),
+            "assertion_failed" => Some(CheckOutcome::AssertionFailed),
+            "late" => Some(CheckOutcome::Late),
+            "failed" => Some(CheckOutcome::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// Outcome of a single probe.
+#[derive(Clone)]
+pub struct CheckResult {
+    /// `None` when no HTTP response was received.
+    pub status_code: Option<i32>,
+    pub up: bool,
+    pub outcome: CheckOutcome,
+    pub response_time_ms: i64,
+    pub response_size: Option<i64>,
+    pub error: Option<String>,
+    /// Description of the assertion that failed, when the outcome is `AssertionFailed`.
+    pub failed_assertion: Option<String>,
+    /// Taken during a maintenance window: kept in the history, but left out of the monitor's
+    /// status, notifications and uptime.
+    pub maintenance: bool,
+}
+
+/// Status of a monitor after one of its checks, see `UrlData`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MonitorStatus {
+    pub up: Option<bool>,
+    pub unconfirmed_checks: u32,
+    pub flapping: bool,
+}
+
+/// A stored probe of a monitored URL.
+pub struct UrlCheck {
+    pub id: i64,
+    pub url_id: i32,
+    pub result: CheckResult,
+    pub checked_at: String,
+}
+
+/// What happens to the checks of a monitor during one of its maintenance windows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MaintenanceMode {
+    /// No checks are made.
+    Pause,
+    /// Checks go on and are recorded as taken during maintenance.
+    Mark,
+}
+
+impl MaintenanceMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MaintenanceMode::Pause => "pause",
+            MaintenanceMode::Mark => "mark",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<MaintenanceMode> {
+        match value {
+            "pause" => Some(MaintenanceMode::Pause),
+            "mark" => Some(MaintenanceMode::Mark),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MaintenanceSchedule {
+    /// A single window, timestamps in UTC.
+    Once { starts_at: String, ends_at: String },
+    /// A window opening at each occurrence of a 5-field cron expression (UTC) and lasting
+    /// `duration` seconds.
+    Recurring { cron: String, duration: u32 },
+}
+
+#[derive(Clone, Debug)]
+pub struct MaintenanceSettings {
+    pub name: String,
+    pub mode: MaintenanceMode,
+    pub schedule: MaintenanceSchedule,
+    /// Monitors covered by the window, along with every monitor of `hosts`.
+    pub url_ids: Vec<i32>,
+    pub hosts: Vec<String>,
+}
+
+pub struct MaintenanceWindow {
+    pub id: i64,
+    pub settings: MaintenanceSettings,
+    pub created_at: String,
+}
+
+/// Host metric an alert rule is evaluated against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Metric {
+    /// Percentage over all cores.
+    TotalCpuUsage,
+    /// MiB, like `/memory`.
+    UsedMemory,
+    FreeMemory,
+    AvailableMemory,
+    /// Percentage of the total memory in use.
+    MemoryUsage,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 5] = [
+        Metric::TotalCpuUsage,
+        Metric::UsedMemory,
+        Metric::FreeMemory,
+        Metric::AvailableMemory,
+        Metric::MemoryUsage,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Metric::TotalCpuUsage => "total_cpu_usage",
+            Metric::UsedMemory => "used_memory",
+            Metric::FreeMemory => "free_memory",
+            Metric::AvailableMemory => "available_memory",
+            Metric::MemoryUsage => "memory_usage",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Metric> {
+        Metric::ALL
+            .into_iter()
+            .find(|metric| metric.as_str() == value)
+    }
+
+    pub fn is_percentage(&self) -> bool {
+        matches!(self, Metric::TotalCpuUsage | Metric::MemoryUsage)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Above,
+    AtLeast,
+    Below,
+    AtMost,
+}
+
+impl Comparison {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Comparison::Above => ">",
+            Comparison::AtLeast => ">=",
+            Comparison::Below => "<",
+            Comparison::AtMost => "<=",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Comparison> {
+        match value {
+            ">" => Some(Comparison::Above),
+            ">=" => Some(Comparison::AtLeast),
+            "<" => Some(Comparison::Below),
+            "<=" => Some(Comparison::AtMost),
+            _ => None,
+        }
+    }
+
+    pub fn holds(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Above => value > threshold,
+            Comparison::AtLeast => value >= threshold,
+            Comparison::Below => value < threshold,
+            Comparison::AtMost => value <= threshold,
+        }
+    }
+}
+
+/// `metric comparison threshold`, which must hold for `duration` seconds before firing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AlertCondition {
+    pub metric: Metric,
+    pub comparison: Comparison,
+    /// In the unit of the metric, percent or MiB.
+    pub threshold: f64,
+    pub duration: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertState {
+    /// The condition does not hold and the rule has not fired since it was last changed.
+    Inactive,
+    /// The condition holds, but not for `duration` yet.
+    Pending,
+    Firing,
+    /// The rule fired and its condition no longer holds.
+    Resolved,
+}
+
+impl AlertState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertState::Inactive => "inactive",
+            AlertState::Pending => "pending",
+            AlertState::Firing => "firing",
+            AlertState::Resolved => "resolved",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<AlertState> {
+        match value {
+            "inactive" => Some(AlertState::Inactive),
+            "pending" => Some(AlertState::Pending),
+            "firing" => Some(AlertState::Firing),
+            "resolved" => Some(AlertState::Resolved),
+            _ => None,
+        }
+    }
+}
+
+pub struct AlertRule {
+    pub id: i64,
+    pub name: String,
+    pub condition: AlertCondition,
+    /// Copied to the alerts raised by the rule, for silences.
+    pub labels: BTreeMap<String, String>,
+    pub state: AlertState,
+    /// When the rule entered its current state, `None` until it is first evaluated.
+    pub state_changed_at: Option<String>,
+    /// Metric value at the latest evaluation.
+    pub value: Option<f64>,
+    pub created_at: String,
+}
+
+/// What raised an alert.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertSource {
+    Rule(i64),
+    Monitor(i32),
+}
+
+impl AlertSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertSource::Rule(_) => "rule",
+            AlertSource::Monitor(_) => "monitor",
+        }
+    }
+
+    pub fn rule_id(&self) -> Option<i64> {
+        match self {
+            AlertSource::Rule(id) => Some(*id),
+            AlertSource::Monitor(_) => None,
+        }
+    }
+
+    pub fn url_id(&self) -> Option<i32> {
+        match self {
+            AlertSource::Monitor(id) => Some(*id),
+            AlertSource::Rule(_) => None,
+        }
+    }
+}
+
+/// One occurrence of a rule firing or a monitor going down, kept after it resolves. A source
+/// has at most one active alert at a time.
+pub struct Alert {
+    pub id: i64,
+    pub source: AlertSource,
+    /// Name of the rule or URL of the monitor when the alert started.
+    pub name: String,
+    /// Labels of the source plus `source` and `rule` or `url`.
+    pub labels: BTreeMap<String, String>,
+    pub started_at: String,
+    /// `None` while the alert is active.
+    pub resolved_at: Option<String>,
+    pub acknowledged_at: Option<String>,
+    pub ack_comment: Option<String>,
+}
+
+impl Alert {
+    pub fn is_active(&self) -> bool {
+        self.resolved_at.is_none()
+    }
+}
+
+/// Which alerts a silence or an escalation policy applies to: every criterion given must
+/// match.
+#[derive(Clone, Debug, Default)]
+pub struct AlertMatcher {
+    pub rule_id: Option<i64>,
+    pub url_id: Option<i32>,
+    pub labels: BTreeMap<String, String>,
+}
+
+impl AlertMatcher {
+    pub fn is_empty(&self) -> bool {
+        self.rule_id.is_none() && self.url_id.is_none() && self.labels.is_empty()
+    }
+
+    pub fn matches(&self, alert: &Alert) -> bool {
+        self.rule_id
+            .is_none_or(|id| alert.source.rule_id() == Some(id))
+            && self
+                .url_id
+                .is_none_or(|id| alert.source.url_id() == Some(id))
+            && self
+                .labels
+                .iter()
+                .all(|(name, value)| alert.labels.get(name) == Some(value))
+    }
+}
+
+/// Suppresses the notifications of matching alerts until it expires.
+pub struct Silence {
+    pub id: i64,
+    pub matcher: AlertMatcher,
+    pub comment: String,
+    pub expires_at: String,
+    pub created_at: String,
+}
+
+/// Channels notified once an alert has gone unacknowledged for `after` seconds.
+#[derive(Clone, Debug)]
+pub struct EscalationStep {
+    pub after: u32,
+    pub channel_ids: Vec<i64>,
+}
+
+impl EscalationStep {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({ "after": self.after, "channel_ids": self.channel_ids })
+    }
+
+    /// Reads the format written by `to_json`.
+    pub fn from_json(json: &serde_json::Value) -> Result<EscalationStep, String> {
+        let after = json
+            .get("after")
+            .and_then(|after| after.as_u64())
+            .and_then(|after| u32::try_from(after).ok())
+            .ok_or("Passo sem o campo 'after'")?;
+        let channel_ids = json
+            .get("channel_ids")
+            .and_then(|ids| ids.as_array())
+            .and_then(|ids| ids.iter().map(|id| id.as_i64()).collect())
+            .ok_or("Passo sem o campo 'channel_ids'")?;
+        Ok(EscalationStep { after, channel_ids })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EscalationSettings {
+    pub name: String,
+    /// An empty matcher applies the policy to every alert.
+    pub matcher: AlertMatcher,
+    /// Ordered by `after`.
+    pub steps: Vec<EscalationStep>,
+}
+
+/// Notifies more channels the longer an alert goes without being acknowledged.
+pub struct EscalationPolicy {
+    pub id: i64,
+    pub settings: EscalationSettings,
+    pub created_at: String,
+}
+
+/// A policy working through its steps for an active alert. It is dropped once the alert is
+/// acknowledged or resolved, or when the policy has no steps left.
+pub struct Escalation {
+    pub id: i64,
+    pub alert_id: i64,
+    pub policy_id: i64,
+    /// Index of the next step to notify.
+    pub step: u32,
+    pub due_at: String,
+    /// The notification that opened the alert, sent again at every step.
+    pub notification: serde_json::Value,
+}
+
+/// The record of an alert kept for post-mortems, with its timeline. It is opened along with
+/// the alert and outlives it.
+pub struct Incident {
+    pub id: i64,
+    pub alert_id: i64,
+    pub source: AlertSource,
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub started_at: String,
+    pub acknowledged_at: Option<String>,
+    /// `None` while the incident is open.
+    pub resolved_at: Option<String>,
+}
+
+impl Incident {
+    pub fn is_open(&self) -> bool {
+        self.resolved_at.is_none()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IncidentEventKind {
+    Detected,
+    /// A channel was notified, or gave up after its delivery attempts.
+    Notified,
+    Acknowledged,
+    Resolved,
+    Comment,
+}
+
+impl IncidentEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IncidentEventKind::Detected => "detected",
+            IncidentEventKind::Notified => "notified",
+            IncidentEventKind::Acknowledged => "acknowledged",
+            IncidentEventKind::Resolved => "resolved",
+            IncidentEventKind::Comment => "comment",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<IncidentEventKind> {
+        match value {
+            "detected" => Some(IncidentEventKind::Detected),
+            "notified" => Some(IncidentEventKind::Notified),
+            "acknowledged" => Some(IncidentEventKind::Acknowledged),
+            "resolved" => Some(IncidentEventKind::Resolved),
+            "comment" => Some(IncidentEventKind::Comment),
+            _ => None,
+        }
+    }
+}
+
+/// One entry of an incident timeline.
+pub struct IncidentEvent {
+    pub id: i64,
+    pub incident_id: i64,
+    pub kind: IncidentEventKind,
+    pub message: String,
+    pub created_at: String,
+}
+
+/// What a notification is about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    UrlDown,
+    UrlUp,
+    /// A monitor started or stopped flapping.
+    UrlFlapping,
+    AlertFiring,
+    AlertResolved,
+    /// The certificate of a monitor entered or left the expiry warning period.
+    CertificateExpiring,
+    /// Sent on demand to check a channel's settings.
+    Test,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 7] = [
+        EventKind::UrlDown,
+        EventKind::UrlUp,
+        EventKind::UrlFlapping,
+        EventKind::AlertFiring,
+        EventKind::AlertResolved,
+        EventKind::CertificateExpiring,
+        EventKind::Test,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::UrlDown => "url_down",
+            EventKind::UrlUp => "url_up",
+            EventKind::UrlFlapping => "url_flapping",
+            EventKind::AlertFiring => "alert_firing",
+            EventKind::AlertResolved => "alert_resolved",
+            EventKind::CertificateExpiring => "certificate_expiring",
+            EventKind::Test => "test",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<EventKind> {
+        EventKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == value)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WebhookConfig {
+    pub url: String,
+    pub method: String,
+    pub headers: BTreeMap<String, String>,
+    /// JSON sent as the body, with `{{field}}` placeholders replaced by the event fields.
+    /// The event itself is sent when there is no template.
+    pub body_template: Option<serde_json::Value>,
+}
+
+/// How the connection to the SMTP server is secured.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SmtpSecurity {
+    /// Plaintext, meant for relays on a trusted network or local test servers.
+    None,
+    /// Plaintext connection upgraded with STARTTLS, which the server must support.
+    StartTls,
+    /// TLS from the start of the connection (SMTPS).
+    Tls,
+}
+
+impl SmtpSecurity {
+    pub const ALL: [SmtpSecurity; 3] = [
+        SmtpSecurity::None,
+        SmtpSecurity::StartTls,
+        SmtpSecurity::Tls,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SmtpSecurity::None => "none",
+            SmtpSecurity::StartTls => "starttls",
+            SmtpSecurity::Tls => "tls",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<SmtpSecurity> {
+        SmtpSecurity::ALL
+            .into_iter()
+            .find(|security| security.as_str() == value)
+    }
+
+    /// The usual port of each mode.
+    pub fn default_port(&self) -> u16 {
+        match self {
+            SmtpSecurity::None => 25,
+            SmtpSecurity::StartTls => 587,
+            SmtpSecurity::Tls => 465,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EmailConfig {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    /// Credentials for SMTP AUTH, skipped when there is no username.
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+/// Incoming webhook of a chat service (Slack, Discord, Teams), the channel type picks the
+/// message format.
+#[derive(Clone, Debug)]
+pub struct ChatWebhookConfig {
+    pub url: String,
+}
+
+pub const TELEGRAM_API_URL: &str = "https://api.telegram.org";
+
+#[derive(Clone, Debug)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    /// Numeric id or `@channel` username.
+    pub chat_id: String,
+    /// Bot API base URL, `TELEGRAM_API_URL` unless it is a self-hosted Bot API server.
+    pub api_url: String,
+}
+
+/// Where and how a notification channel delivers.
+#[derive(Clone, Debug)]
+pub enum ChannelConfig {
+    Webhook(WebhookConfig),
+    Email(EmailConfig),
+    Slack(ChatWebhookConfig),
+    Discord(ChatWebhookConfig),
+    Teams(ChatWebhookConfig),
+    Telegram(TelegramConfig),
+}
+
+impl ChannelConfig {
+    pub const KINDS: [&'static str; 6] =
+        ["webhook", "email", "slack", "discord", "teams", "telegram"];
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ChannelConfig::Webhook(_) => "webhook",
+            ChannelConfig::Email(_) => "email",
+            ChannelConfig::Slack(_) => "slack",
+            ChannelConfig::Discord(_) => "discord",
+            ChannelConfig::Teams(_) => "teams",
+            ChannelConfig::Telegram(_) => "telegram",
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            ChannelConfig::Webhook(webhook) => serde_json::json!({
+                "url": webhook.url,
+                "method": webhook.method,
+                "headers": webhook.headers,
+                "body_template": webhook.body_template,
+            }),
+            ChannelConfig::Email(email) => serde_json::json!({
+                "host": email.host,
+                "port": email.port,
+                "security": email.security.as_str(),
+                "username": email.username,
+                "password": email.password,
+                "from": email.from,
+                "to": email.to,
+            }),
+            ChannelConfig::Slack(chat)
+            | ChannelConfig::Discord(chat)
+            | ChannelConfig::Teams(chat) => {
+                serde_json::json!({ "url": chat.url })
+            }
+            ChannelConfig::Telegram(telegram) => serde_json::json!({
+                "bot_token": telegram.bot_token,
+                "chat_id": telegram.chat_id,
+                "api_url": telegram.api_url,
+            }),
+        }
+    }
+
+    /// Reads the format written by `to_json` for a channel of type `kind`. Only the shape is
+    /// checked here, the API validates the values before saving.
+    pub fn from_json(kind: &str, json: &serde_json::Value) -> Result<ChannelConfig, String> {
+        let field = |name: &str| {
+            json.get(name)
+                .and_then(|value| value.as_str())
+                .map(str::to_string)
+                .ok_or(format!("Configuração sem o campo '{}'", name))
+        };
+        match kind {
+            "webhook" => Ok(ChannelConfig::Webhook(WebhookConfig {
+                url: field("url")?,
+                method: field("method").unwrap_or("POST".into()),
+                headers: match json.get("headers") {
+                    None | Some(serde_json::Value::Null) => BTreeMap::new(),
+                    Some(headers) => serde_json::from_value(headers.clone())
+                        .map_err(|_| "Campo 'headers' deve ser um objeto de strings")?,
+                },
+                body_template: json
+                    .get("body_template")
+                    .filter(|template| !template.is_null())
+                    .cloned(),
+            })),
+            "email" => {
+                let security = match json.get("security") {
+                    None | Some(serde_json::Value::Null) => SmtpSecurity::StartTls,
+                    Some(security) => {
+                        security
+                            .as_str()
+                            .and_then(SmtpSecurity::parse)
+                            .ok_or(format!(
+                                "Campo 'security' deve ser um de {}",
+                                SmtpSecurity::ALL
+                                    .iter()
+                                    .map(SmtpSecurity::as_str)
+                                    .collect::<Vec<&str>>()
+                                    .join(", ")
+                            ))?
+                    }
+                };
+                let port = match json.get("port") {
+                    None | Some(serde_json::Value::Null) => security.default_port(),
+                    Some(port) => port
+                        .as_u64()
+                        .and_then(|port| u16::try_from(port).ok())
+                        .filter(|port| *port > 0)
+                        .ok_or("Campo 'port' deve ser uma porta entre 1 e 65535")?,
+                };
+                let to = match json.get("to") {
+                    Some(serde_json::Value::String(to)) => vec![to.clone()],
+                    Some(to) => serde_json::from_value(to.clone())
+                        .map_err(|_| "Campo 'to' deve ser uma lista de endereços")?,
+                    None => return Err("Configuração sem o campo 'to'".into()),
+                };
+                Ok(ChannelConfig::Email(EmailConfig {
+                    host: field("host")?,
+                    port,
+                    security,
+                    username: field("username").ok(),
+                    password: field("password").ok(),
+                    from: field("from")?,
+                    to,
+                }))
+            }
+            "slack" | "discord" | "teams" => {
+                let chat = ChatWebhookConfig { url: field("url")? };
+                Ok(match kind {
+                    "slack" => ChannelConfig::Slack(chat),
+                    "discord" => ChannelConfig::Discord(chat),
+                    _ => ChannelConfig::Teams(chat),
+                })
+            }
+            "telegram" => Ok(ChannelConfig::Telegram(TelegramConfig {
+                bot_token: field("bot_token")?,
+                chat_id: match json.get("chat_id") {
+                    Some(serde_json::Value::Number(chat_id)) => chat_id.to_string(),
+                    _ => field("chat_id")?,
+                },
+                api_url: field("api_url").unwrap_or(TELEGRAM_API_URL.into()),
+            })),
+            _ => Err(format!("Tipo de canal inválido: {}", kind)),
+        }
+    }
+}
+
+pub struct NotificationChannel {
+    pub id: i64,
+    pub name: String,
+    pub config: ChannelConfig,
+    /// Events delivered to the channel, every event when empty.
+    pub events: Vec<EventKind>,
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+impl NotificationChannel {
+    pub fn accepts(&self, kind: EventKind) -> bool {
+        self.enabled && (self.events.is_empty() || self.events.contains(&kind))
+    }
+}
+
+/// Outcome of one attempt at delivering a notification.
+#[derive(Clone)]
+pub struct DeliveryResult {
+    pub event: EventKind,
+    pub subject: String,
+    /// Starts at 1, retries of the same notification count up.
+    pub attempt: u32,
+    pub success: bool,
+    /// Status of the channel's HTTP response or SMTP reply, when there is one.
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+/// A stored delivery attempt.
+pub struct NotificationDelivery {
+    pub id: i64,
+    pub channel_id: i64,
+    pub result: DeliveryResult,
+    pub created_at: String,
+}
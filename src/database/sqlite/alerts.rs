@@ -0,0 +1,402 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDateTime;
+use rusqlite::{types::Type, Connection, OptionalExtension, Row};
+
+use crate::database::{
+    models::{
+        Alert, AlertCondition, AlertMatcher, AlertRule, AlertSource, AlertState, Comparison,
+        Metric, Silence,
+    },
+    sqlite::{escalations, incidents},
+    time::format_timestamp,
+};
+
+const RULE_COLUMNS: &str = "id, name, metric, comparison, threshold, duration, state, state_changed_at, value, created_at, labels";
+const ALERT_COLUMNS: &str =
+    "id, rule_id, url_id, name, labels, started_at, resolved_at, acknowledged_at, ack_comment";
+const SILENCE_COLUMNS: &str = "id, rule_id, url_id, labels, comment, expires_at, created_at";
+
+fn parsed<T>(value: String, column: usize, parse: fn(&str) -> Option<T>) -> rusqlite::Result<T> {
+    parse(&value).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(
+            column,
+            Type::Text,
+            format!("Unknown value {}", value).into(),
+        )
+    })
+}
+
+pub fn labels_from_sql(value: String, column: usize) -> rusqlite::Result<BTreeMap<String, String>> {
+    serde_json::from_str(&value)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(column, Type::Text, e.into()))
+}
+
+pub fn labels_to_sql(labels: &BTreeMap<String, String>) -> String {
+    serde_json::to_string(labels).unwrap()
+}
+
+fn rule_from_row(row: &Row) -> rusqlite::Result<AlertRule> {
+    Ok(AlertRule {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        condition: AlertCondition {
+            metric: parsed(row.get(2)?, 2, Metric::parse)?,
+            comparison: parsed(row.get(3)?, 3, Comparison::parse)?,
+            threshold: row.get(4)?,
+            duration: row.get(5)?,
+        },
+        labels: labels_from_sql(row.get(10)?, 10)?,
+        state: parsed(row.get(6)?, 6, AlertState::parse)?,
+        state_changed_at: row.get(7)?,
+        value: row.get(8)?,
+        created_at: row.get(9)?,
+    })
+}
+
+pub fn get_alert_rules(sqlite: &Connection) -> rusqlite::Result<Vec<AlertRule>> {
+    let mut stmt = sqlite.prepare(&format!(
+        "SELECT {} FROM alert_rules ORDER BY id",
+        RULE_COLUMNS
+    ))?;
+    let rule_iter = stmt.query_map([], rule_from_row)?;
+
+    let mut rules = Vec::new();
+    for rule in rule_iter {
+        rules.push(rule?);
+    }
+    Ok(rules)
+}
+
+pub fn get_alert_rule(sqlite: &Connection, id: i64) -> rusqlite::Result<Option<AlertRule>> {
+    sqlite
+        .query_row(
+            &format!("SELECT {} FROM alert_rules WHERE id = ?1", RULE_COLUMNS),
+            (id,),
+            rule_from_row,
+        )
+        .optional()
+}
+
+pub fn create_alert_rule(
+    sqlite: &Connection,
+    name: &str,
+    condition: &AlertCondition,
+    labels: &BTreeMap<String, String>,
+) -> rusqlite::Result<AlertRule> {
+    sqlite.query_row(
+        &format!(
+            "INSERT INTO alert_rules (name, metric, comparison, threshold, duration, labels)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6) RETURNING {}",
+            RULE_COLUMNS
+        ),
+        (
+            name,
+            condition.metric.as_str(),
+            condition.comparison.as_str(),
+            condition.threshold,
+            condition.duration,
+            labels_to_sql(labels),
+        ),
+        rule_from_row,
+    )
+}
+
+/// A new condition starts over from `inactive`, its previous state and active alert no
+/// longer apply.
+pub fn update_alert_rule(
+    sqlite: &Connection,
+    id: i64,
+    name: &str,
+    condition: &AlertCondition,
+    labels: &BTreeMap<String, String>,
+) -> rusqlite::Result<Option<AlertRule>> {
+    let changed = get_alert_rule(sqlite, id)?.is_some_and(|rule| rule.condition != *condition);
+    if changed {
+        resolve_alert(sqlite, AlertSource::Rule(id), "Condição da regra alterada")?;
+    }
+    sqlite
+        .query_row(
+            &format!(
+                "UPDATE alert_rules SET name = ?2,
+                 state = CASE WHEN metric = ?3 AND comparison = ?4 AND threshold = ?5 AND duration = ?6 THEN state ELSE 'inactive' END,
+                 state_changed_at = CASE WHEN metric = ?3 AND comparison = ?4 AND threshold = ?5 AND duration = ?6 THEN state_changed_at END,
+                 metric = ?3, comparison = ?4, threshold = ?5, duration = ?6, labels = ?7
+                 WHERE id = ?1 RETURNING {}",
+                RULE_COLUMNS
+            ),
+            (
+                id,
+                name,
+                condition.metric.as_str(),
+                condition.comparison.as_str(),
+                condition.threshold,
+                condition.duration,
+                labels_to_sql(labels),
+            ),
+            rule_from_row,
+        )
+        .optional()
+}
+
+pub fn delete_alert_rule(sqlite: &Connection, id: i64) -> rusqlite::Result<bool> {
+    resolve_alert(sqlite, AlertSource::Rule(id), "Regra removida")?;
+    forget_rule(sqlite, id)?;
+    escalations::forget_rule(sqlite, id)?;
+    let deleted = sqlite.execute("DELETE FROM alert_rules WHERE id = ?1", (id,))?;
+    Ok(deleted > 0)
+}
+
+pub fn save_alert_state(
+    sqlite: &Connection,
+    id: i64,
+    state: AlertState,
+    value: f64,
+) -> rusqlite::Result<()> {
+    sqlite.execute(
+        "UPDATE alert_rules SET value = ?3,
+         state_changed_at = CASE WHEN state = ?2 AND state_changed_at IS NOT NULL THEN state_changed_at ELSE CURRENT_TIMESTAMP END,
+         state = ?2
+         WHERE id = ?1",
+        (id, state.as_str(), value),
+    )?;
+    Ok(())
+}
+
+/// The source from the `rule_id` and `url_id` columns, `column` being the first of them.
+pub fn source_from_sql(
+    rule_id: Option<i64>,
+    url_id: Option<i32>,
+    column: usize,
+) -> rusqlite::Result<AlertSource> {
+    match (rule_id, url_id) {
+        (Some(rule_id), _) => Ok(AlertSource::Rule(rule_id)),
+        (None, Some(url_id)) => Ok(AlertSource::Monitor(url_id)),
+        (None, None) => Err(rusqlite::Error::FromSqlConversionFailure(
+            column,
+            Type::Null,
+            "Alert without a rule or monitor".into(),
+        )),
+    }
+}
+
+fn alert_from_row(row: &Row) -> rusqlite::Result<Alert> {
+    Ok(Alert {
+        id: row.get(0)?,
+        source: source_from_sql(row.get(1)?, row.get(2)?, 1)?,
+        name: row.get(3)?,
+        labels: labels_from_sql(row.get(4)?, 4)?,
+        started_at: row.get(5)?,
+        resolved_at: row.get(6)?,
+        acknowledged_at: row.get(7)?,
+        ack_comment: row.get(8)?,
+    })
+}
+
+/// `None` when the source already has an active alert. The incident of the alert is opened
+/// along with it, `detail` telling what was detected.
+pub fn open_alert(
+    sqlite: &Connection,
+    source: AlertSource,
+    name: &str,
+    labels: &BTreeMap<String, String>,
+    detail: &str,
+) -> rusqlite::Result<Option<Alert>> {
+    let active = sqlite
+        .query_row(
+            "SELECT id FROM alerts WHERE rule_id IS ?1 AND url_id IS ?2 AND resolved_at IS NULL",
+            (source.rule_id(), source.url_id()),
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()?;
+    if active.is_some() {
+        return Ok(None);
+    }
+    let alert = sqlite.query_row(
+        &format!(
+            "INSERT INTO alerts (rule_id, url_id, name, labels) VALUES (?1, ?2, ?3, ?4)
+             RETURNING {}",
+            ALERT_COLUMNS
+        ),
+        (
+            source.rule_id(),
+            source.url_id(),
+            name,
+            labels_to_sql(labels),
+        ),
+        alert_from_row,
+    )?;
+    incidents::open_incident(sqlite, &alert, detail)?;
+    Ok(Some(alert))
+}
+
+/// Resolves the active alert of the source and its incident, if it has one. `detail` tells
+/// why on the incident timeline.
+pub fn resolve_alert(
+    sqlite: &Connection,
+    source: AlertSource,
+    detail: &str,
+) -> rusqlite::Result<Option<Alert>> {
+    let alert = sqlite
+        .query_row(
+            &format!(
+                "UPDATE alerts SET resolved_at = CURRENT_TIMESTAMP
+                 WHERE rule_id IS ?1 AND url_id IS ?2 AND resolved_at IS NULL RETURNING {}",
+                ALERT_COLUMNS
+            ),
+            (source.rule_id(), source.url_id()),
+            alert_from_row,
+        )
+        .optional()?;
+    if let Some(alert) = &alert {
+        incidents::resolve_incident(sqlite, alert, detail)?;
+    }
+    Ok(alert)
+}
+
+pub fn get_alerts(
+    sqlite: &Connection,
+    active: Option<bool>,
+    limit: u32,
+) -> rusqlite::Result<Vec<Alert>> {
+    let mut stmt = sqlite.prepare(&format!(
+        "SELECT {} FROM alerts WHERE ?1 IS NULL OR (resolved_at IS NULL) = ?1
+         ORDER BY started_at DESC, id DESC LIMIT ?2",
+        ALERT_COLUMNS
+    ))?;
+    let alert_iter = stmt.query_map((active, limit), alert_from_row)?;
+
+    let mut alerts = Vec::new();
+    for alert in alert_iter {
+        alerts.push(alert?);
+    }
+    Ok(alerts)
+}
+
+pub fn get_alert(sqlite: &Connection, id: i64) -> rusqlite::Result<Option<Alert>> {
+    sqlite
+        .query_row(
+            &format!("SELECT {} FROM alerts WHERE id = ?1", ALERT_COLUMNS),
+            (id,),
+            alert_from_row,
+        )
+        .optional()
+}
+
+pub fn acknowledge_alert(
+    sqlite: &Connection,
+    id: i64,
+    comment: Option<&str>,
+) -> rusqlite::Result<Option<Alert>> {
+    let alert = sqlite
+        .query_row(
+            &format!(
+                "UPDATE alerts SET acknowledged_at = CURRENT_TIMESTAMP, ack_comment = ?2
+                 WHERE id = ?1 AND acknowledged_at IS NULL AND resolved_at IS NULL
+                 RETURNING {}",
+                ALERT_COLUMNS
+            ),
+            (id, comment),
+            alert_from_row,
+        )
+        .optional()?;
+    if let Some(alert) = &alert {
+        incidents::acknowledge_incident(sqlite, alert)?;
+    }
+    Ok(alert)
+}
+
+/// Drops alerts resolved before `before`, along with what is left of their escalations, and
+/// silences that expired before it.
+pub fn flush_alerts(sqlite: &Connection, before: &NaiveDateTime) -> rusqlite::Result<()> {
+    let before = format_timestamp(before);
+    sqlite.execute("DELETE FROM alerts WHERE resolved_at < ?1", (&before,))?;
+    sqlite.execute(
+        "DELETE FROM alert_escalations WHERE alert_id NOT IN (SELECT id FROM alerts)",
+        (),
+    )?;
+    sqlite.execute("DELETE FROM silences WHERE expires_at < ?1", (&before,))?;
+    Ok(())
+}
+
+fn silence_from_row(row: &Row) -> rusqlite::Result<Silence> {
+    Ok(Silence {
+        id: row.get(0)?,
+        matcher: AlertMatcher {
+            rule_id: row.get(1)?,
+            url_id: row.get(2)?,
+            labels: labels_from_sql(row.get(3)?, 3)?,
+        },
+        comment: row.get(4)?,
+        expires_at: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+/// Silences still in effect at `at`, or every silence when `at` is `None`.
+pub fn get_silences(
+    sqlite: &Connection,
+    at: Option<&NaiveDateTime>,
+) -> rusqlite::Result<Vec<Silence>> {
+    let mut stmt = sqlite.prepare(&format!(
+        "SELECT {} FROM silences WHERE ?1 IS NULL OR expires_at > ?1 ORDER BY id",
+        SILENCE_COLUMNS
+    ))?;
+    let silence_iter = stmt.query_map((at.map(format_timestamp),), silence_from_row)?;
+
+    let mut silences = Vec::new();
+    for silence in silence_iter {
+        silences.push(silence?);
+    }
+    Ok(silences)
+}
+
+pub fn get_silence(sqlite: &Connection, id: i64) -> rusqlite::Result<Option<Silence>> {
+    sqlite
+        .query_row(
+            &format!("SELECT {} FROM silences WHERE id = ?1", SILENCE_COLUMNS),
+            (id,),
+            silence_from_row,
+        )
+        .optional()
+}
+
+pub fn create_silence(
+    sqlite: &Connection,
+    matcher: &AlertMatcher,
+    comment: &str,
+    expires_at: &NaiveDateTime,
+) -> rusqlite::Result<Silence> {
+    sqlite.query_row(
+        &format!(
+            "INSERT INTO silences (rule_id, url_id, labels, comment, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5) RETURNING {}",
+            SILENCE_COLUMNS
+        ),
+        (
+            matcher.rule_id,
+            matcher.url_id,
+            labels_to_sql(&matcher.labels),
+            comment,
+            format_timestamp(expires_at),
+        ),
+        silence_from_row,
+    )
+}
+
+pub fn delete_silence(sqlite: &Connection, id: i64) -> rusqlite::Result<bool> {
+    let deleted = sqlite.execute("DELETE FROM silences WHERE id = ?1", (id,))?;
+    Ok(deleted > 0)
+}
+
+/// Drops the silences of a deleted monitor, a later monitor reusing its id would be muted.
+pub fn forget_url(sqlite: &Connection, url_id: i32) -> rusqlite::Result<()> {
+    sqlite.execute("DELETE FROM silences WHERE url_id = ?1", (url_id,))?;
+    Ok(())
+}
+
+/// Drops the silences of a deleted rule, a later rule reusing its id would be muted.
+pub fn forget_rule(sqlite: &Connection, rule_id: i64) -> rusqlite::Result<()> {
+    sqlite.execute("DELETE FROM silences WHERE rule_id = ?1", (rule_id,))?;
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use chrono::NaiveDateTime;
+use rusqlite::Connection;
+
+use crate::database::{
+    models::{CheckOutcome, CheckResult, MonitorStatus, UrlCheck},
+    time::format_timestamp,
+};
+
+/// Records a probe and, unless it was taken during maintenance, stores the resulting status
+/// of the monitor. The status code and outcome are only mirrored when the probe agrees with
+/// that status.
+pub fn save_check_result(
+    sqlite: &Connection,
+    url_id: i32,
+    result: &CheckResult,
+    status: &MonitorStatus,
+) -> rusqlite::Result<()> {
+    sqlite.execute(
+        "INSERT INTO url_checks (url_id, status_code, up, outcome, response_time_ms, response_size, error, failed_assertion, maintenance) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        (
+            url_id,
+            result.status_code,
+            result.up,
+            result.outcome.as_str(),
+            result.response_time_ms,
+            result.response_size,
+            &result.error,
+            &result.failed_assertion,
+            result.maintenance,
+        ),
+    )?;
+    if result.maintenance {
+        return Ok(());
+    }
+    sqlite.execute(
+        "UPDATE urls SET status_code = CASE WHEN ?6 THEN ?1 ELSE status_code END, is_up = ?2,
+         outcome = CASE WHEN ?6 THEN ?3 ELSE outcome END, unconfirmed_checks = ?4, flapping = ?5
+         WHERE id = ?7",
+        (
+            result.status_code,
+            status.up,
+            result.outcome.as_str(),
+            status.unconfirmed_checks,
+            status.flapping,
+            status.up == Some(result.up),
+            url_id,
+        ),
+    )?;
+    Ok(())
+}
+
+/// Newest checks first, optionally limited to `[from, to]`.
+pub fn get_url_checks(
+    sqlite: &Connection,
+    url_id: i32,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+    limit: u32,
+) -> rusqlite::Result<Vec<UrlCheck>> {
+    let mut stmt = sqlite.prepare(
+        "SELECT id, url_id, checked_at, status_code, up, response_time_ms, response_size, error, outcome, failed_assertion, maintenance FROM url_checks
+         WHERE url_id = ?1 AND (?2 IS NULL OR checked_at >= ?2) AND (?3 IS NULL OR checked_at <= ?3)
+         ORDER BY checked_at DESC, id DESC LIMIT ?4",
+    )?;
+    let check_iter = stmt.query_map(
+        (
+            url_id,
+            from.as_ref().map(format_timestamp),
+            to.as_ref().map(format_timestamp),
+            limit,
+        ),
+        |row| {
+            Ok(UrlCheck {
+                id: row.get(0)?,
+                url_id: row.get(1)?,
+                checked_at: row.get(2)?,
+                result: CheckResult {
+                    status_code: row.get(3)?,
+                    up: row.get(4)?,
+                    response_time_ms: row.get(5)?,
+                    response_size: row.get(6)?,
+                    error: row.get(7)?,
+                    outcome: outcome_from_sql(row.get::<_, String>(8)?, 8)?,
+                    failed_assertion: row.get(9)?,
+                    maintenance: row.get(10)?,
+                },
+            })
+        },
+    )?;
+
+    let mut checks = Vec::new();
+    for check in check_iter {
+        checks.push(check?);
+    }
+    Ok(checks)
+}
+
+pub fn flush_url_checks(sqlite: &Connection, before: &NaiveDateTime) -> rusqlite::Result<()> {
+    sqlite.execute(
+        "DELETE FROM url_checks WHERE checked_at < ?1",
+        (format_timestamp(before),),
+    )?;
+    Ok(())
+}
+
+pub fn outcome_from_sql(value: String, column: usize) -> rusqlite::Result<CheckOutcome> {
+    CheckOutcome::parse(&value).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(
+            column,
+            rusqlite::types::Type::Text,
+            format!("Unknown check outcome {}", value).into(),
+        )
+    })
+}
@@ -1,6 +1,84 @@
-use rusqlite::Connection;
+use std::{env, path::Path, time::Duration};
 
-pub async fn connection_database() -> Result<Connection, rusqlite::Error> {
-    let conn = Connection::open("my_database.db")?;
+use rusqlite::{Connection, OpenFlags};
+
+pub const MEMORY_PATH: &str = ":memory:";
+
+/// SQLite settings, read from the environment (see `.env.exemple`).
+#[derive(Clone, Debug)]
+pub struct DatabaseConfig {
+    /// File path of the database, or `:memory:` for an ephemeral database.
+    pub path: String,
+    pub journal_mode: String,
+    pub synchronous: String,
+    pub busy_timeout: Duration,
+    /// Number of read-only connections kept open next to the writer.
+    pub readers: usize,
+}
+
+impl DatabaseConfig {
+    pub fn from_env() -> Result<DatabaseConfig, Box<dyn std::error::Error>> {
+        let busy_timeout = env::var("DATABASE_BUSY_TIMEOUT_MS")
+            .unwrap_or("5000".into())
+            .parse::<u64>()
+            .map_err(|e| format!("Invalid DATABASE_BUSY_TIMEOUT_MS: {}", e))?;
+        let readers = env::var("DATABASE_READERS")
+            .unwrap_or("4".into())
+            .parse::<usize>()
+            .map_err(|e| format!("Invalid DATABASE_READERS: {}", e))?;
+
+        Ok(DatabaseConfig {
+            path: env::var("DATABASE_PATH").unwrap_or("my_database.db".into()),
+            journal_mode: env::var("DATABASE_JOURNAL_MODE").unwrap_or("WAL".into()),
+            synchronous: env::var("DATABASE_SYNCHRONOUS").unwrap_or("NORMAL".into()),
+            busy_timeout: Duration::from_millis(busy_timeout),
+            readers,
+        })
+    }
+
+    pub fn is_memory(&self) -> bool {
+        self.path == MEMORY_PATH
+    }
+}
+
+pub async fn connection_database(
+    config: &DatabaseConfig,
+) -> Result<Connection, Box<dyn std::error::Error>> {
+    let conn = if config.is_memory() {
+        Connection::open_in_memory()?
+    } else {
+        if let Some(parent) = Path::new(&config.path).parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        let conn = Connection::open(&config.path)?;
+        // journal_mode reports the mode actually in effect, so it is read back rather than set blindly
+        let journal_mode: String =
+            conn.pragma_update_and_check(None, "journal_mode", &config.journal_mode, |row| {
+                row.get(0)
+            })?;
+        if !journal_mode.eq_ignore_ascii_case(&config.journal_mode) {
+            eprintln!(
+                "Requested journal mode {} but database is using {}",
+                config.journal_mode, journal_mode
+            );
+        }
+        conn
+    };
+
+    conn.pragma_update(None, "synchronous", &config.synchronous)?;
+    conn.busy_timeout(config.busy_timeout)?;
+    println!("Using database {}", config.path);
+    Ok(conn)
+}
+
+/// Opens a read-only connection to an existing database file.
+pub fn connection_reader(config: &DatabaseConfig) -> rusqlite::Result<Connection> {
+    let conn = Connection::open_with_flags(
+        &config.path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    conn.busy_timeout(config.busy_timeout)?;
     Ok(conn)
 }
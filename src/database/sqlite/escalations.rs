@@ -0,0 +1,246 @@
+use chrono::NaiveDateTime;
+use rusqlite::{types::Type, Connection, OptionalExtension, Row};
+
+use crate::database::{
+    models::{AlertMatcher, Escalation, EscalationPolicy, EscalationSettings, EscalationStep},
+    sqlite::alerts::{labels_from_sql, labels_to_sql},
+    time::format_timestamp,
+};
+
+const POLICY_COLUMNS: &str = "id, name, rule_id, url_id, labels, steps, created_at";
+const ESCALATION_COLUMNS: &str = "id, alert_id, policy_id, step, due_at, notification";
+
+fn json_from_sql(value: String, column: usize) -> rusqlite::Result<serde_json::Value> {
+    serde_json::from_str(&value)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(column, Type::Text, e.into()))
+}
+
+fn steps_from_sql(value: String, column: usize) -> rusqlite::Result<Vec<EscalationStep>> {
+    let json = json_from_sql(value, column)?;
+    json.as_array()
+        .into_iter()
+        .flatten()
+        .map(|step| {
+            EscalationStep::from_json(step).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(column, Type::Text, e.into())
+            })
+        })
+        .collect()
+}
+
+fn steps_to_sql(steps: &[EscalationStep]) -> String {
+    serde_json::Value::Array(steps.iter().map(EscalationStep::to_json).collect()).to_string()
+}
+
+fn policy_from_row(row: &Row) -> rusqlite::Result<EscalationPolicy> {
+    Ok(EscalationPolicy {
+        id: row.get(0)?,
+        settings: EscalationSettings {
+            name: row.get(1)?,
+            matcher: AlertMatcher {
+                rule_id: row.get(2)?,
+                url_id: row.get(3)?,
+                labels: labels_from_sql(row.get(4)?, 4)?,
+            },
+            steps: steps_from_sql(row.get(5)?, 5)?,
+        },
+        created_at: row.get(6)?,
+    })
+}
+
+pub fn get_escalation_policies(sqlite: &Connection) -> rusqlite::Result<Vec<EscalationPolicy>> {
+    let mut stmt = sqlite.prepare(&format!(
+        "SELECT {} FROM escalation_policies ORDER BY id",
+        POLICY_COLUMNS
+    ))?;
+    let policy_iter = stmt.query_map([], policy_from_row)?;
+
+    let mut policies = Vec::new();
+    for policy in policy_iter {
+        policies.push(policy?);
+    }
+    Ok(policies)
+}
+
+pub fn get_escalation_policy(
+    sqlite: &Connection,
+    id: i64,
+) -> rusqlite::Result<Option<EscalationPolicy>> {
+    sqlite
+        .query_row(
+            &format!(
+                "SELECT {} FROM escalation_policies WHERE id = ?1",
+                POLICY_COLUMNS
+            ),
+            (id,),
+            policy_from_row,
+        )
+        .optional()
+}
+
+pub fn create_escalation_policy(
+    sqlite: &Connection,
+    settings: &EscalationSettings,
+) -> rusqlite::Result<EscalationPolicy> {
+    sqlite.query_row(
+        &format!(
+            "INSERT INTO escalation_policies (name, rule_id, url_id, labels, steps)
+             VALUES (?1, ?2, ?3, ?4, ?5) RETURNING {}",
+            POLICY_COLUMNS
+        ),
+        (
+            &settings.name,
+            settings.matcher.rule_id,
+            settings.matcher.url_id,
+            labels_to_sql(&settings.matcher.labels),
+            steps_to_sql(&settings.steps),
+        ),
+        policy_from_row,
+    )
+}
+
+pub fn update_escalation_policy(
+    sqlite: &Connection,
+    id: i64,
+    settings: &EscalationSettings,
+) -> rusqlite::Result<Option<EscalationPolicy>> {
+    sqlite
+        .query_row(
+            &format!(
+                "UPDATE escalation_policies SET name = ?2, rule_id = ?3, url_id = ?4, labels = ?5,
+                 steps = ?6 WHERE id = ?1 RETURNING {}",
+                POLICY_COLUMNS
+            ),
+            (
+                id,
+                &settings.name,
+                settings.matcher.rule_id,
+                settings.matcher.url_id,
+                labels_to_sql(&settings.matcher.labels),
+                steps_to_sql(&settings.steps),
+            ),
+            policy_from_row,
+        )
+        .optional()
+}
+
+pub fn delete_escalation_policy(sqlite: &Connection, id: i64) -> rusqlite::Result<bool> {
+    sqlite.execute("DELETE FROM alert_escalations WHERE policy_id = ?1", (id,))?;
+    let deleted = sqlite.execute("DELETE FROM escalation_policies WHERE id = ?1", (id,))?;
+    Ok(deleted > 0)
+}
+
+/// Drops the policies of a deleted monitor along with their escalations.
+pub fn forget_url(sqlite: &Connection, url_id: i32) -> rusqlite::Result<()> {
+    sqlite.execute(
+        "DELETE FROM alert_escalations WHERE policy_id IN (SELECT id FROM escalation_policies WHERE url_id = ?1)",
+        (url_id,),
+    )?;
+    sqlite.execute(
+        "DELETE FROM escalation_policies WHERE url_id = ?1",
+        (url_id,),
+    )?;
+    Ok(())
+}
+
+/// Drops the policies of a deleted rule along with their escalations.
+pub fn forget_rule(sqlite: &Connection, rule_id: i64) -> rusqlite::Result<()> {
+    sqlite.execute(
+        "DELETE FROM alert_escalations WHERE policy_id IN (SELECT id FROM escalation_policies WHERE rule_id = ?1)",
+        (rule_id,),
+    )?;
+    sqlite.execute(
+        "DELETE FROM escalation_policies WHERE rule_id = ?1",
+        (rule_id,),
+    )?;
+    Ok(())
+}
+
+/// Takes a deleted channel out of the steps that notify it.
+pub fn forget_channel(sqlite: &Connection, channel_id: i64) -> rusqlite::Result<()> {
+    for mut policy in get_escalation_policies(sqlite)? {
+        let listed = policy
+            .settings
+            .steps
+            .iter()
+            .any(|step| step.channel_ids.contains(&channel_id));
+        if !listed {
+            continue;
+        }
+        for step in policy.settings.steps.iter_mut() {
+            step.channel_ids.retain(|id| *id != channel_id);
+        }
+        update_escalation_policy(sqlite, policy.id, &policy.settings)?;
+    }
+    Ok(())
+}
+
+fn escalation_from_row(row: &Row) -> rusqlite::Result<Escalation> {
+    Ok(Escalation {
+        id: row.get(0)?,
+        alert_id: row.get(1)?,
+        policy_id: row.get(2)?,
+        step: row.get(3)?,
+        due_at: row.get(4)?,
+        notification: json_from_sql(row.get(5)?, 5)?,
+    })
+}
+
+pub fn start_escalation(
+    sqlite: &Connection,
+    alert_id: i64,
+    policy_id: i64,
+    due_at: &NaiveDateTime,
+    notification: &serde_json::Value,
+) -> rusqlite::Result<Escalation> {
+    sqlite.query_row(
+        &format!(
+            "INSERT INTO alert_escalations (alert_id, policy_id, due_at, notification)
+             VALUES (?1, ?2, ?3, ?4) RETURNING {}",
+            ESCALATION_COLUMNS
+        ),
+        (
+            alert_id,
+            policy_id,
+            format_timestamp(due_at),
+            notification.to_string(),
+        ),
+        escalation_from_row,
+    )
+}
+
+pub fn get_escalations(
+    sqlite: &Connection,
+    alert_id: Option<i64>,
+) -> rusqlite::Result<Vec<Escalation>> {
+    let mut stmt = sqlite.prepare(&format!(
+        "SELECT {} FROM alert_escalations WHERE ?1 IS NULL OR alert_id = ?1
+         ORDER BY due_at, id",
+        ESCALATION_COLUMNS
+    ))?;
+    let escalation_iter = stmt.query_map((alert_id,), escalation_from_row)?;
+
+    let mut escalations = Vec::new();
+    for escalation in escalation_iter {
+        escalations.push(escalation?);
+    }
+    Ok(escalations)
+}
+
+pub fn advance_escalation(
+    sqlite: &Connection,
+    id: i64,
+    step: u32,
+    due_at: &NaiveDateTime,
+) -> rusqlite::Result<()> {
+    sqlite.execute(
+        "UPDATE alert_escalations SET step = ?2, due_at = ?3 WHERE id = ?1",
+        (id, step, format_timestamp(due_at)),
+    )?;
+    Ok(())
+}
+
+pub fn delete_escalation(sqlite: &Connection, id: i64) -> rusqlite::Result<bool> {
+    let deleted = sqlite.execute("DELETE FROM alert_escalations WHERE id = ?1", (id,))?;
+    Ok(deleted > 0)
+}
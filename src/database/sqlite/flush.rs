@@ -1,14 +1,12 @@
 use rusqlite::Connection;
-use std::sync::{Arc, Mutex};
 
-pub async fn flush_cpu_info(con: Arc<Mutex<Connection>>) -> Result<(), Box<dyn std::error::Error>> {
-    let con = con.lock();
-    if con.is_err() {
-        return Err("Failed to acquire lock on the database connection".into());
-    }
-    let con = con.unwrap();
+pub fn flush_cpu_info(con: &Connection) -> rusqlite::Result<()> {
     // Flush CPU info
-    let query: &'static str = "DELETE FROM cpu WHERE create_at < datetime('now', '-5 minutes')";
+    let query: &'static str = "DELETE FROM cpu WHERE created_at < datetime('now', '-5 minutes')";
     con.execute(query, ())?;
+    con.execute(
+        "DELETE FROM cpu_core WHERE cpu_id NOT IN (SELECT id FROM cpu)",
+        (),
+    )?;
     Ok(())
 }
@@ -0,0 +1,199 @@
+use chrono::NaiveDateTime;
+use rusqlite::{types::Type, Connection, OptionalExtension, Row};
+
+use crate::database::{
+    models::{Alert, AlertSource, Incident, IncidentEvent, IncidentEventKind},
+    sqlite::alerts::{labels_from_sql, labels_to_sql, source_from_sql},
+    time::format_timestamp,
+};
+
+const INCIDENT_COLUMNS: &str =
+    "id, alert_id, rule_id, url_id, name, labels, started_at, acknowledged_at, resolved_at";
+const EVENT_COLUMNS: &str = "id, incident_id, kind, message, created_at";
+
+fn incident_from_row(row: &Row) -> rusqlite::Result<Incident> {
+    Ok(Incident {
+        id: row.get(0)?,
+        alert_id: row.get(1)?,
+        source: source_from_sql(row.get(2)?, row.get(3)?, 2)?,
+        name: row.get(4)?,
+        labels: labels_from_sql(row.get(5)?, 5)?,
+        started_at: row.get(6)?,
+        acknowledged_at: row.get(7)?,
+        resolved_at: row.get(8)?,
+    })
+}
+
+fn event_from_row(row: &Row) -> rusqlite::Result<IncidentEvent> {
+    let kind: String = row.get(2)?;
+    Ok(IncidentEvent {
+        id: row.get(0)?,
+        incident_id: row.get(1)?,
+        kind: IncidentEventKind::parse(&kind).ok_or_else(|| {
+            rusqlite::Error::FromSqlConversionFailure(
+                2,
+                Type::Text,
+                format!("Unknown incident event {}", kind).into(),
+            )
+        })?,
+        message: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
+
+pub fn open_incident(sqlite: &Connection, alert: &Alert, detail: &str) -> rusqlite::Result<()> {
+    let id: i64 = sqlite.query_row(
+        "INSERT INTO incidents (alert_id, rule_id, url_id, name, labels, started_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6) RETURNING id",
+        (
+            alert.id,
+            alert.source.rule_id(),
+            alert.source.url_id(),
+            &alert.name,
+            labels_to_sql(&alert.labels),
+            &alert.started_at,
+        ),
+        |row| row.get(0),
+    )?;
+    add_incident_event(sqlite, id, IncidentEventKind::Detected, detail)?;
+    Ok(())
+}
+
+pub fn acknowledge_incident(sqlite: &Connection, alert: &Alert) -> rusqlite::Result<()> {
+    let Some(incident) = get_alert_incident(sqlite, alert.id)? else {
+        return Ok(());
+    };
+    sqlite.execute(
+        "UPDATE incidents SET acknowledged_at = ?2 WHERE id = ?1",
+        (incident.id, &alert.acknowledged_at),
+    )?;
+    let message = alert.ack_comment.as_deref().unwrap_or("Alerta reconhecido");
+    add_incident_event(
+        sqlite,
+        incident.id,
+        IncidentEventKind::Acknowledged,
+        message,
+    )?;
+    Ok(())
+}
+
+pub fn resolve_incident(sqlite: &Connection, alert: &Alert, detail: &str) -> rusqlite::Result<()> {
+    let Some(incident) = get_alert_incident(sqlite, alert.id)? else {
+        return Ok(());
+    };
+    sqlite.execute(
+        "UPDATE incidents SET resolved_at = ?2 WHERE id = ?1",
+        (incident.id, &alert.resolved_at),
+    )?;
+    add_incident_event(sqlite, incident.id, IncidentEventKind::Resolved, detail)?;
+    Ok(())
+}
+
+/// Newest first, only the open or resolved ones when `open` is given and the ones of a rule
+/// or monitor when `source` is.
+pub fn get_incidents(
+    sqlite: &Connection,
+    open: Option<bool>,
+    source: Option<AlertSource>,
+    limit: u32,
+) -> rusqlite::Result<Vec<Incident>> {
+    let mut stmt = sqlite.prepare(&format!(
+        "SELECT {} FROM incidents WHERE (?1 IS NULL OR (resolved_at IS NULL) = ?1)
+         AND (?2 IS NULL OR rule_id = ?2) AND (?3 IS NULL OR url_id = ?3)
+         ORDER BY started_at DESC, id DESC LIMIT ?4",
+        INCIDENT_COLUMNS
+    ))?;
+    let incident_iter = stmt.query_map(
+        (
+            open,
+            source.and_then(|source| source.rule_id()),
+            source.and_then(|source| source.url_id()),
+            limit,
+        ),
+        incident_from_row,
+    )?;
+
+    let mut incidents = Vec::new();
+    for incident in incident_iter {
+        incidents.push(incident?);
+    }
+    Ok(incidents)
+}
+
+pub fn get_incident(sqlite: &Connection, id: i64) -> rusqlite::Result<Option<Incident>> {
+    sqlite
+        .query_row(
+            &format!("SELECT {} FROM incidents WHERE id = ?1", INCIDENT_COLUMNS),
+            (id,),
+            incident_from_row,
+        )
+        .optional()
+}
+
+/// Alert ids can be taken again once the alerts are flushed, the latest incident is the one
+/// of the current alert.
+pub fn get_alert_incident(
+    sqlite: &Connection,
+    alert_id: i64,
+) -> rusqlite::Result<Option<Incident>> {
+    sqlite
+        .query_row(
+            &format!(
+                "SELECT {} FROM incidents WHERE alert_id = ?1 ORDER BY id DESC LIMIT 1",
+                INCIDENT_COLUMNS
+            ),
+            (alert_id,),
+            incident_from_row,
+        )
+        .optional()
+}
+
+/// Oldest first.
+pub fn get_incident_events(
+    sqlite: &Connection,
+    incident_id: i64,
+) -> rusqlite::Result<Vec<IncidentEvent>> {
+    let mut stmt = sqlite.prepare(&format!(
+        "SELECT {} FROM incident_events WHERE incident_id = ?1 ORDER BY created_at, id",
+        EVENT_COLUMNS
+    ))?;
+    let event_iter = stmt.query_map((incident_id,), event_from_row)?;
+
+    let mut events = Vec::new();
+    for event in event_iter {
+        events.push(event?);
+    }
+    Ok(events)
+}
+
+/// `None` when no incident has this id.
+pub fn add_incident_event(
+    sqlite: &Connection,
+    incident_id: i64,
+    kind: IncidentEventKind,
+    message: &str,
+) -> rusqlite::Result<Option<IncidentEvent>> {
+    sqlite
+        .query_row(
+            &format!(
+                "INSERT INTO incident_events (incident_id, kind, message)
+                 SELECT id, ?2, ?3 FROM incidents WHERE id = ?1 RETURNING {}",
+                EVENT_COLUMNS
+            ),
+            (incident_id, kind.as_str(), message),
+            event_from_row,
+        )
+        .optional()
+}
+
+/// Drops incidents resolved before `before` along with their timelines.
+pub fn flush_incidents(sqlite: &Connection, before: &NaiveDateTime) -> rusqlite::Result<()> {
+    let before = format_timestamp(before);
+    sqlite.execute(
+        "DELETE FROM incident_events
+         WHERE incident_id IN (SELECT id FROM incidents WHERE resolved_at < ?1)",
+        (&before,),
+    )?;
+    sqlite.execute("DELETE FROM incidents WHERE resolved_at < ?1", (&before,))?;
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use rusqlite::{types::Type, Connection, OptionalExtension, Row};
+
+use crate::database::models::{
+    MaintenanceMode, MaintenanceSchedule, MaintenanceSettings, MaintenanceWindow,
+};
+
+const WINDOW_COLUMNS: &str =
+    "id, name, mode, starts_at, ends_at, cron, duration, url_ids, hosts, created_at";
+
+fn conversion_error(column: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(column, Type::Text, message.into())
+}
+
+fn url_ids_from_sql(value: String, column: usize) -> rusqlite::Result<Vec<i32>> {
+    serde_json::from_str(&value).map_err(|e| conversion_error(column, e.to_string()))
+}
+
+fn hosts_from_sql(value: String, column: usize) -> rusqlite::Result<Vec<String>> {
+    serde_json::from_str(&value).map_err(|e| conversion_error(column, e.to_string()))
+}
+
+fn window_from_row(row: &Row) -> rusqlite::Result<MaintenanceWindow> {
+    let mode: String = row.get(2)?;
+    let schedule = match (row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?) {
+        (Some(starts_at), Some(ends_at), _, _) => MaintenanceSchedule::Once { starts_at, ends_at },
+        (_, _, Some(cron), Some(duration)) => MaintenanceSchedule::Recurring { cron, duration },
+        _ => return Err(conversion_error(3, "Window without a schedule".to_string())),
+    };
+    Ok(MaintenanceWindow {
+        id: row.get(0)?,
+        settings: MaintenanceSettings {
+            name: row.get(1)?,
+            mode: MaintenanceMode::parse(&mode)
+                .ok_or_else(|| conversion_error(2, format!("Unknown mode {}", mode)))?,
+            schedule,
+            url_ids: url_ids_from_sql(row.get(7)?, 7)?,
+            hosts: hosts_from_sql(row.get(8)?, 8)?,
+        },
+        created_at: row.get(9)?,
+    })
+}
+
+/// The schedule as the (starts_at, ends_at, cron, duration) columns.
+fn schedule_to_sql(
+    schedule: &MaintenanceSchedule,
+) -> (Option<&str>, Option<&str>, Option<&str>, Option<u32>) {
+    match schedule {
+        MaintenanceSchedule::Once { starts_at, ends_at } => {
+            (Some(starts_at), Some(ends_at), None, None)
+        }
+        MaintenanceSchedule::Recurring { cron, duration } => {
+            (None, None, Some(cron), Some(*duration))
+        }
+    }
+}
+
+pub fn get_maintenance_windows(sqlite: &Connection) -> rusqlite::Result<Vec<MaintenanceWindow>> {
+    let mut stmt = sqlite.prepare(&format!(
+        "SELECT {} FROM maintenance_windows ORDER BY id",
+        WINDOW_COLUMNS
+    ))?;
+    let window_iter = stmt.query_map([], window_from_row)?;
+
+    let mut windows = Vec::new();
+    for window in window_iter {
+        windows.push(window?);
+    }
+    Ok(windows)
+}
+
+pub fn get_maintenance_window(
+    sqlite: &Connection,
+    id: i64,
+) -> rusqlite::Result<Option<MaintenanceWindow>> {
+    sqlite
+        .query_row(
+            &format!(
+                "SELECT {} FROM maintenance_windows WHERE id = ?1",
+                WINDOW_COLUMNS
+            ),
+            (id,),
+            window_from_row,
+        )
+        .optional()
+}
+
+pub fn create_maintenance_window(
+    sqlite: &Connection,
+    settings: &MaintenanceSettings,
+) -> rusqlite::Result<MaintenanceWindow> {
+    let (starts_at, ends_at, cron, duration) = schedule_to_sql(&settings.schedule);
+    sqlite.query_row(
+        &format!(
+            "INSERT INTO maintenance_windows (name, mode, starts_at, ends_at, cron, duration, url_ids, hosts)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8) RETURNING {}",
+            WINDOW_COLUMNS
+        ),
+        (
+            &settings.name,
+            settings.mode.as_str(),
+            starts_at,
+            ends_at,
+            cron,
+            duration,
+            serde_json::to_string(&settings.url_ids).unwrap(),
+            serde_json::to_string(&settings.hosts).unwrap(),
+        ),
+        window_from_row,
+    )
+}
+
+pub fn update_maintenance_window(
+    sqlite: &Connection,
+    id: i64,
+    settings: &MaintenanceSettings,
+) -> rusqlite::Result<Option<MaintenanceWindow>> {
+    let (starts_at, ends_at, cron, duration) = schedule_to_sql(&settings.schedule);
+    sqlite
+        .query_row(
+            &format!(
+                "UPDATE maintenance_windows SET name = ?2, mode = ?3, starts_at = ?4, ends_at = ?5,
+                 cron = ?6, duration = ?7, url_ids = ?8, hosts = ?9 WHERE id = ?1 RETURNING {}",
+                WINDOW_COLUMNS
+            ),
+            (
+                id,
+                &settings.name,
+                settings.mode.as_str(),
+                starts_at,
+                ends_at,
+                cron,
+                duration,
+                serde_json::to_string(&settings.url_ids).unwrap(),
+                serde_json::to_string(&settings.hosts).unwrap(),
+            ),
+            window_from_row,
+        )
+        .optional()
+}
+
+pub fn delete_maintenance_window(sqlite: &Connection, id: i64) -> rusqlite::Result<bool> {
+    let deleted = sqlite.execute("DELETE FROM maintenance_windows WHERE id = ?1", (id,))?;
+    Ok(deleted > 0)
+}
+
+/// Takes a deleted monitor out of the windows that list it.
+pub fn forget_url(sqlite: &Connection, url_id: i32) -> rusqlite::Result<()> {
+    sqlite.execute(
+        "UPDATE maintenance_windows SET url_ids = (
+             SELECT json_group_array(value) FROM json_each(url_ids) WHERE value != ?1
+         ) WHERE EXISTS (SELECT 1 FROM json_each(url_ids) WHERE value = ?1)",
+        (url_id,),
+    )?;
+    Ok(())
+}
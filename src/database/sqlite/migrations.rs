@@ -0,0 +1,267 @@
+use rusqlite::Connection;
+
+/// Ordered schema migrations, applied once each and tracked through `PRAGMA user_version`.
+/// Never edit a migration that has shipped; append a new one instead.
+const MIGRATIONS: &[(i32, &str)] = &[
+    (
+        1,
+        // Baseline schema. Uses IF NOT EXISTS so databases created before migrations existed
+        // are adopted as version 1.
+        "CREATE TABLE IF NOT EXISTS memory (id INTEGER PRIMARY KEY, total_memory INTEGER NOT NULL, used_memory INTEGER NOT NULL, free_memory INTEGER NOT NULL);
+         CREATE TABLE IF NOT EXISTS cpu (id INTEGER PRIMARY KEY, total_cpus INTEGER NOT NULL, total_cpu_usage REAL NOT NULL, cores_usage TEXT NOT NULL, create_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP);
+         CREATE TABLE IF NOT EXISTS cpu_core (id INTEGER PRIMARY KEY, cpu_id INTEGER NOT NULL REFERENCES cpu(id) ON DELETE CASCADE, core INTEGER NOT NULL, usage REAL NOT NULL);
+         CREATE INDEX IF NOT EXISTS cpu_core_core_idx ON cpu_core (core, cpu_id);
+         CREATE TABLE IF NOT EXISTS urls (id INTEGER PRIMARY KEY, url TEXT NOT NULL, status_code INTEGER, created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP);",
+    ),
+    (
+        2,
+        // Align cpu with the other tables and drop the Debug-formatted cores column,
+        // cpu_core holds the per-core samples now.
+        "ALTER TABLE cpu RENAME COLUMN create_at TO created_at;
+         ALTER TABLE cpu DROP COLUMN cores_usage;",
+    ),
+    (
+        3,
+        // Per-monitor check settings, headers are a JSON object
+        "ALTER TABLE urls ADD COLUMN check_interval INTEGER NOT NULL DEFAULT 60;
+         ALTER TABLE urls ADD COLUMN check_timeout INTEGER NOT NULL DEFAULT 30;
+         ALTER TABLE urls ADD COLUMN method TEXT NOT NULL DEFAULT 'GET';
+         ALTER TABLE urls ADD COLUMN headers TEXT NOT NULL DEFAULT '{}';
+         ALTER TABLE urls ADD COLUMN body TEXT;
+         ALTER TABLE urls ADD COLUMN follow_redirects INTEGER NOT NULL DEFAULT 1;
+         ALTER TABLE urls ADD COLUMN expected_status TEXT NOT NULL DEFAULT '200-399';
+         ALTER TABLE urls ADD COLUMN is_up INTEGER;",
+    ),
+    (
+        4,
+        // Every probe of a monitored URL
+        "CREATE TABLE url_checks (id INTEGER PRIMARY KEY, url_id INTEGER NOT NULL REFERENCES urls(id) ON DELETE CASCADE, checked_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP, status_code INTEGER, up INTEGER NOT NULL, response_time_ms INTEGER NOT NULL, response_size INTEGER, error TEXT);
+         CREATE INDEX url_checks_url_idx ON url_checks (url_id, checked_at);
+         CREATE INDEX url_checks_checked_at_idx ON url_checks (checked_at);",
+    ),
+    (
+        5,
+        // Failure kind of each probe. Older failed checks cannot be told apart, so they
+        // become http_error when a status was received and connect otherwise. Failed
+        // requests used to be stored as a 404 in urls, which is cleared.
+        "ALTER TABLE url_checks ADD COLUMN outcome TEXT NOT NULL DEFAULT 'ok';
+         UPDATE url_checks SET outcome = CASE WHEN up THEN 'ok' WHEN status_code IS NULL THEN 'connect' ELSE 'http_error' END;
+         ALTER TABLE urls ADD COLUMN outcome TEXT;
+         UPDATE urls SET outcome = (SELECT outcome FROM url_checks WHERE url_checks.url_id = urls.id ORDER BY checked_at DESC, id DESC LIMIT 1);
+         UPDATE urls SET status_code = (SELECT status_code FROM url_checks WHERE url_checks.url_id = urls.id ORDER BY checked_at DESC, id DESC LIMIT 1) WHERE outcome IS NOT NULL;",
+    ),
+    (
+        6,
+        // Response assertions, a JSON array on the monitor and the failing one on each check
+        "ALTER TABLE urls ADD COLUMN assertions TEXT NOT NULL DEFAULT '[]';
+         ALTER TABLE url_checks ADD COLUMN failed_assertion TEXT;",
+    ),
+    (
+        7,
+        // Peer certificate chain of https monitors, a JSON array with the leaf first
+        "ALTER TABLE urls ADD COLUMN certificate TEXT;
+         ALTER TABLE urls ADD COLUMN certificate_checked_at TIMESTAMP;",
+    ),
+    (
+        8,
+        // Monitor protocol, every existing monitor is an HTTP one
+        "ALTER TABLE urls ADD COLUMN monitor_type TEXT NOT NULL DEFAULT 'http';",
+    ),
+    (
+        9,
+        // Duplicate lookups when monitors are created or changed
+        "CREATE INDEX urls_url_idx ON urls (url);",
+    ),
+    (
+        10,
+        // Threshold rules on host metrics and their evaluation state
+        "CREATE TABLE alert_rules (id INTEGER PRIMARY KEY, name TEXT NOT NULL, metric TEXT NOT NULL, comparison TEXT NOT NULL, threshold REAL NOT NULL, duration INTEGER NOT NULL DEFAULT 0, state TEXT NOT NULL DEFAULT 'inactive', state_changed_at TIMESTAMP, value REAL, created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP);",
+    ),
+    (
+        11,
+        // Notification channels, their settings as JSON, and the log of delivery attempts
+        "CREATE TABLE notification_channels (id INTEGER PRIMARY KEY, name TEXT NOT NULL, type TEXT NOT NULL, config TEXT NOT NULL, events TEXT NOT NULL DEFAULT '[]', enabled INTEGER NOT NULL DEFAULT 1, created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP);
+         CREATE TABLE notification_deliveries (id INTEGER PRIMARY KEY, channel_id INTEGER NOT NULL REFERENCES notification_channels(id) ON DELETE CASCADE, event TEXT NOT NULL, subject TEXT NOT NULL, attempt INTEGER NOT NULL, success INTEGER NOT NULL, status_code INTEGER, error TEXT, created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP);
+         CREATE INDEX notification_deliveries_channel_idx ON notification_deliveries (channel_id, created_at);
+         CREATE INDEX notification_deliveries_created_at_idx ON notification_deliveries (created_at);",
+    ),
+    (
+        12,
+        // Labels on monitors and rules, the alerts they raise and the silences muting them
+        "ALTER TABLE urls ADD COLUMN labels TEXT NOT NULL DEFAULT '{}';
+         ALTER TABLE alert_rules ADD COLUMN labels TEXT NOT NULL DEFAULT '{}';
+         CREATE TABLE alerts (id INTEGER PRIMARY KEY, rule_id INTEGER, url_id INTEGER, name TEXT NOT NULL, labels TEXT NOT NULL DEFAULT '{}', started_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, resolved_at TIMESTAMP, acknowledged_at TIMESTAMP, ack_comment TEXT);
+         CREATE INDEX alerts_rule_idx ON alerts (rule_id, resolved_at);
+         CREATE INDEX alerts_url_idx ON alerts (url_id, resolved_at);
+         CREATE TABLE silences (id INTEGER PRIMARY KEY, rule_id INTEGER, url_id INTEGER, labels TEXT NOT NULL DEFAULT '{}', comment TEXT NOT NULL, expires_at TIMESTAMP NOT NULL, created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP);",
+    ),
+    (
+        13,
+        // Maintenance windows, either one-off (starts_at, ends_at) or recurring (cron, duration)
+        "ALTER TABLE url_checks ADD COLUMN maintenance INTEGER NOT NULL DEFAULT 0;
+         CREATE TABLE maintenance_windows (id INTEGER PRIMARY KEY, name TEXT NOT NULL, mode TEXT NOT NULL, starts_at TIMESTAMP, ends_at TIMESTAMP, cron TEXT, duration INTEGER, url_ids TEXT NOT NULL DEFAULT '[]', hosts TEXT NOT NULL DEFAULT '[]', created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP);",
+    ),
+    (
+        14,
+        // Confirmations before a monitor changes status, and flap detection
+        "ALTER TABLE urls ADD COLUMN confirmations_down INTEGER NOT NULL DEFAULT 1;
+         ALTER TABLE urls ADD COLUMN confirmations_up INTEGER NOT NULL DEFAULT 1;
+         ALTER TABLE urls ADD COLUMN retry_interval INTEGER NOT NULL DEFAULT 10;
+         ALTER TABLE urls ADD COLUMN unconfirmed_checks INTEGER NOT NULL DEFAULT 0;
+         ALTER TABLE urls ADD COLUMN flapping INTEGER NOT NULL DEFAULT 0;",
+    ),
+    (
+        15,
+        // Escalation policies and the progress of each one on the active alerts
+        "CREATE TABLE escalation_policies (id INTEGER PRIMARY KEY, name TEXT NOT NULL, rule_id INTEGER, url_id INTEGER, labels TEXT NOT NULL DEFAULT '{}', steps TEXT NOT NULL DEFAULT '[]', created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP);
+         CREATE TABLE alert_escalations (id INTEGER PRIMARY KEY, alert_id INTEGER NOT NULL, policy_id INTEGER NOT NULL, step INTEGER NOT NULL DEFAULT 0, due_at TIMESTAMP NOT NULL, notification TEXT NOT NULL);
+         CREATE INDEX alert_escalations_due_idx ON alert_escalations (due_at);",
+    ),
+    (
+        16,
+        // Heartbeat monitors: grace time, ping token and the state of the pings
+        "ALTER TABLE urls ADD COLUMN grace INTEGER NOT NULL DEFAULT 60;
+         ALTER TABLE urls ADD COLUMN heartbeat_token TEXT;
+         ALTER TABLE urls ADD COLUMN heartbeat_last_ping_at TIMESTAMP;
+         ALTER TABLE urls ADD COLUMN heartbeat_started_at TIMESTAMP;
+         ALTER TABLE urls ADD COLUMN heartbeat_expected_at TIMESTAMP;
+         CREATE UNIQUE INDEX urls_heartbeat_token_idx ON urls (heartbeat_token);",
+    ),
+    (
+        17,
+        // Incidents and their timelines, started from the alerts already recorded
+        "CREATE TABLE incidents (id INTEGER PRIMARY KEY, alert_id INTEGER NOT NULL, rule_id INTEGER, url_id INTEGER, name TEXT NOT NULL, labels TEXT NOT NULL DEFAULT '{}', started_at TIMESTAMP NOT NULL, acknowledged_at TIMESTAMP, resolved_at TIMESTAMP);
+         CREATE INDEX incidents_alert_idx ON incidents (alert_id);
+         CREATE INDEX incidents_started_at_idx ON incidents (started_at);
+         CREATE TABLE incident_events (id INTEGER PRIMARY KEY, incident_id INTEGER NOT NULL, kind TEXT NOT NULL, message TEXT NOT NULL, created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP);
+         CREATE INDEX incident_events_incident_idx ON incident_events (incident_id);
+         INSERT INTO incidents (alert_id, rule_id, url_id, name, labels, started_at, acknowledged_at, resolved_at)
+             SELECT id, rule_id, url_id, name, labels, started_at, acknowledged_at, resolved_at FROM alerts ORDER BY id;
+         INSERT INTO incident_events (incident_id, kind, message, created_at)
+             SELECT id, 'detected', 'Alerta aberto: ' || name, started_at FROM incidents;
+         INSERT INTO incident_events (incident_id, kind, message, created_at)
+             SELECT incidents.id, 'acknowledged', COALESCE(alerts.ack_comment, 'Alerta reconhecido'), incidents.acknowledged_at
+             FROM incidents JOIN alerts ON alerts.id = incidents.alert_id WHERE incidents.acknowledged_at IS NOT NULL;
+         INSERT INTO incident_events (incident_id, kind, message, created_at)
+             SELECT id, 'resolved', 'Alerta resolvido', resolved_at FROM incidents WHERE resolved_at IS NOT NULL;",
+    ),
+];
+
+pub fn latest_version() -> i32 {
+    MIGRATIONS.last().map(|(version, _)| *version).unwrap_or(0)
+}
+
+pub fn run_migrations(sqlite: &mut Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let current: i32 = sqlite.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    let latest = latest_version();
+
+    if current > latest {
+        return Err(format!(
+            "Database schema version {} is newer than the latest supported version {}",
+            current, latest
+        )
+        .into());
+    }
+
+    for (version, sql) in MIGRATIONS.iter().filter(|(version, _)| *version > current) {
+        println!("Applying database migration {}", version);
+        let tx = sqlite.transaction()?;
+        tx.execute_batch(sql)
+            .map_err(|e| format!("Migration {} failed: {}", version, e))?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{
+        models::MonitorType,
+        sqlite::{query, urls},
+    };
+
+    /// The tables as created before migrations existed.
+    const BASELINE: &str = "CREATE TABLE memory (id INTEGER PRIMARY KEY, total_memory INTEGER NOT NULL, used_memory INTEGER NOT NULL, free_memory INTEGER NOT NULL);
+         CREATE TABLE cpu (id INTEGER PRIMARY KEY, total_cpus INTEGER NOT NULL, total_cpu_usage INTEGER NOT NULL, cores_usage TEXT NOT NULL, create_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP);
+         CREATE TABLE urls (id INTEGER PRIMARY KEY, url TEXT NOT NULL, status_code INTEGER, created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP);";
+
+    fn columns(sqlite: &Connection, table: &str) -> Vec<String> {
+        let mut stmt = sqlite
+            .prepare(&format!("SELECT name FROM pragma_table_info('{}')", table))
+            .unwrap();
+        stmt.query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn baseline_database_is_upgraded_with_its_data() {
+        let mut sqlite = Connection::open_in_memory().unwrap();
+        sqlite.execute_batch(BASELINE).unwrap();
+        sqlite
+            .execute_batch(
+                "INSERT INTO memory (total_memory, used_memory, free_memory) VALUES (16384, 4096, 12288);
+                 INSERT INTO cpu (total_cpus, total_cpu_usage, cores_usage, create_at) VALUES (4, 37, '[12.5, 61.5]', '2024-05-01 10:00:00');
+                 INSERT INTO urls (url, status_code, created_at) VALUES ('https://example.com/', 200, '2024-04-01 08:00:00');",
+            )
+            .unwrap();
+
+        run_migrations(&mut sqlite).unwrap();
+
+        let version: i32 = sqlite
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, latest_version());
+        assert_eq!(version, 17);
+
+        let cpu_columns = columns(&sqlite, "cpu");
+        assert!(cpu_columns.contains(&"created_at".to_string()));
+        assert!(!cpu_columns.contains(&"create_at".to_string()));
+        assert!(!cpu_columns.contains(&"cores_usage".to_string()));
+        let history = query::get_cpu_history(&sqlite).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].total_cpus, 4);
+        assert_eq!(history[0].total_cpu_usage, 37.0);
+        assert_eq!(history[0].created_at, "2024-05-01 10:00:00");
+        assert!(history[0].cores_usage.is_empty());
+
+        let memory: (i64, i64, i64) = sqlite
+            .query_row(
+                "SELECT total_memory, used_memory, free_memory FROM memory",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(memory, (16384, 4096, 12288));
+
+        let monitors = urls::get_urls(&sqlite).unwrap();
+        assert_eq!(monitors.len(), 1);
+        let monitor = &monitors[0];
+        assert_eq!(monitor.url, "https://example.com/");
+        assert_eq!(monitor.created_at, "2024-04-01 08:00:00");
+        // Without any check yet, the settings take their defaults
+        assert_eq!(monitor.status_code, Some(200));
+        assert_eq!(monitor.up, None);
+        assert_eq!(monitor.settings.interval, 60);
+        assert_eq!(monitor.settings.method, "GET");
+        assert_eq!(monitor.settings.expected_status, "200-399");
+        assert!(monitor.settings.monitor_type == MonitorType::Http);
+        assert!(monitor.heartbeat.is_none());
+
+        // Already up to date, nothing is applied again
+        run_migrations(&mut sqlite).unwrap();
+        assert_eq!(urls::get_urls(&sqlite).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn newer_database_is_refused() {
+        let mut sqlite = Connection::open_in_memory().unwrap();
+        sqlite
+            .pragma_update(None, "user_version", latest_version() + 1)
+            .unwrap();
+        assert!(run_migrations(&mut sqlite).is_err());
+    }
+}
@@ -1,5 +1,13 @@
+pub mod alerts;
+pub mod checks;
 pub mod connection;
-pub mod create_database;
-pub mod query;  
+pub mod escalations;
 pub mod flush;
-pub mod urls;
\ No newline at end of file
+pub mod incidents;
+pub mod maintenance;
+pub mod migrations;
+pub mod notifications;
+pub mod query;
+pub mod storage;
+pub mod urls;
+pub mod worker;
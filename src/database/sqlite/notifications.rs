@@ -0,0 +1,195 @@
+use chrono::NaiveDateTime;
+use rusqlite::{types::Type, Connection, OptionalExtension, Row};
+
+use crate::database::{
+    models::{ChannelConfig, DeliveryResult, EventKind, NotificationChannel, NotificationDelivery},
+    sqlite::escalations,
+    time::format_timestamp,
+};
+
+const CHANNEL_COLUMNS: &str = "id, name, type, config, events, enabled, created_at";
+
+fn conversion_error(column: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(column, Type::Text, message.into())
+}
+
+fn event_from_sql(value: String, column: usize) -> rusqlite::Result<EventKind> {
+    EventKind::parse(&value)
+        .ok_or_else(|| conversion_error(column, format!("Unknown event {}", value)))
+}
+
+fn events_to_sql(events: &[EventKind]) -> String {
+    serde_json::Value::from(events.iter().map(EventKind::as_str).collect::<Vec<&str>>()).to_string()
+}
+
+fn channel_from_row(row: &Row) -> rusqlite::Result<NotificationChannel> {
+    let kind: String = row.get(2)?;
+    let config: String = row.get(3)?;
+    let config = serde_json::from_str::<serde_json::Value>(&config)
+        .map_err(|e| conversion_error(3, e.to_string()))?;
+    let events: String = row.get(4)?;
+    let events = serde_json::from_str::<Vec<String>>(&events)
+        .map_err(|e| conversion_error(4, e.to_string()))?;
+    Ok(NotificationChannel {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        config: ChannelConfig::from_json(&kind, &config).map_err(|e| conversion_error(3, e))?,
+        events: events
+            .into_iter()
+            .map(|event| event_from_sql(event, 4))
+            .collect::<rusqlite::Result<_>>()?,
+        enabled: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+pub fn get_channels(sqlite: &Connection) -> rusqlite::Result<Vec<NotificationChannel>> {
+    let mut stmt = sqlite.prepare(&format!(
+        "SELECT {} FROM notification_channels ORDER BY id",
+        CHANNEL_COLUMNS
+    ))?;
+    let channel_iter = stmt.query_map([], channel_from_row)?;
+
+    let mut channels = Vec::new();
+    for channel in channel_iter {
+        channels.push(channel?);
+    }
+    Ok(channels)
+}
+
+pub fn get_channel(sqlite: &Connection, id: i64) -> rusqlite::Result<Option<NotificationChannel>> {
+    sqlite
+        .query_row(
+            &format!(
+                "SELECT {} FROM notification_channels WHERE id = ?1",
+                CHANNEL_COLUMNS
+            ),
+            (id,),
+            channel_from_row,
+        )
+        .optional()
+}
+
+pub fn create_channel(
+    sqlite: &Connection,
+    name: &str,
+    config: &ChannelConfig,
+    events: &[EventKind],
+    enabled: bool,
+) -> rusqlite::Result<NotificationChannel> {
+    sqlite.query_row(
+        &format!(
+            "INSERT INTO notification_channels (name, type, config, events, enabled)
+             VALUES (?1, ?2, ?3, ?4, ?5) RETURNING {}",
+            CHANNEL_COLUMNS
+        ),
+        (
+            name,
+            config.kind(),
+            config.to_json().to_string(),
+            events_to_sql(events),
+            enabled,
+        ),
+        channel_from_row,
+    )
+}
+
+pub fn update_channel(
+    sqlite: &Connection,
+    id: i64,
+    name: &str,
+    config: &ChannelConfig,
+    events: &[EventKind],
+    enabled: bool,
+) -> rusqlite::Result<Option<NotificationChannel>> {
+    sqlite
+        .query_row(
+            &format!(
+                "UPDATE notification_channels SET name = ?2, type = ?3, config = ?4, events = ?5, enabled = ?6
+                 WHERE id = ?1 RETURNING {}",
+                CHANNEL_COLUMNS
+            ),
+            (
+                id,
+                name,
+                config.kind(),
+                config.to_json().to_string(),
+                events_to_sql(events),
+                enabled,
+            ),
+            channel_from_row,
+        )
+        .optional()
+}
+
+pub fn delete_channel(sqlite: &Connection, id: i64) -> rusqlite::Result<bool> {
+    sqlite.execute(
+        "DELETE FROM notification_deliveries WHERE channel_id = ?1",
+        (id,),
+    )?;
+    escalations::forget_channel(sqlite, id)?;
+    let deleted = sqlite.execute("DELETE FROM notification_channels WHERE id = ?1", (id,))?;
+    Ok(deleted > 0)
+}
+
+pub fn save_delivery(
+    sqlite: &Connection,
+    channel_id: i64,
+    result: &DeliveryResult,
+) -> rusqlite::Result<()> {
+    sqlite.execute(
+        "INSERT INTO notification_deliveries (channel_id, event, subject, attempt, success, status_code, error) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        (
+            channel_id,
+            result.event.as_str(),
+            &result.subject,
+            result.attempt,
+            result.success,
+            result.status_code,
+            &result.error,
+        ),
+    )?;
+    Ok(())
+}
+
+pub fn get_deliveries(
+    sqlite: &Connection,
+    channel_id: Option<i64>,
+    limit: u32,
+) -> rusqlite::Result<Vec<NotificationDelivery>> {
+    let mut stmt = sqlite.prepare(
+        "SELECT id, channel_id, event, subject, attempt, success, status_code, error, created_at
+         FROM notification_deliveries
+         WHERE (?1 IS NULL OR channel_id = ?1)
+         ORDER BY created_at DESC, id DESC LIMIT ?2",
+    )?;
+    let delivery_iter = stmt.query_map((channel_id, limit), |row| {
+        Ok(NotificationDelivery {
+            id: row.get(0)?,
+            channel_id: row.get(1)?,
+            result: DeliveryResult {
+                event: event_from_sql(row.get(2)?, 2)?,
+                subject: row.get(3)?,
+                attempt: row.get(4)?,
+                success: row.get(5)?,
+                status_code: row.get(6)?,
+                error: row.get(7)?,
+            },
+            created_at: row.get(8)?,
+        })
+    })?;
+
+    let mut deliveries = Vec::new();
+    for delivery in delivery_iter {
+        deliveries.push(delivery?);
+    }
+    Ok(deliveries)
+}
+
+pub fn flush_deliveries(sqlite: &Connection, before: &NaiveDateTime) -> rusqlite::Result<()> {
+    sqlite.execute(
+        "DELETE FROM notification_deliveries WHERE created_at < ?1",
+        (format_timestamp(before),),
+    )?;
+    Ok(())
+}
@@ -1,40 +1,77 @@
-use std::sync::{Arc, Mutex};
-
 use rusqlite::Connection;
 
-pub async fn save_cpu_info(
-    con: Arc<Mutex<Connection>>,
+use crate::database::models::{CoreSample, CpuSample};
+
+/// Inserts a cpu sample and its per-core rows. Meant to run on the writer, whose savepoint
+/// makes the inserts atomic.
+pub fn save_cpu_info(
+    con: &Connection,
     total_cpus: u32,
-    total_cpu_usage: u32,
-    cores_usage: String,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let con = con.lock().unwrap();
-    let query: &'static str =
-        "INSERT INTO cpu (total_cpus, total_cpu_usage, cores_usage) VALUES (?1, ?2, ?3)";
-    con.execute(query, (&total_cpus, &total_cpu_usage, &cores_usage))?;
+    total_cpu_usage: f32,
+    cores_usage: &[f32],
+) -> rusqlite::Result<()> {
+    con.execute(
+        "INSERT INTO cpu (total_cpus, total_cpu_usage) VALUES (?1, ?2)",
+        (&total_cpus, &total_cpu_usage),
+    )?;
+    let cpu_id = con.last_insert_rowid();
+    let mut stmt =
+        con.prepare_cached("INSERT INTO cpu_core (cpu_id, core, usage) VALUES (?1, ?2, ?3)")?;
+    for (core, usage) in cores_usage.iter().enumerate() {
+        stmt.execute((cpu_id, core as u32, usage))?;
+    }
     Ok(())
 }
 
-pub async fn get_cpu_history(
-    con: Arc<Mutex<Connection>>,
-) -> Result<Vec<(u32, u32, u32, String, String)>, Box<dyn std::error::Error>> {
-    let con = con.lock().unwrap();
+pub fn get_cpu_history(con: &Connection) -> rusqlite::Result<Vec<CpuSample>> {
     let mut stmt = con.prepare(
-        "SELECT id, total_cpus, total_cpu_usage, cores_usage, create_at FROM cpu ORDER BY create_at DESC LIMIT 30",
+        "SELECT id, total_cpus, total_cpu_usage, created_at FROM cpu ORDER BY created_at DESC, id DESC LIMIT 30",
     )?;
     let cpu_iter = stmt.query_map([], |row| {
-        Ok((
-            row.get(0)?,
-            row.get(1)?,
-            row.get(2)?,
-            row.get(3)?,
-            row.get(4)?,
-        ))
+        Ok(CpuSample {
+            id: row.get(0)?,
+            total_cpus: row.get(1)?,
+            total_cpu_usage: row.get::<_, f64>(2)? as f32,
+            cores_usage: Vec::new(),
+            created_at: row.get(3)?,
+        })
     })?;
 
     let mut cpu_info = Vec::new();
     for cpu in cpu_iter {
         cpu_info.push(cpu?);
     }
+
+    let mut cores_stmt =
+        con.prepare("SELECT usage FROM cpu_core WHERE cpu_id = ?1 ORDER BY core ASC")?;
+    for sample in cpu_info.iter_mut() {
+        let cores = cores_stmt.query_map((sample.id,), |row| row.get::<_, f64>(0))?;
+        for usage in cores {
+            sample.cores_usage.push(usage? as f32);
+        }
+    }
     Ok(cpu_info)
 }
+
+pub fn get_core_history(con: &Connection, core: u32) -> rusqlite::Result<Vec<CoreSample>> {
+    let mut stmt = con.prepare(
+        "SELECT cpu_core.cpu_id, cpu_core.core, cpu_core.usage, cpu.created_at FROM cpu_core
+         INNER JOIN cpu ON cpu.id = cpu_core.cpu_id
+         WHERE cpu_core.core = ?1
+         ORDER BY cpu.created_at DESC, cpu.id DESC LIMIT 30",
+    )?;
+    let core_iter = stmt.query_map((core,), |row| {
+        Ok(CoreSample {
+            cpu_id: row.get(0)?,
+            core: row.get(1)?,
+            usage: row.get::<_, f64>(2)? as f32,
+            created_at: row.get(3)?,
+        })
+    })?;
+
+    let mut samples = Vec::new();
+    for sample in core_iter {
+        samples.push(sample?);
+    }
+    Ok(samples)
+}
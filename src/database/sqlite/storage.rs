@@ -0,0 +1,531 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+
+use crate::database::{
+    error::{StorageError, StorageResult},
+    models::{
+        Alert, AlertCondition, AlertMatcher, AlertRule, AlertSource, AlertState, ChannelConfig,
+        CheckResult, CoreSample, CpuSample, DeliveryResult, Escalation, EscalationPolicy,
+        EscalationSettings, EventKind, Heartbeat, Incident, IncidentEvent, IncidentEventKind,
+        MaintenanceSettings, MaintenanceWindow, MonitorStatus, NotificationChannel,
+        NotificationDelivery, Silence, UrlCertificate, UrlCheck, UrlData, UrlSettings,
+    },
+    sqlite::{
+        alerts, checks,
+        connection::{self, DatabaseConfig},
+        escalations, flush, incidents, maintenance, migrations, notifications, query, urls,
+        worker::Worker,
+    },
+    storage::Storage,
+};
+
+/// Default storage backend, backed by the SQLite writer thread and reader pool.
+#[derive(Clone)]
+pub struct SqliteStorage {
+    worker: Worker,
+}
+
+impl SqliteStorage {
+    /// Opens the database, applies pending migrations and starts the writer and readers.
+    pub async fn open(
+        config: &DatabaseConfig,
+    ) -> Result<SqliteStorage, Box<dyn std::error::Error>> {
+        let mut writer = connection::connection_database(config).await?;
+        migrations::run_migrations(&mut writer)?;
+
+        // Separate connections to :memory: would each see their own empty database
+        let reader_count = if config.is_memory() {
+            0
+        } else {
+            config.readers
+        };
+        let mut readers = Vec::with_capacity(reader_count);
+        for _ in 0..reader_count {
+            readers.push(connection::connection_reader(config)?);
+        }
+
+        Ok(SqliteStorage {
+            worker: Worker::start(writer, readers)?,
+        })
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn save_cpu_info(
+        &self,
+        total_cpus: u32,
+        total_cpu_usage: f32,
+        cores_usage: Vec<f32>,
+    ) -> StorageResult<()> {
+        self.worker
+            .write(move |con| query::save_cpu_info(con, total_cpus, total_cpu_usage, &cores_usage))
+            .await
+    }
+
+    async fn get_cpu_history(&self) -> StorageResult<Vec<CpuSample>> {
+        self.worker.read(query::get_cpu_history).await
+    }
+
+    async fn get_core_history(&self, core: u32) -> StorageResult<Vec<CoreSample>> {
+        self.worker
+            .read(move |con| query::get_core_history(con, core))
+            .await
+    }
+
+    async fn flush_cpu_info(&self) -> StorageResult<()> {
+        self.worker.write(flush::flush_cpu_info).await
+    }
+
+    async fn get_urls(&self) -> StorageResult<Vec<UrlData>> {
+        self.worker.read(urls::get_urls).await
+    }
+
+    async fn get_url(&self, id: i32) -> StorageResult<Option<UrlData>> {
+        self.worker.read(move |con| urls::get_url(con, id)).await
+    }
+
+    async fn get_url_by_heartbeat_token(&self, token: String) -> StorageResult<Option<UrlData>> {
+        self.worker
+            .read(move |con| urls::get_url_by_heartbeat_token(con, &token))
+            .await
+    }
+
+    async fn create_url(
+        &self,
+        url: String,
+        settings: UrlSettings,
+        heartbeat: Option<Heartbeat>,
+    ) -> StorageResult<UrlData> {
+        self.worker
+            .write(move |con| urls::create_url(con, &url, &settings, heartbeat.as_ref()))
+            .await?
+            .map_err(StorageError::Duplicate)
+    }
+
+    async fn update_url(
+        &self,
+        id: i32,
+        url: String,
+        settings: UrlSettings,
+        heartbeat: Option<Heartbeat>,
+    ) -> StorageResult<Option<UrlData>> {
+        self.worker
+            .write(move |con| urls::update_url(con, id, &url, &settings, heartbeat.as_ref()))
+            .await?
+            .map_err(StorageError::Duplicate)
+    }
+
+    async fn rename_url(&self, id: i32, url: String) -> StorageResult<bool> {
+        self.worker
+            .write(move |con| urls::rename_url(con, id, &url))
+            .await?
+            .map_err(StorageError::Duplicate)
+    }
+
+    async fn delete_url(&self, id: i32) -> StorageResult<bool> {
+        self.worker
+            .write(move |con| urls::delete_url(con, id))
+            .await
+    }
+
+    async fn save_certificate(
+        &self,
+        url_id: i32,
+        certificate: UrlCertificate,
+    ) -> StorageResult<()> {
+        self.worker
+            .write(move |con| urls::save_certificate(con, url_id, &certificate))
+            .await
+    }
+
+    async fn save_heartbeat(&self, url_id: i32, heartbeat: Option<Heartbeat>) -> StorageResult<()> {
+        self.worker
+            .write(move |con| urls::save_heartbeat(con, url_id, heartbeat.as_ref()))
+            .await
+    }
+
+    async fn save_missed_heartbeat(
+        &self,
+        url_id: i32,
+        expected_at: String,
+        heartbeat: Heartbeat,
+    ) -> StorageResult<bool> {
+        self.worker
+            .write(move |con| urls::save_missed_heartbeat(con, url_id, &expected_at, &heartbeat))
+            .await
+    }
+
+    async fn save_check_result(
+        &self,
+        url_id: i32,
+        result: CheckResult,
+        status: MonitorStatus,
+    ) -> StorageResult<()> {
+        self.worker
+            .write(move |con| checks::save_check_result(con, url_id, &result, &status))
+            .await
+    }
+
+    async fn get_url_checks(
+        &self,
+        url_id: i32,
+        from: Option<NaiveDateTime>,
+        to: Option<NaiveDateTime>,
+        limit: u32,
+    ) -> StorageResult<Vec<UrlCheck>> {
+        self.worker
+            .read(move |con| checks::get_url_checks(con, url_id, from, to, limit))
+            .await
+    }
+
+    async fn flush_url_checks(&self, before: NaiveDateTime) -> StorageResult<()> {
+        self.worker
+            .write(move |con| checks::flush_url_checks(con, &before))
+            .await
+    }
+
+    async fn get_alert_rules(&self) -> StorageResult<Vec<AlertRule>> {
+        self.worker.read(alerts::get_alert_rules).await
+    }
+
+    async fn get_alert_rule(&self, id: i64) -> StorageResult<Option<AlertRule>> {
+        self.worker
+            .read(move |con| alerts::get_alert_rule(con, id))
+            .await
+    }
+
+    async fn create_alert_rule(
+        &self,
+        name: String,
+        condition: AlertCondition,
+        labels: BTreeMap<String, String>,
+    ) -> StorageResult<AlertRule> {
+        self.worker
+            .write(move |con| alerts::create_alert_rule(con, &name, &condition, &labels))
+            .await
+    }
+
+    async fn update_alert_rule(
+        &self,
+        id: i64,
+        name: String,
+        condition: AlertCondition,
+        labels: BTreeMap<String, String>,
+    ) -> StorageResult<Option<AlertRule>> {
+        self.worker
+            .write(move |con| alerts::update_alert_rule(con, id, &name, &condition, &labels))
+            .await
+    }
+
+    async fn delete_alert_rule(&self, id: i64) -> StorageResult<bool> {
+        self.worker
+            .write(move |con| alerts::delete_alert_rule(con, id))
+            .await
+    }
+
+    async fn save_alert_state(&self, id: i64, state: AlertState, value: f64) -> StorageResult<()> {
+        self.worker
+            .write(move |con| alerts::save_alert_state(con, id, state, value))
+            .await
+    }
+
+    async fn open_alert(
+        &self,
+        source: AlertSource,
+        name: String,
+        labels: BTreeMap<String, String>,
+        detail: String,
+    ) -> StorageResult<Option<Alert>> {
+        self.worker
+            .write(move |con| alerts::open_alert(con, source, &name, &labels, &detail))
+            .await
+    }
+
+    async fn resolve_alert(
+        &self,
+        source: AlertSource,
+        detail: String,
+    ) -> StorageResult<Option<Alert>> {
+        self.worker
+            .write(move |con| alerts::resolve_alert(con, source, &detail))
+            .await
+    }
+
+    async fn get_alerts(&self, active: Option<bool>, limit: u32) -> StorageResult<Vec<Alert>> {
+        self.worker
+            .read(move |con| alerts::get_alerts(con, active, limit))
+            .await
+    }
+
+    async fn get_alert(&self, id: i64) -> StorageResult<Option<Alert>> {
+        self.worker
+            .read(move |con| alerts::get_alert(con, id))
+            .await
+    }
+
+    async fn acknowledge_alert(
+        &self,
+        id: i64,
+        comment: Option<String>,
+    ) -> StorageResult<Option<Alert>> {
+        self.worker
+            .write(move |con| alerts::acknowledge_alert(con, id, comment.as_deref()))
+            .await
+    }
+
+    async fn flush_alerts(&self, before: NaiveDateTime) -> StorageResult<()> {
+        self.worker
+            .write(move |con| alerts::flush_alerts(con, &before))
+            .await
+    }
+
+    async fn get_incidents(
+        &self,
+        open: Option<bool>,
+        source: Option<AlertSource>,
+        limit: u32,
+    ) -> StorageResult<Vec<Incident>> {
+        self.worker
+            .read(move |con| incidents::get_incidents(con, open, source, limit))
+            .await
+    }
+
+    async fn get_incident(&self, id: i64) -> StorageResult<Option<Incident>> {
+        self.worker
+            .read(move |con| incidents::get_incident(con, id))
+            .await
+    }
+
+    async fn get_alert_incident(&self, alert_id: i64) -> StorageResult<Option<Incident>> {
+        self.worker
+            .read(move |con| incidents::get_alert_incident(con, alert_id))
+            .await
+    }
+
+    async fn get_incident_events(&self, incident_id: i64) -> StorageResult<Vec<IncidentEvent>> {
+        self.worker
+            .read(move |con| incidents::get_incident_events(con, incident_id))
+            .await
+    }
+
+    async fn add_incident_event(
+        &self,
+        incident_id: i64,
+        kind: IncidentEventKind,
+        message: String,
+    ) -> StorageResult<Option<IncidentEvent>> {
+        self.worker
+            .write(move |con| incidents::add_incident_event(con, incident_id, kind, &message))
+            .await
+    }
+
+    async fn flush_incidents(&self, before: NaiveDateTime) -> StorageResult<()> {
+        self.worker
+            .write(move |con| incidents::flush_incidents(con, &before))
+            .await
+    }
+
+    async fn get_silences(&self, at: Option<NaiveDateTime>) -> StorageResult<Vec<Silence>> {
+        self.worker
+            .read(move |con| alerts::get_silences(con, at.as_ref()))
+            .await
+    }
+
+    async fn get_silence(&self, id: i64) -> StorageResult<Option<Silence>> {
+        self.worker
+            .read(move |con| alerts::get_silence(con, id))
+            .await
+    }
+
+    async fn create_silence(
+        &self,
+        matcher: AlertMatcher,
+        comment: String,
+        expires_at: NaiveDateTime,
+    ) -> StorageResult<Silence> {
+        self.worker
+            .write(move |con| alerts::create_silence(con, &matcher, &comment, &expires_at))
+            .await
+    }
+
+    async fn delete_silence(&self, id: i64) -> StorageResult<bool> {
+        self.worker
+            .write(move |con| alerts::delete_silence(con, id))
+            .await
+    }
+
+    async fn get_escalation_policies(&self) -> StorageResult<Vec<EscalationPolicy>> {
+        self.worker.read(escalations::get_escalation_policies).await
+    }
+
+    async fn get_escalation_policy(&self, id: i64) -> StorageResult<Option<EscalationPolicy>> {
+        self.worker
+            .read(move |con| escalations::get_escalation_policy(con, id))
+            .await
+    }
+
+    async fn create_escalation_policy(
+        &self,
+        settings: EscalationSettings,
+    ) -> StorageResult<EscalationPolicy> {
+        self.worker
+            .write(move |con| escalations::create_escalation_policy(con, &settings))
+            .await
+    }
+
+    async fn update_escalation_policy(
+        &self,
+        id: i64,
+        settings: EscalationSettings,
+    ) -> StorageResult<Option<EscalationPolicy>> {
+        self.worker
+            .write(move |con| escalations::update_escalation_policy(con, id, &settings))
+            .await
+    }
+
+    async fn delete_escalation_policy(&self, id: i64) -> StorageResult<bool> {
+        self.worker
+            .write(move |con| escalations::delete_escalation_policy(con, id))
+            .await
+    }
+
+    async fn start_escalation(
+        &self,
+        alert_id: i64,
+        policy_id: i64,
+        due_at: NaiveDateTime,
+        notification: serde_json::Value,
+    ) -> StorageResult<Escalation> {
+        self.worker
+            .write(move |con| {
+                escalations::start_escalation(con, alert_id, policy_id, &due_at, &notification)
+            })
+            .await
+    }
+
+    async fn get_escalations(&self, alert_id: Option<i64>) -> StorageResult<Vec<Escalation>> {
+        self.worker
+            .read(move |con| escalations::get_escalations(con, alert_id))
+            .await
+    }
+
+    async fn advance_escalation(
+        &self,
+        id: i64,
+        step: u32,
+        due_at: NaiveDateTime,
+    ) -> StorageResult<()> {
+        self.worker
+            .write(move |con| escalations::advance_escalation(con, id, step, &due_at))
+            .await
+    }
+
+    async fn delete_escalation(&self, id: i64) -> StorageResult<bool> {
+        self.worker
+            .write(move |con| escalations::delete_escalation(con, id))
+            .await
+    }
+
+    async fn get_maintenance_windows(&self) -> StorageResult<Vec<MaintenanceWindow>> {
+        self.worker.read(maintenance::get_maintenance_windows).await
+    }
+
+    async fn get_maintenance_window(&self, id: i64) -> StorageResult<Option<MaintenanceWindow>> {
+        self.worker
+            .read(move |con| maintenance::get_maintenance_window(con, id))
+            .await
+    }
+
+    async fn create_maintenance_window(
+        &self,
+        settings: MaintenanceSettings,
+    ) -> StorageResult<MaintenanceWindow> {
+        self.worker
+            .write(move |con| maintenance::create_maintenance_window(con, &settings))
+            .await
+    }
+
+    async fn update_maintenance_window(
+        &self,
+        id: i64,
+        settings: MaintenanceSettings,
+    ) -> StorageResult<Option<MaintenanceWindow>> {
+        self.worker
+            .write(move |con| maintenance::update_maintenance_window(con, id, &settings))
+            .await
+    }
+
+    async fn delete_maintenance_window(&self, id: i64) -> StorageResult<bool> {
+        self.worker
+            .write(move |con| maintenance::delete_maintenance_window(con, id))
+            .await
+    }
+
+    async fn get_channels(&self) -> StorageResult<Vec<NotificationChannel>> {
+        self.worker.read(notifications::get_channels).await
+    }
+
+    async fn get_channel(&self, id: i64) -> StorageResult<Option<NotificationChannel>> {
+        self.worker
+            .read(move |con| notifications::get_channel(con, id))
+            .await
+    }
+
+    async fn create_channel(
+        &self,
+        name: String,
+        config: ChannelConfig,
+        events: Vec<EventKind>,
+        enabled: bool,
+    ) -> StorageResult<NotificationChannel> {
+        self.worker
+            .write(move |con| notifications::create_channel(con, &name, &config, &events, enabled))
+            .await
+    }
+
+    async fn update_channel(
+        &self,
+        id: i64,
+        name: String,
+        config: ChannelConfig,
+        events: Vec<EventKind>,
+        enabled: bool,
+    ) -> StorageResult<Option<NotificationChannel>> {
+        self.worker
+            .write(move |con| {
+                notifications::update_channel(con, id, &name, &config, &events, enabled)
+            })
+            .await
+    }
+
+    async fn delete_channel(&self, id: i64) -> StorageResult<bool> {
+        self.worker
+            .write(move |con| notifications::delete_channel(con, id))
+            .await
+    }
+
+    async fn save_delivery(&self, channel_id: i64, result: DeliveryResult) -> StorageResult<()> {
+        self.worker
+            .write(move |con| notifications::save_delivery(con, channel_id, &result))
+            .await
+    }
+
+    async fn get_deliveries(
+        &self,
+        channel_id: Option<i64>,
+        limit: u32,
+    ) -> StorageResult<Vec<NotificationDelivery>> {
+        self.worker
+            .read(move |con| notifications::get_deliveries(con, channel_id, limit))
+            .await
+    }
+
+    async fn flush_deliveries(&self, before: NaiveDateTime) -> StorageResult<()> {
+        self.worker
+            .write(move |con| notifications::flush_deliveries(con, &before))
+            .await
+    }
+}
@@ -1,56 +1,347 @@
-use rusqlite::Connection;
-use std::sync::{Arc, Mutex};
-
-pub struct UrlData {
-    pub id: i32,
-    pub url: String,
-    pub status_code: i32,
-    pub created_at: String,
-}
-
-pub fn get_urls(sqlite: &Arc<std::sync::Mutex<rusqlite::Connection>>) -> Vec<UrlData> {
-    let sqlite = sqlite.lock().unwrap();
-    let mut stmt = sqlite.prepare("SELECT * FROM urls").unwrap();
-    let url_iter = stmt
-        .query_map([], |row| {
-            Ok(UrlData {
-                id: row.get(0).unwrap(),
-                url: row.get(1).unwrap(),
-                status_code: row.get(2).unwrap_or(0),
-                created_at: row.get(3).unwrap(),
+use rusqlite::{params, types::Type, Connection, OptionalExtension, Row};
+
+use crate::database::{
+    models::{
+        AlertSource, Assertion, CertificateInfo, Heartbeat, MonitorType, UrlCertificate, UrlData,
+        UrlSettings,
+    },
+    sqlite::{
+        alerts::{self, labels_from_sql, labels_to_sql, resolve_alert},
+        checks::outcome_from_sql,
+        escalations, maintenance,
+    },
+};
+
+const URL_COLUMNS: &str = "id, url, status_code, created_at, check_interval, check_timeout, method, headers, body, follow_redirects, expected_status, is_up, outcome, assertions, certificate, certificate_checked_at, monitor_type, labels, confirmations_down, confirmations_up, retry_interval, unconfirmed_checks, flapping, grace, heartbeat_token, heartbeat_last_ping_at, heartbeat_started_at, heartbeat_expected_at";
+
+fn json_from_sql(value: String, column: usize) -> rusqlite::Result<serde_json::Value> {
+    serde_json::from_str(&value)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(column, Type::Text, e.into()))
+}
+
+fn assertions_from_sql(value: String, column: usize) -> rusqlite::Result<Vec<Assertion>> {
+    let json = json_from_sql(value, column)?;
+    json.as_array()
+        .into_iter()
+        .flatten()
+        .map(|assertion| {
+            Assertion::from_json(assertion).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(column, Type::Text, e.into())
             })
         })
-        .unwrap();
+        .collect()
+}
+
+fn assertions_to_sql(assertions: &[Assertion]) -> String {
+    serde_json::Value::Array(assertions.iter().map(Assertion::to_json).collect()).to_string()
+}
+
+fn certificate_from_sql(
+    chain: Option<String>,
+    checked_at: Option<String>,
+    column: usize,
+) -> rusqlite::Result<Option<UrlCertificate>> {
+    let (Some(chain), Some(checked_at)) = (chain, checked_at) else {
+        return Ok(None);
+    };
+    let json = json_from_sql(chain, column)?;
+    let chain = json
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|certificate| {
+            CertificateInfo::from_json(certificate).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(column, Type::Text, e.into())
+            })
+        })
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(Some(UrlCertificate { chain, checked_at }))
+}
+
+fn monitor_type_from_sql(value: String, column: usize) -> rusqlite::Result<MonitorType> {
+    MonitorType::parse(&value).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(
+            column,
+            Type::Text,
+            format!("Unknown monitor type {}", value).into(),
+        )
+    })
+}
+
+fn heartbeat_from_row(row: &Row) -> rusqlite::Result<Option<Heartbeat>> {
+    let (Some(token), Some(expected_at)) = (row.get(24)?, row.get(27)?) else {
+        return Ok(None);
+    };
+    Ok(Some(Heartbeat {
+        token,
+        last_ping_at: row.get(25)?,
+        started_at: row.get(26)?,
+        expected_at,
+    }))
+}
+
+fn url_from_row(row: &Row) -> rusqlite::Result<UrlData> {
+    let headers: String = row.get(7)?;
+    Ok(UrlData {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        status_code: row.get(2)?,
+        created_at: row.get(3)?,
+        settings: UrlSettings {
+            monitor_type: monitor_type_from_sql(row.get(16)?, 16)?,
+            interval: row.get(4)?,
+            grace: row.get(23)?,
+            timeout: row.get(5)?,
+            method: row.get(6)?,
+            headers: serde_json::from_str(&headers)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(7, Type::Text, e.into()))?,
+            body: row.get(8)?,
+            follow_redirects: row.get(9)?,
+            expected_status: row.get(10)?,
+            assertions: assertions_from_sql(row.get(13)?, 13)?,
+            labels: labels_from_sql(row.get(17)?, 17)?,
+            confirmations_down: row.get(18)?,
+            confirmations_up: row.get(19)?,
+            retry_interval: row.get(20)?,
+        },
+        up: row.get(11)?,
+        outcome: row
+            .get::<_, Option<String>>(12)?
+            .map(|outcome| outcome_from_sql(outcome, 12))
+            .transpose()?,
+        unconfirmed_checks: row.get(21)?,
+        flapping: row.get(22)?,
+        certificate: certificate_from_sql(row.get(14)?, row.get(15)?, 14)?,
+        heartbeat: heartbeat_from_row(row)?,
+    })
+}
+
+pub fn get_urls(sqlite: &Connection) -> rusqlite::Result<Vec<UrlData>> {
+    let mut stmt = sqlite.prepare(&format!("SELECT {} FROM urls ORDER BY id", URL_COLUMNS))?;
+    let url_iter = stmt.query_map([], url_from_row)?;
 
     let mut urls = Vec::new();
     for url in url_iter {
-        urls.push(url.unwrap());
+        urls.push(url?);
     }
-    urls
+    Ok(urls)
+}
+
+pub fn get_url_by_heartbeat_token(
+    sqlite: &Connection,
+    token: &str,
+) -> rusqlite::Result<Option<UrlData>> {
+    sqlite
+        .query_row(
+            &format!(
+                "SELECT {} FROM urls WHERE heartbeat_token = ?1",
+                URL_COLUMNS
+            ),
+            (token,),
+            url_from_row,
+        )
+        .optional()
 }
 
+pub fn get_url(sqlite: &Connection, id: i32) -> rusqlite::Result<Option<UrlData>> {
+    sqlite
+        .query_row(
+            &format!("SELECT {} FROM urls WHERE id = ?1", URL_COLUMNS),
+            (id,),
+            url_from_row,
+        )
+        .optional()
+}
+
+/// Id of a monitor other than `except` checking `url`.
+fn find_duplicate(
+    sqlite: &Connection,
+    url: &str,
+    except: Option<i32>,
+) -> rusqlite::Result<Option<i32>> {
+    sqlite
+        .query_row(
+            "SELECT id FROM urls WHERE url = ?1 AND id IS NOT ?2 LIMIT 1",
+            (url, except),
+            |row| row.get(0),
+        )
+        .optional()
+}
+
+/// `Err` holds the id of the monitor that already has this URL.
 pub fn create_url(
-    sqlite: Arc<Mutex<Connection>>,
+    sqlite: &Connection,
     url: &str,
-    status_code: Option<i32>,
+    settings: &UrlSettings,
+    heartbeat: Option<&Heartbeat>,
+) -> rusqlite::Result<Result<UrlData, i32>> {
+    if let Some(existing) = find_duplicate(sqlite, url, None)? {
+        return Ok(Err(existing));
+    }
+    sqlite.query_row(
+        &format!(
+            "INSERT INTO urls (url, check_interval, check_timeout, method, headers, body, follow_redirects, expected_status, assertions, monitor_type, labels, confirmations_down, confirmations_up, retry_interval, grace, heartbeat_token, heartbeat_last_ping_at, heartbeat_started_at, heartbeat_expected_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19) RETURNING {}",
+            URL_COLUMNS
+        ),
+        params![
+            url,
+            settings.interval,
+            settings.timeout,
+            &settings.method,
+            serde_json::to_string(&settings.headers).unwrap(),
+            &settings.body,
+            settings.follow_redirects,
+            &settings.expected_status,
+            assertions_to_sql(&settings.assertions),
+            settings.monitor_type.as_str(),
+            labels_to_sql(&settings.labels),
+            settings.confirmations_down,
+            settings.confirmations_up,
+            settings.retry_interval,
+            settings.grace,
+            heartbeat.map(|heartbeat| &heartbeat.token),
+            heartbeat.and_then(|heartbeat| heartbeat.last_ping_at.as_ref()),
+            heartbeat.and_then(|heartbeat| heartbeat.started_at.as_ref()),
+            heartbeat.map(|heartbeat| &heartbeat.expected_at),
+        ],
+        url_from_row,
+    )
+    .map(Ok)
+}
+
+/// The previous status belongs to the old address, so it is cleared along with the change and
+/// the active alert of the monitor is resolved. `Err` holds the id of another monitor that already has this URL.
+pub fn update_url(
+    sqlite: &Connection,
+    id: i32,
+    url: &str,
+    settings: &UrlSettings,
+    heartbeat: Option<&Heartbeat>,
+) -> rusqlite::Result<Result<Option<UrlData>, i32>> {
+    if let Some(existing) = find_duplicate(sqlite, url, Some(id))? {
+        return Ok(Err(existing));
+    }
+    if get_url(sqlite, id)?.is_some_and(|current| current.url != url) {
+        resolve_alert(sqlite, AlertSource::Monitor(id), "URL do monitor alterada")?;
+    }
+    sqlite
+        .query_row(
+            &format!(
+                "UPDATE urls SET url = ?1, status_code = CASE WHEN url = ?1 THEN status_code END,
+                 is_up = CASE WHEN url = ?1 THEN is_up END,
+                 outcome = CASE WHEN url = ?1 THEN outcome END,
+                 unconfirmed_checks = CASE WHEN url = ?1 THEN unconfirmed_checks ELSE 0 END,
+                 flapping = CASE WHEN url = ?1 THEN flapping ELSE 0 END,
+                 certificate = CASE WHEN url = ?1 THEN certificate END,
+                 certificate_checked_at = CASE WHEN url = ?1 THEN certificate_checked_at END,
+                 check_interval = ?3, check_timeout = ?4, method = ?5, headers = ?6, body = ?7,
+                 follow_redirects = ?8, expected_status = ?9, assertions = ?10, monitor_type = ?11,
+                 labels = ?12, confirmations_down = ?13, confirmations_up = ?14, retry_interval = ?15,
+                 grace = ?16, heartbeat_token = ?17, heartbeat_last_ping_at = ?18,
+                 heartbeat_started_at = ?19, heartbeat_expected_at = ?20 WHERE id = ?2 RETURNING {}",
+                URL_COLUMNS
+            ),
+            params![
+                url,
+                id,
+                settings.interval,
+                settings.timeout,
+                &settings.method,
+                serde_json::to_string(&settings.headers).unwrap(),
+                &settings.body,
+                settings.follow_redirects,
+                &settings.expected_status,
+                assertions_to_sql(&settings.assertions),
+                settings.monitor_type.as_str(),
+                labels_to_sql(&settings.labels),
+                settings.confirmations_down,
+                settings.confirmations_up,
+                settings.retry_interval,
+                settings.grace,
+                heartbeat.map(|heartbeat| &heartbeat.token),
+                heartbeat.and_then(|heartbeat| heartbeat.last_ping_at.as_ref()),
+                heartbeat.and_then(|heartbeat| heartbeat.started_at.as_ref()),
+                heartbeat.map(|heartbeat| &heartbeat.expected_at),
+            ],
+            url_from_row,
+        )
+        .optional()
+        .map(Ok)
+}
+
+pub fn save_certificate(
+    sqlite: &Connection,
+    url_id: i32,
+    certificate: &UrlCertificate,
 ) -> rusqlite::Result<()> {
-    let sqlite = sqlite.lock().unwrap();
+    let chain = serde_json::Value::Array(
+        certificate
+            .chain
+            .iter()
+            .map(CertificateInfo::to_json)
+            .collect(),
+    );
     sqlite.execute(
-        "INSERT INTO urls (url, status_code) VALUES (?1, ?2)",
-        (url, status_code),
+        "UPDATE urls SET certificate = ?2, certificate_checked_at = ?3 WHERE id = ?1",
+        (url_id, chain.to_string(), &certificate.checked_at),
     )?;
     Ok(())
 }
 
-pub fn update_url_status(sqlite: &Connection, id: i32, status_code: i32) -> rusqlite::Result<()> {
+pub fn save_heartbeat(
+    sqlite: &Connection,
+    url_id: i32,
+    heartbeat: Option<&Heartbeat>,
+) -> rusqlite::Result<()> {
     sqlite.execute(
-        "UPDATE urls SET status_code = ?1 WHERE id = ?2",
-        (status_code, id),
+        "UPDATE urls SET heartbeat_token = ?2, heartbeat_last_ping_at = ?3,
+         heartbeat_started_at = ?4, heartbeat_expected_at = ?5 WHERE id = ?1",
+        (
+            url_id,
+            heartbeat.map(|heartbeat| &heartbeat.token),
+            heartbeat.and_then(|heartbeat| heartbeat.last_ping_at.as_ref()),
+            heartbeat.and_then(|heartbeat| heartbeat.started_at.as_ref()),
+            heartbeat.map(|heartbeat| &heartbeat.expected_at),
+        ),
     )?;
     Ok(())
 }
 
-pub fn delete_url(sqlite: &Connection, id: i32) -> rusqlite::Result<()> {
-    sqlite.execute("DELETE FROM urls WHERE id = ?1", (id,))?;
-    Ok(())
+pub fn save_missed_heartbeat(
+    sqlite: &Connection,
+    url_id: i32,
+    expected_at: &str,
+    heartbeat: &Heartbeat,
+) -> rusqlite::Result<bool> {
+    let saved = sqlite.execute(
+        "UPDATE urls SET heartbeat_started_at = ?3, heartbeat_expected_at = ?4
+         WHERE id = ?1 AND heartbeat_expected_at = ?2",
+        (
+            url_id,
+            expected_at,
+            &heartbeat.started_at,
+            &heartbeat.expected_at,
+        ),
+    )?;
+    Ok(saved > 0)
+}
+
+/// `Err` holds the id of another monitor that already has this URL.
+pub fn rename_url(sqlite: &Connection, id: i32, url: &str) -> rusqlite::Result<Result<bool, i32>> {
+    if let Some(existing) = find_duplicate(sqlite, url, Some(id))? {
+        return Ok(Err(existing));
+    }
+    let renamed = sqlite.execute("UPDATE urls SET url = ?2 WHERE id = ?1", (id, url))?;
+    Ok(Ok(renamed > 0))
+}
+
+pub fn delete_url(sqlite: &Connection, id: i32) -> rusqlite::Result<bool> {
+    sqlite.execute("DELETE FROM url_checks WHERE url_id = ?1", (id,))?;
+    resolve_alert(sqlite, AlertSource::Monitor(id), "Monitor removido")?;
+    maintenance::forget_url(sqlite, id)?;
+    alerts::forget_url(sqlite, id)?;
+    escalations::forget_url(sqlite, id)?;
+    let deleted = sqlite.execute("DELETE FROM urls WHERE id = ?1", (id,))?;
+    Ok(deleted > 0)
 }
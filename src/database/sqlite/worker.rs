@@ -0,0 +1,263 @@
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use rusqlite::Connection;
+use tokio::sync::{mpsc, oneshot, Semaphore};
+
+use crate::database::error::{StorageError, StorageResult};
+
+/// Maximum number of queued writes committed together in one transaction.
+const WRITE_BATCH_SIZE: usize = 64;
+const WRITE_QUEUE_SIZE: usize = 1024;
+
+trait Job: Send {
+    /// Runs the job against the connection and reports whether it succeeded.
+    fn run(&mut self, conn: &Connection) -> bool;
+    /// Sends the result back to the caller once the batch is committed or rolled back.
+    fn finish(self: Box<Self>, commit_error: Option<&rusqlite::Error>);
+}
+
+struct PendingJob<T, F> {
+    task: Option<F>,
+    result: Option<rusqlite::Result<T>>,
+    reply: oneshot::Sender<StorageResult<T>>,
+}
+
+impl<T, F> Job for PendingJob<T, F>
+where
+    T: Send,
+    F: FnOnce(&Connection) -> rusqlite::Result<T> + Send,
+{
+    fn run(&mut self, conn: &Connection) -> bool {
+        let task = self.task.take().expect("job already ran");
+        let result = task(conn);
+        let ok = result.is_ok();
+        self.result = Some(result);
+        ok
+    }
+
+    fn finish(self: Box<Self>, commit_error: Option<&rusqlite::Error>) {
+        let result = match (self.result, commit_error) {
+            (Some(Err(e)), _) => Err(StorageError::Sqlite(e)),
+            (_, Some(e)) => Err(StorageError::Transaction(e.to_string())),
+            (Some(Ok(value)), None) => Ok(value),
+            (None, None) => Err(StorageError::Unavailable),
+        };
+        // The caller may have given up waiting, nothing to do then
+        let _ = self.reply.send(result);
+    }
+}
+
+struct ReaderPool {
+    connections: Mutex<Vec<Connection>>,
+    permits: Arc<Semaphore>,
+}
+
+/// Owns the SQLite connections: a single writer thread that batches queued writes into
+/// transactions, and a pool of read-only connections used from `spawn_blocking`.
+/// Without readers (e.g. `:memory:` databases) reads are queued on the writer too.
+#[derive(Clone)]
+pub struct Worker {
+    writer: mpsc::Sender<Box<dyn Job>>,
+    readers: Option<Arc<ReaderPool>>,
+}
+
+impl Worker {
+    pub fn start(writer: Connection, readers: Vec<Connection>) -> std::io::Result<Worker> {
+        let (sender, receiver) = mpsc::channel(WRITE_QUEUE_SIZE);
+        thread::Builder::new()
+            .name("sqlite-writer".into())
+            .spawn(move || run_writer(writer, receiver))?;
+
+        let readers = if readers.is_empty() {
+            None
+        } else {
+            Some(Arc::new(ReaderPool {
+                permits: Arc::new(Semaphore::new(readers.len())),
+                connections: Mutex::new(readers),
+            }))
+        };
+
+        Ok(Worker {
+            writer: sender,
+            readers,
+        })
+    }
+
+    /// Queues `task` on the writer thread. It runs inside its own savepoint, so a failing
+    /// task never rolls back the other writes batched with it.
+    pub async fn write<T, F>(&self, task: F) -> StorageResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let (reply, response) = oneshot::channel();
+        let job = PendingJob {
+            task: Some(task),
+            result: None,
+            reply,
+        };
+        self.writer
+            .send(Box::new(job))
+            .await
+            .map_err(|_| StorageError::Unavailable)?;
+        response.await.map_err(|_| StorageError::Unavailable)?
+    }
+
+    /// Runs `task` on a read-only connection without blocking the async runtime.
+    pub async fn read<T, F>(&self, task: F) -> StorageResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let Some(readers) = self.readers.clone() else {
+            return self.write(task).await;
+        };
+
+        let permit = readers
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| StorageError::Unavailable)?;
+
+        tokio::task::spawn_blocking(move || {
+            let conn = readers
+                .connections
+                .lock()
+                .unwrap()
+                .pop()
+                .expect("a permit guarantees a free reader");
+            let result = task(&conn);
+            readers.connections.lock().unwrap().push(conn);
+            drop(permit);
+            result
+        })
+        .await
+        .map_err(|_| StorageError::Unavailable)?
+        .map_err(StorageError::from)
+    }
+}
+
+fn run_writer(mut conn: Connection, mut receiver: mpsc::Receiver<Box<dyn Job>>) {
+    while let Some(first) = receiver.blocking_recv() {
+        let mut batch = vec![first];
+        while batch.len() < WRITE_BATCH_SIZE {
+            match receiver.try_recv() {
+                Ok(job) => batch.push(job),
+                Err(_) => break,
+            }
+        }
+
+        let commit_error = run_batch(&mut conn, &mut batch).err();
+        if let Some(e) = &commit_error {
+            eprintln!("Error committing {} queued writes: {}", batch.len(), e);
+        }
+        for job in batch {
+            job.finish(commit_error.as_ref());
+        }
+    }
+}
+
+fn run_batch(conn: &mut Connection, batch: &mut [Box<dyn Job>]) -> rusqlite::Result<()> {
+    let mut tx = conn.transaction()?;
+    for job in batch.iter_mut() {
+        let savepoint = tx.savepoint()?;
+        if job.run(&savepoint) {
+            savepoint.commit()?;
+        }
+        // An uncommitted savepoint rolls back on drop, undoing only the failed job
+    }
+    tx.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job<F>(task: F) -> (Box<dyn Job>, oneshot::Receiver<StorageResult<()>>)
+    where
+        F: FnOnce(&Connection) -> rusqlite::Result<()> + Send + 'static,
+    {
+        let (reply, response) = oneshot::channel();
+        let job = PendingJob {
+            task: Some(task),
+            result: None,
+            reply,
+        };
+        (Box::new(job), response)
+    }
+
+    fn insert(name: &'static str) -> impl FnOnce(&Connection) -> rusqlite::Result<()> {
+        move |conn| {
+            conn.execute("INSERT INTO names (name) VALUES (?1)", (name,))?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failing_write_does_not_roll_back_its_batch() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE names (name TEXT NOT NULL)")
+            .unwrap();
+        let (first, first_result) = job(insert("first"));
+        // Writes a row before failing, which must be undone along with it
+        let (failing, failing_result) = job(|conn| {
+            insert("failing")(conn)?;
+            conn.execute("INSERT INTO missing (name) VALUES ('x')", ())?;
+            Ok(())
+        });
+        let (last, last_result) = job(insert("last"));
+        let mut batch = vec![first, failing, last];
+
+        run_batch(&mut conn, &mut batch).unwrap();
+        for job in batch {
+            job.finish(None);
+        }
+
+        assert!(first_result.blocking_recv().unwrap().is_ok());
+        assert!(matches!(
+            failing_result.blocking_recv().unwrap(),
+            Err(StorageError::Sqlite(_))
+        ));
+        assert!(last_result.blocking_recv().unwrap().is_ok());
+        let mut stmt = conn
+            .prepare("SELECT name FROM names ORDER BY rowid")
+            .unwrap();
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(names, ["first", "last"]);
+    }
+
+    #[tokio::test]
+    async fn queued_writes_are_committed_around_a_failing_one() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE names (name TEXT NOT NULL)")
+            .unwrap();
+        let worker = Worker::start(conn, Vec::new()).unwrap();
+
+        let (first, failing, last) = tokio::join!(
+            worker.write(insert("first")),
+            worker.write(|conn| {
+                conn.execute("INSERT INTO missing (name) VALUES ('x')", ())?;
+                Ok(())
+            }),
+            worker.write(insert("last")),
+        );
+        assert!(first.is_ok());
+        assert!(failing.is_err());
+        assert!(last.is_ok());
+        let count = worker
+            .read(|conn| {
+                conn.query_row("SELECT COUNT(*) FROM names", [], |row| row.get::<_, i64>(0))
+            })
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+}
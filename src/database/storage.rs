@@ -0,0 +1,956 @@
+use std::{collections::BTreeMap, env, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+
+use crate::database::{
+    error::StorageResult,
+    memory::storage::MemoryStorage,
+    models::{
+        Alert, AlertCondition, AlertMatcher, AlertRule, AlertSource, AlertState, ChannelConfig,
+        CheckResult, CoreSample, CpuSample, DeliveryResult, Escalation, EscalationPolicy,
+        EscalationSettings, EventKind, Heartbeat, Incident, IncidentEvent, IncidentEventKind,
+        MaintenanceSettings, MaintenanceWindow, MonitorStatus, NotificationChannel,
+        NotificationDelivery, Silence, UrlCertificate, UrlCheck, UrlData, UrlSettings,
+    },
+    sqlite::{connection::DatabaseConfig, storage::SqliteStorage},
+};
+
+/// Persistence used by the HTTP handlers and the jobs. SQLite is the default backend;
+/// `MemoryStorage` keeps everything in process memory for tests and ephemeral use.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    // Metric samples
+    async fn save_cpu_info(
+        &self,
+        total_cpus: u32,
+        total_cpu_usage: f32,
+        cores_usage: Vec<f32>,
+    ) -> StorageResult<()>;
+    async fn get_cpu_history(&self) -> StorageResult<Vec<CpuSample>>;
+    async fn get_core_history(&self, core: u32) -> StorageResult<Vec<CoreSample>>;
+    /// Drops cpu samples older than five minutes.
+    async fn flush_cpu_info(&self) -> StorageResult<()>;
+
+    // URL monitors
+    async fn get_urls(&self) -> StorageResult<Vec<UrlData>>;
+    async fn get_url(&self, id: i32) -> StorageResult<Option<UrlData>>;
+    async fn get_url_by_heartbeat_token(&self, token: String) -> StorageResult<Option<UrlData>>;
+    /// Fails with `StorageError::Duplicate` when another monitor has the same URL. The ping
+    /// state of heartbeat monitors is stored in the same write.
+    async fn create_url(
+        &self,
+        url: String,
+        settings: UrlSettings,
+        heartbeat: Option<Heartbeat>,
+    ) -> StorageResult<UrlData>;
+    /// Replaces the URL, its settings and the ping state of heartbeat monitors in one write,
+    /// returning `None` when no monitor has this id. Fails with `StorageError::Duplicate`
+    /// when another monitor has the same URL.
+    async fn update_url(
+        &self,
+        id: i32,
+        url: String,
+        settings: UrlSettings,
+        heartbeat: Option<Heartbeat>,
+    ) -> StorageResult<Option<UrlData>>;
+    /// Rewrites the URL of a monitor into an equivalent form, keeping its status, alert and
+    /// history. Fails with `StorageError::Duplicate` when another monitor has the new form.
+    async fn rename_url(&self, id: i32, url: String) -> StorageResult<bool>;
+    /// Returns whether a monitor with this id existed. Its active alert is resolved, and the
+    /// silences and escalation policies targeting it are dropped.
+    async fn delete_url(&self, id: i32) -> StorageResult<bool>;
+    /// Replaces the certificate chain recorded for a monitor.
+    async fn save_certificate(&self, url_id: i32, certificate: UrlCertificate)
+        -> StorageResult<()>;
+    /// Replaces the ping state of a heartbeat monitor, `None` clearing it along with the token.
+    async fn save_heartbeat(&self, url_id: i32, heartbeat: Option<Heartbeat>) -> StorageResult<()>;
+    /// Records a missed ping by moving the deadline and ending a started run, unless the
+    /// deadline stored is no longer `expected_at`: a ping received since the monitor was
+    /// loaded moved it and is kept. Returns whether it was recorded.
+    async fn save_missed_heartbeat(
+        &self,
+        url_id: i32,
+        expected_at: String,
+        heartbeat: Heartbeat,
+    ) -> StorageResult<bool>;
+
+    // Check results
+    /// Stores a probe along with the resulting status of the monitor, which is left as it was
+    /// when the probe was taken during maintenance.
+    async fn save_check_result(
+        &self,
+        url_id: i32,
+        result: CheckResult,
+        status: MonitorStatus,
+    ) -> StorageResult<()>;
+    /// Newest first, optionally limited to checks between `from` and `to`.
+    async fn get_url_checks(
+        &self,
+        url_id: i32,
+        from: Option<NaiveDateTime>,
+        to: Option<NaiveDateTime>,
+        limit: u32,
+    ) -> StorageResult<Vec<UrlCheck>>;
+    /// Drops checks older than `before`.
+    async fn flush_url_checks(&self, before: NaiveDateTime) -> StorageResult<()>;
+
+    // Alert rules
+    async fn get_alert_rules(&self) -> StorageResult<Vec<AlertRule>>;
+    async fn get_alert_rule(&self, id: i64) -> StorageResult<Option<AlertRule>>;
+    async fn create_alert_rule(
+        &self,
+        name: String,
+        condition: AlertCondition,
+        labels: BTreeMap<String, String>,
+    ) -> StorageResult<AlertRule>;
+    /// Replaces the rule, resetting its state and resolving its active alert when the
+    /// condition changes.
+    async fn update_alert_rule(
+        &self,
+        id: i64,
+        name: String,
+        condition: AlertCondition,
+        labels: BTreeMap<String, String>,
+    ) -> StorageResult<Option<AlertRule>>;
+    /// Also resolves the active alert of the rule and drops the silences and escalation
+    /// policies targeting it.
+    async fn delete_alert_rule(&self, id: i64) -> StorageResult<bool>;
+    /// Records an evaluation, `state_changed_at` only moves when the state differs.
+    async fn save_alert_state(&self, id: i64, state: AlertState, value: f64) -> StorageResult<()>;
+
+    // Alerts raised by rules and monitors
+    /// Starts an alert for the source, `None` when it already has an active one. Its incident
+    /// is opened with `detail` as what was detected.
+    async fn open_alert(
+        &self,
+        source: AlertSource,
+        name: String,
+        labels: BTreeMap<String, String>,
+        detail: String,
+    ) -> StorageResult<Option<Alert>>;
+    /// Resolves the active alert of the source and its incident, returning the alert if there
+    /// was one. `detail` is added to the incident timeline.
+    async fn resolve_alert(
+        &self,
+        source: AlertSource,
+        detail: String,
+    ) -> StorageResult<Option<Alert>>;
+    /// Newest first, only the active or resolved ones when `active` is given.
+    async fn get_alerts(&self, active: Option<bool>, limit: u32) -> StorageResult<Vec<Alert>>;
+    async fn get_alert(&self, id: i64) -> StorageResult<Option<Alert>>;
+    /// Also acknowledges the incident of the alert, the comment going to its timeline. `None`
+    /// when no active alert waiting for acknowledgement has this id, so only one of concurrent
+    /// acknowledgements succeeds.
+    async fn acknowledge_alert(
+        &self,
+        id: i64,
+        comment: Option<String>,
+    ) -> StorageResult<Option<Alert>>;
+    /// Drops alerts resolved before `before`, their escalations, and silences that expired
+    /// before it.
+    async fn flush_alerts(&self, before: NaiveDateTime) -> StorageResult<()>;
+
+    // Incidents
+    /// Newest first, only the open or resolved ones when `open` is given and the ones of a
+    /// rule or monitor when `source` is.
+    async fn get_incidents(
+        &self,
+        open: Option<bool>,
+        source: Option<AlertSource>,
+        limit: u32,
+    ) -> StorageResult<Vec<Incident>>;
+    async fn get_incident(&self, id: i64) -> StorageResult<Option<Incident>>;
+    async fn get_alert_incident(&self, alert_id: i64) -> StorageResult<Option<Incident>>;
+    /// The timeline of the incident, oldest first.
+    async fn get_incident_events(&self, incident_id: i64) -> StorageResult<Vec<IncidentEvent>>;
+    /// Returns `None` when no incident has this id.
+    async fn add_incident_event(
+        &self,
+        incident_id: i64,
+        kind: IncidentEventKind,
+        message: String,
+    ) -> StorageResult<Option<IncidentEvent>>;
+    /// Drops incidents resolved before `before` along with their timelines.
+    async fn flush_incidents(&self, before: NaiveDateTime) -> StorageResult<()>;
+
+    // Silences
+    /// The silences still in effect at `at`, or all of them when `at` is `None`.
+    async fn get_silences(&self, at: Option<NaiveDateTime>) -> StorageResult<Vec<Silence>>;
+    async fn get_silence(&self, id: i64) -> StorageResult<Option<Silence>>;
+    async fn create_silence(
+        &self,
+        matcher: AlertMatcher,
+        comment: String,
+        expires_at: NaiveDateTime,
+    ) -> StorageResult<Silence>;
+    async fn delete_silence(&self, id: i64) -> StorageResult<bool>;
+
+    // Escalation policies
+    async fn get_escalation_policies(&self) -> StorageResult<Vec<EscalationPolicy>>;
+    async fn get_escalation_policy(&self, id: i64) -> StorageResult<Option<EscalationPolicy>>;
+    async fn create_escalation_policy(
+        &self,
+        settings: EscalationSettings,
+    ) -> StorageResult<EscalationPolicy>;
+    /// Returns `None` when no policy has this id. Escalations in progress keep their step.
+    async fn update_escalation_policy(
+        &self,
+        id: i64,
+        settings: EscalationSettings,
+    ) -> StorageResult<Option<EscalationPolicy>>;
+    /// Also stops the escalations in progress of the policy.
+    async fn delete_escalation_policy(&self, id: i64) -> StorageResult<bool>;
+
+    // Escalations of active alerts
+    /// Starts the policy on the alert at its first step, due at `due_at`.
+    async fn start_escalation(
+        &self,
+        alert_id: i64,
+        policy_id: i64,
+        due_at: NaiveDateTime,
+        notification: serde_json::Value,
+    ) -> StorageResult<Escalation>;
+    /// Soonest due first, only the ones of an alert when `alert_id` is given.
+    async fn get_escalations(&self, alert_id: Option<i64>) -> StorageResult<Vec<Escalation>>;
+    async fn advance_escalation(
+        &self,
+        id: i64,
+        step: u32,
+        due_at: NaiveDateTime,
+    ) -> StorageResult<()>;
+    async fn delete_escalation(&self, id: i64) -> StorageResult<bool>;
+
+    // Maintenance windows
+    async fn get_maintenance_windows(&self) -> StorageResult<Vec<MaintenanceWindow>>;
+    async fn get_maintenance_window(&self, id: i64) -> StorageResult<Option<MaintenanceWindow>>;
+    async fn create_maintenance_window(
+        &self,
+        settings: MaintenanceSettings,
+    ) -> StorageResult<MaintenanceWindow>;
+    /// Returns `None` when no window has this id.
+    async fn update_maintenance_window(
+        &self,
+        id: i64,
+        settings: MaintenanceSettings,
+    ) -> StorageResult<Option<MaintenanceWindow>>;
+    async fn delete_maintenance_window(&self, id: i64) -> StorageResult<bool>;
+
+    // Notification channels
+    async fn get_channels(&self) -> StorageResult<Vec<NotificationChannel>>;
+    async fn get_channel(&self, id: i64) -> StorageResult<Option<NotificationChannel>>;
+    async fn create_channel(
+        &self,
+        name: String,
+        config: ChannelConfig,
+        events: Vec<EventKind>,
+        enabled: bool,
+    ) -> StorageResult<NotificationChannel>;
+    async fn update_channel(
+        &self,
+        id: i64,
+        name: String,
+        config: ChannelConfig,
+        events: Vec<EventKind>,
+        enabled: bool,
+    ) -> StorageResult<Option<NotificationChannel>>;
+    /// Also drops the channel's delivery log and takes it out of escalation steps.
+    async fn delete_channel(&self, id: i64) -> StorageResult<bool>;
+
+    // Delivery log
+    async fn save_delivery(&self, channel_id: i64, result: DeliveryResult) -> StorageResult<()>;
+    /// Newest first, optionally only the deliveries of one channel.
+    async fn get_deliveries(
+        &self,
+        channel_id: Option<i64>,
+        limit: u32,
+    ) -> StorageResult<Vec<NotificationDelivery>>;
+    /// Drops deliveries older than `before`.
+    async fn flush_deliveries(&self, before: NaiveDateTime) -> StorageResult<()>;
+}
+
+pub type SharedStorage = Arc<dyn Storage>;
+
+/// Opens the backend selected by `STORAGE_BACKEND` (`sqlite` or `memory`).
+pub async fn open_storage() -> Result<SharedStorage, Box<dyn std::error::Error>> {
+    let backend = env::var("STORAGE_BACKEND").unwrap_or("sqlite".into());
+    match backend.as_str() {
+        "sqlite" => {
+            let config = DatabaseConfig::from_env()?;
+            Ok(Arc::new(SqliteStorage::open(&config).await?))
+        }
+        "memory" => {
+            println!("Using in-memory storage, data is lost on restart");
+            Ok(Arc::new(MemoryStorage::new()))
+        }
+        other => Err(format!("Unknown STORAGE_BACKEND: {}", other).into()),
+    }
+}
+
+/// Scenarios run against every backend, so `MemoryStorage` keeps behaving like SQLite.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{
+        error::StorageError,
+        models::{CheckOutcome, Comparison, Metric, MonitorType},
+        sqlite::connection,
+        time::now,
+    };
+
+    async fn backends() -> Vec<(&'static str, SharedStorage)> {
+        let config = DatabaseConfig {
+            path: connection::MEMORY_PATH.into(),
+            journal_mode: "MEMORY".into(),
+            synchronous: "OFF".into(),
+            busy_timeout: std::time::Duration::from_secs(1),
+            readers: 0,
+        };
+        vec![
+            ("memory", Arc::new(MemoryStorage::new())),
+            (
+                "sqlite",
+                Arc::new(SqliteStorage::open(&config).await.unwrap()),
+            ),
+        ]
+    }
+
+    fn check(up: bool, status_code: i32) -> CheckResult {
+        CheckResult {
+            status_code: Some(status_code),
+            up,
+            outcome: if up {
+                CheckOutcome::Ok
+            } else {
+                CheckOutcome::HttpError
+            },
+            response_time_ms: 12,
+            response_size: Some(2),
+            error: None,
+            failed_assertion: None,
+            maintenance: false,
+        }
+    }
+
+    fn status(up: bool, unconfirmed_checks: u32) -> MonitorStatus {
+        MonitorStatus {
+            up: Some(up),
+            unconfirmed_checks,
+            flapping: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn urls_are_created_updated_and_kept_unique() {
+        for (backend, storage) in backends().await {
+            let first = storage
+                .create_url("https://a.example/".into(), UrlSettings::default(), None)
+                .await
+                .unwrap();
+            assert_eq!(first.url, "https://a.example/", "{}", backend);
+            assert_eq!(first.up, None, "{}", backend);
+
+            let duplicate = storage
+                .create_url("https://a.example/".into(), UrlSettings::default(), None)
+                .await;
+            assert!(
+                matches!(duplicate, Err(StorageError::Duplicate(id)) if id == first.id),
+                "{}",
+                backend
+            );
+
+            let second = storage
+                .create_url("https://b.example/".into(), UrlSettings::default(), None)
+                .await
+                .unwrap();
+            assert_ne!(second.id, first.id, "{}", backend);
+            let taken = storage
+                .update_url(
+                    second.id,
+                    "https://a.example/".into(),
+                    UrlSettings::default(),
+                    None,
+                )
+                .await;
+            assert!(
+                matches!(taken, Err(StorageError::Duplicate(id)) if id == first.id),
+                "{}",
+                backend
+            );
+
+            let settings = UrlSettings {
+                interval: 300,
+                method: "HEAD".into(),
+                ..UrlSettings::default()
+            };
+            let updated = storage
+                .update_url(second.id, "https://c.example/".into(), settings, None)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(updated.url, "https://c.example/", "{}", backend);
+            assert_eq!(updated.settings.interval, 300, "{}", backend);
+            assert_eq!(updated.settings.method, "HEAD", "{}", backend);
+            let reloaded = storage.get_url(second.id).await.unwrap().unwrap();
+            assert_eq!(reloaded.url, "https://c.example/", "{}", backend);
+            assert_eq!(reloaded.settings.interval, 300, "{}", backend);
+
+            let missing = storage
+                .update_url(
+                    999,
+                    "https://d.example/".into(),
+                    UrlSettings::default(),
+                    None,
+                )
+                .await
+                .unwrap();
+            assert!(missing.is_none(), "{}", backend);
+
+            assert!(storage.delete_url(first.id).await.unwrap(), "{}", backend);
+            assert!(!storage.delete_url(first.id).await.unwrap(), "{}", backend);
+            assert!(
+                storage.get_url(first.id).await.unwrap().is_none(),
+                "{}",
+                backend
+            );
+            // The URL is free again once its monitor is gone
+            storage
+                .create_url("https://a.example/".into(), UrlSettings::default(), None)
+                .await
+                .unwrap();
+            assert_eq!(storage.get_urls().await.unwrap().len(), 2, "{}", backend);
+        }
+    }
+
+    #[tokio::test]
+    async fn renaming_a_url_keeps_its_status() {
+        for (backend, storage) in backends().await {
+            let first = storage
+                .create_url("HTTPS://A.example".into(), UrlSettings::default(), None)
+                .await
+                .unwrap();
+            let second = storage
+                .create_url("https://b.example/".into(), UrlSettings::default(), None)
+                .await
+                .unwrap();
+            storage
+                .save_check_result(first.id, check(false, 500), status(false, 0))
+                .await
+                .unwrap();
+
+            let renamed = storage
+                .rename_url(first.id, "https://a.example/".into())
+                .await
+                .unwrap();
+            assert!(renamed, "{}", backend);
+            let monitor = storage.get_url(first.id).await.unwrap().unwrap();
+            assert_eq!(monitor.url, "https://a.example/", "{}", backend);
+            assert_eq!(monitor.up, Some(false), "{}", backend);
+            assert_eq!(monitor.status_code, Some(500), "{}", backend);
+
+            let taken = storage
+                .rename_url(second.id, "https://a.example/".into())
+                .await;
+            assert!(
+                matches!(taken, Err(StorageError::Duplicate(id)) if id == first.id),
+                "{}",
+                backend
+            );
+            let missing = storage
+                .rename_url(999, "https://c.example/".into())
+                .await
+                .unwrap();
+            assert!(!missing, "{}", backend);
+        }
+    }
+
+    #[tokio::test]
+    async fn deleting_a_url_drops_its_silences_and_escalation_policies() {
+        for (backend, storage) in backends().await {
+            let deleted = storage
+                .create_url("https://a.example/".into(), UrlSettings::default(), None)
+                .await
+                .unwrap();
+            let kept = storage
+                .create_url("https://b.example/".into(), UrlSettings::default(), None)
+                .await
+                .unwrap();
+            let matcher = |url_id: i32| AlertMatcher {
+                url_id: Some(url_id),
+                ..AlertMatcher::default()
+            };
+            let expires_at = now() + chrono::TimeDelta::hours(1);
+            for url_id in [deleted.id, kept.id] {
+                storage
+                    .create_silence(matcher(url_id), "Deploy".into(), expires_at)
+                    .await
+                    .unwrap();
+                storage
+                    .create_escalation_policy(EscalationSettings {
+                        name: format!("url {}", url_id),
+                        matcher: matcher(url_id),
+                        steps: Vec::new(),
+                    })
+                    .await
+                    .unwrap();
+            }
+            let alert = storage
+                .open_alert(
+                    AlertSource::Monitor(deleted.id),
+                    deleted.url.clone(),
+                    BTreeMap::new(),
+                    "Fora do ar".into(),
+                )
+                .await
+                .unwrap()
+                .unwrap();
+            let policies = storage.get_escalation_policies().await.unwrap();
+            storage
+                .start_escalation(alert.id, policies[0].id, expires_at, serde_json::json!({}))
+                .await
+                .unwrap();
+
+            assert!(storage.delete_url(deleted.id).await.unwrap(), "{}", backend);
+            let silences = storage.get_silences(None).await.unwrap();
+            assert_eq!(silences.len(), 1, "{}", backend);
+            assert_eq!(silences[0].matcher.url_id, Some(kept.id), "{}", backend);
+            let policies = storage.get_escalation_policies().await.unwrap();
+            assert_eq!(policies.len(), 1, "{}", backend);
+            assert_eq!(
+                policies[0].settings.matcher.url_id,
+                Some(kept.id),
+                "{}",
+                backend
+            );
+            assert!(
+                storage.get_escalations(None).await.unwrap().is_empty(),
+                "{}",
+                backend
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn deleting_a_rule_drops_its_silences_and_escalation_policies() {
+        for (backend, storage) in backends().await {
+            let condition = AlertCondition {
+                metric: Metric::TotalCpuUsage,
+                comparison: Comparison::Above,
+                threshold: 90.0,
+                duration: 0,
+            };
+            let deleted = storage
+                .create_alert_rule("cpu".into(), condition, BTreeMap::new())
+                .await
+                .unwrap();
+            let kept = storage
+                .create_alert_rule("cpu alta".into(), condition, BTreeMap::new())
+                .await
+                .unwrap();
+            let matcher = |rule_id: i64| AlertMatcher {
+                rule_id: Some(rule_id),
+                ..AlertMatcher::default()
+            };
+            let expires_at = now() + chrono::TimeDelta::hours(1);
+            for rule_id in [deleted.id, kept.id] {
+                storage
+                    .create_silence(matcher(rule_id), "Manutenção".into(), expires_at)
+                    .await
+                    .unwrap();
+                storage
+                    .create_escalation_policy(EscalationSettings {
+                        name: format!("regra {}", rule_id),
+                        matcher: matcher(rule_id),
+                        steps: Vec::new(),
+                    })
+                    .await
+                    .unwrap();
+            }
+            let alert = storage
+                .open_alert(
+                    AlertSource::Rule(deleted.id),
+                    deleted.name.clone(),
+                    BTreeMap::new(),
+                    "CPU acima de 90%".into(),
+                )
+                .await
+                .unwrap()
+                .unwrap();
+            let policies = storage.get_escalation_policies().await.unwrap();
+            storage
+                .start_escalation(alert.id, policies[0].id, expires_at, serde_json::json!({}))
+                .await
+                .unwrap();
+
+            assert!(
+                storage.delete_alert_rule(deleted.id).await.unwrap(),
+                "{}",
+                backend
+            );
+            let silences = storage.get_silences(None).await.unwrap();
+            assert_eq!(silences.len(), 1, "{}", backend);
+            assert_eq!(silences[0].matcher.rule_id, Some(kept.id), "{}", backend);
+            let policies = storage.get_escalation_policies().await.unwrap();
+            assert_eq!(policies.len(), 1, "{}", backend);
+            assert_eq!(
+                policies[0].settings.matcher.rule_id,
+                Some(kept.id),
+                "{}",
+                backend
+            );
+            assert!(
+                storage.get_escalations(None).await.unwrap().is_empty(),
+                "{}",
+                backend
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn heartbeat_is_created_with_its_monitor() {
+        for (backend, storage) in backends().await {
+            let settings = UrlSettings {
+                monitor_type: MonitorType::Heartbeat,
+                ..UrlSettings::default()
+            };
+            let heartbeat = Heartbeat {
+                token: "abc123".into(),
+                last_ping_at: None,
+                started_at: None,
+                expected_at: "2024-05-01 00:02:00".into(),
+            };
+            let created = storage
+                .create_url("heartbeat://backup".into(), settings, Some(heartbeat))
+                .await
+                .unwrap();
+            let created_heartbeat = created.heartbeat.unwrap();
+            assert_eq!(created_heartbeat.token, "abc123", "{}", backend);
+            assert_eq!(
+                created_heartbeat.expected_at, "2024-05-01 00:02:00",
+                "{}",
+                backend
+            );
+
+            let found = storage
+                .get_url_by_heartbeat_token("abc123".into())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(found.id, created.id, "{}", backend);
+
+            let updated = storage
+                .update_url(
+                    created.id,
+                    "https://backup.example/".into(),
+                    UrlSettings::default(),
+                    None,
+                )
+                .await
+                .unwrap()
+                .unwrap();
+            assert!(updated.heartbeat.is_none(), "{}", backend);
+            let gone = storage
+                .get_url_by_heartbeat_token("abc123".into())
+                .await
+                .unwrap();
+            assert!(gone.is_none(), "{}", backend);
+        }
+    }
+
+    #[tokio::test]
+    async fn missed_heartbeat_does_not_overwrite_a_newer_ping() {
+        for (backend, storage) in backends().await {
+            let settings = UrlSettings {
+                monitor_type: MonitorType::Heartbeat,
+                ..UrlSettings::default()
+            };
+            let heartbeat = Heartbeat {
+                token: "abc123".into(),
+                last_ping_at: None,
+                started_at: Some("2024-05-01 00:00:00".into()),
+                expected_at: "2024-05-01 00:02:00".into(),
+            };
+            let url = storage
+                .create_url(
+                    "heartbeat://backup".into(),
+                    settings,
+                    Some(heartbeat.clone()),
+                )
+                .await
+                .unwrap();
+            let missed = Heartbeat {
+                started_at: None,
+                expected_at: "2024-05-01 00:03:00".into(),
+                ..heartbeat.clone()
+            };
+
+            // A ping moved the deadline after the monitor was loaded
+            let pinged = Heartbeat {
+                last_ping_at: Some("2024-05-01 00:02:30".into()),
+                started_at: None,
+                expected_at: "2024-05-01 00:04:30".into(),
+                ..heartbeat.clone()
+            };
+            storage.save_heartbeat(url.id, Some(pinged)).await.unwrap();
+            let saved = storage
+                .save_missed_heartbeat(url.id, heartbeat.expected_at.clone(), missed.clone())
+                .await
+                .unwrap();
+            assert!(!saved, "{}", backend);
+            let kept = storage.get_url(url.id).await.unwrap().unwrap();
+            let kept = kept.heartbeat.unwrap();
+            assert_eq!(kept.expected_at, "2024-05-01 00:04:30", "{}", backend);
+            assert_eq!(
+                kept.last_ping_at.as_deref(),
+                Some("2024-05-01 00:02:30"),
+                "{}",
+                backend
+            );
+
+            let saved = storage
+                .save_missed_heartbeat(url.id, kept.expected_at.clone(), missed)
+                .await
+                .unwrap();
+            assert!(saved, "{}", backend);
+            let heartbeat = storage.get_url(url.id).await.unwrap().unwrap().heartbeat;
+            let heartbeat = heartbeat.unwrap();
+            assert_eq!(heartbeat.expected_at, "2024-05-01 00:03:00", "{}", backend);
+            assert_eq!(heartbeat.started_at, None, "{}", backend);
+        }
+    }
+
+    #[tokio::test]
+    async fn check_history_is_newest_first_and_updates_the_monitor() {
+        for (backend, storage) in backends().await {
+            let url = storage
+                .create_url("https://a.example/".into(), UrlSettings::default(), None)
+                .await
+                .unwrap();
+            storage
+                .save_check_result(url.id, check(true, 200), status(true, 0))
+                .await
+                .unwrap();
+            // Down but not confirmed yet: the monitor stays up with the last agreeing status
+            storage
+                .save_check_result(url.id, check(false, 503), status(true, 1))
+                .await
+                .unwrap();
+
+            let monitor = storage.get_url(url.id).await.unwrap().unwrap();
+            assert_eq!(monitor.up, Some(true), "{}", backend);
+            assert_eq!(monitor.status_code, Some(200), "{}", backend);
+            assert_eq!(monitor.unconfirmed_checks, 1, "{}", backend);
+
+            storage
+                .save_check_result(url.id, check(false, 500), status(false, 0))
+                .await
+                .unwrap();
+            let monitor = storage.get_url(url.id).await.unwrap().unwrap();
+            assert_eq!(monitor.up, Some(false), "{}", backend);
+            assert_eq!(monitor.status_code, Some(500), "{}", backend);
+            assert_eq!(
+                monitor.outcome,
+                Some(CheckOutcome::HttpError),
+                "{}",
+                backend
+            );
+            assert_eq!(monitor.unconfirmed_checks, 0, "{}", backend);
+
+            let checks = storage
+                .get_url_checks(url.id, None, None, 100)
+                .await
+                .unwrap();
+            let codes = checks
+                .iter()
+                .map(|check| check.result.status_code)
+                .collect::<Vec<_>>();
+            assert_eq!(codes, [Some(500), Some(503), Some(200)], "{}", backend);
+            assert!(checks[0].id > checks[1].id, "{}", backend);
+            assert_eq!(checks[0].result.response_time_ms, 12, "{}", backend);
+
+            let latest = storage.get_url_checks(url.id, None, None, 1).await.unwrap();
+            assert_eq!(latest.len(), 1, "{}", backend);
+            assert_eq!(latest[0].result.status_code, Some(500), "{}", backend);
+
+            let later = now() + chrono::TimeDelta::minutes(1);
+            let future = storage
+                .get_url_checks(url.id, Some(later), None, 100)
+                .await
+                .unwrap();
+            assert!(future.is_empty(), "{}", backend);
+
+            // Checks taken during maintenance are kept but leave the monitor alone
+            let mut maintenance = check(true, 200);
+            maintenance.maintenance = true;
+            storage
+                .save_check_result(url.id, maintenance, status(true, 0))
+                .await
+                .unwrap();
+            let monitor = storage.get_url(url.id).await.unwrap().unwrap();
+            assert_eq!(monitor.up, Some(false), "{}", backend);
+            let checks = storage
+                .get_url_checks(url.id, None, None, 100)
+                .await
+                .unwrap();
+            assert_eq!(checks.len(), 4, "{}", backend);
+            assert!(checks[0].result.maintenance, "{}", backend);
+
+            storage.flush_url_checks(later).await.unwrap();
+            let checks = storage
+                .get_url_checks(url.id, None, None, 100)
+                .await
+                .unwrap();
+            assert!(checks.is_empty(), "{}", backend);
+        }
+    }
+
+    #[tokio::test]
+    async fn alerts_open_acknowledge_and_resolve_with_their_incident() {
+        for (backend, storage) in backends().await {
+            let url = storage
+                .create_url("https://a.example/".into(), UrlSettings::default(), None)
+                .await
+                .unwrap();
+            let source = AlertSource::Monitor(url.id);
+            let labels = BTreeMap::from([("team".to_string(), "web".to_string())]);
+
+            let alert = storage
+                .open_alert(source, url.url.clone(), labels.clone(), "Fora do ar".into())
+                .await
+                .unwrap()
+                .unwrap();
+            assert!(alert.is_active(), "{}", backend);
+            assert_eq!(alert.labels, labels, "{}", backend);
+            let again = storage
+                .open_alert(source, url.url.clone(), labels, "Fora do ar".into())
+                .await
+                .unwrap();
+            assert!(again.is_none(), "{}", backend);
+
+            let active = storage.get_alerts(Some(true), 100).await.unwrap();
+            assert_eq!(active.len(), 1, "{}", backend);
+            assert_eq!(active[0].id, alert.id, "{}", backend);
+
+            let incident = storage.get_alert_incident(alert.id).await.unwrap().unwrap();
+            assert!(incident.is_open(), "{}", backend);
+            assert_eq!(incident.source, source, "{}", backend);
+            assert_eq!(incident.name, url.url, "{}", backend);
+
+            let acknowledged = storage
+                .acknowledge_alert(alert.id, Some("Investigando".into()))
+                .await
+                .unwrap()
+                .unwrap();
+            assert!(acknowledged.acknowledged_at.is_some(), "{}", backend);
+            assert_eq!(
+                acknowledged.ack_comment.as_deref(),
+                Some("Investigando"),
+                "{}",
+                backend
+            );
+            let incident = storage.get_incident(incident.id).await.unwrap().unwrap();
+            assert!(incident.acknowledged_at.is_some(), "{}", backend);
+            let twice = storage
+                .acknowledge_alert(alert.id, Some("De novo".into()))
+                .await
+                .unwrap();
+            assert!(twice.is_none(), "{}", backend);
+
+            let comment = storage
+                .add_incident_event(
+                    incident.id,
+                    IncidentEventKind::Comment,
+                    "Disco cheio".into(),
+                )
+                .await
+                .unwrap();
+            assert!(comment.is_some(), "{}", backend);
+            let unknown = storage
+                .add_incident_event(999, IncidentEventKind::Comment, "Nada".into())
+                .await
+                .unwrap();
+            assert!(unknown.is_none(), "{}", backend);
+
+            let resolved = storage
+                .resolve_alert(source, "Voltou".into())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(resolved.id, alert.id, "{}", backend);
+            assert!(!resolved.is_active(), "{}", backend);
+            let none_left = storage
+                .resolve_alert(source, "Voltou".into())
+                .await
+                .unwrap();
+            assert!(none_left.is_none(), "{}", backend);
+            let after_resolved = storage.acknowledge_alert(alert.id, None).await.unwrap();
+            assert!(after_resolved.is_none(), "{}", backend);
+            assert!(
+                storage
+                    .get_alerts(Some(true), 100)
+                    .await
+                    .unwrap()
+                    .is_empty(),
+                "{}",
+                backend
+            );
+            assert_eq!(
+                storage.get_alerts(Some(false), 100).await.unwrap().len(),
+                1,
+                "{}",
+                backend
+            );
+
+            let incident = storage.get_incident(incident.id).await.unwrap().unwrap();
+            assert!(!incident.is_open(), "{}", backend);
+            let timeline = storage.get_incident_events(incident.id).await.unwrap();
+            let kinds = timeline.iter().map(|event| event.kind).collect::<Vec<_>>();
+            assert_eq!(
+                kinds,
+                [
+                    IncidentEventKind::Detected,
+                    IncidentEventKind::Acknowledged,
+                    IncidentEventKind::Comment,
+                    IncidentEventKind::Resolved,
+                ],
+                "{}",
+                backend
+            );
+            assert_eq!(timeline[0].message, "Fora do ar", "{}", backend);
+            assert_eq!(timeline[1].message, "Investigando", "{}", backend);
+            assert_eq!(timeline[3].message, "Voltou", "{}", backend);
+
+            let open = storage
+                .get_incidents(Some(true), Some(source), 100)
+                .await
+                .unwrap();
+            assert!(open.is_empty(), "{}", backend);
+
+            // A new outage starts a new alert and incident, and deleting the monitor ends it
+            let next = storage
+                .open_alert(
+                    source,
+                    url.url.clone(),
+                    BTreeMap::new(),
+                    "Fora do ar".into(),
+                )
+                .await
+                .unwrap()
+                .unwrap();
+            assert_ne!(next.id, alert.id, "{}", backend);
+            assert!(storage.delete_url(url.id).await.unwrap(), "{}", backend);
+            let next = storage.get_alert(next.id).await.unwrap().unwrap();
+            assert!(!next.is_active(), "{}", backend);
+            let incidents = storage
+                .get_incidents(None, Some(source), 100)
+                .await
+                .unwrap();
+            assert_eq!(incidents.len(), 2, "{}", backend);
+            assert!(
+                incidents.iter().all(|incident| !incident.is_open()),
+                "{}",
+                backend
+            );
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+/// Same layout SQLite uses for CURRENT_TIMESTAMP (UTC), so timestamps compare as strings.
+pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub fn now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
+
+pub fn format_timestamp(timestamp: &NaiveDateTime) -> String {
+    timestamp.format(TIMESTAMP_FORMAT).to_string()
+}
+
+/// Parses RFC 3339 timestamps, or `YYYY-MM-DD[ HH:MM:SS]` / `YYYY-MM-DDTHH:MM:SS` taken as UTC.
+pub fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Some(timestamp.naive_utc());
+    }
+    for format in [TIMESTAMP_FORMAT, "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(timestamp) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(timestamp);
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+}
+
+/// Writes seconds as `1d 2h 3m 4s`, leaving out empty units, in the syntax read by the
+/// alert expressions.
+pub fn format_duration(seconds: i64) -> String {
+    let units = [(86400, "d"), (3600, "h"), (60, "m"), (1, "s")];
+    let mut remaining = seconds.max(0);
+    let mut parts = Vec::new();
+    for (size, unit) in units {
+        if remaining >= size {
+            parts.push(format!("{}{}", remaining / size, unit));
+            remaining %= size;
+        }
+    }
+    if parts.is_empty() {
+        "0s".into()
+    } else {
+        parts.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_duration_splits_units() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(-5), "0s");
+        assert_eq!(format_duration(90), "1m 30s");
+        assert_eq!(format_duration(3600), "1h");
+        assert_eq!(format_duration(93784), "1d 2h 3m 4s");
+    }
+}
@@ -0,0 +1,42 @@
+use std::env;
+
+use chrono::TimeDelta;
+
+use crate::database::{storage::SharedStorage, time::now};
+
+pub fn clear_database(storage: SharedStorage) {
+    let retention_days = env::var("CHECK_RETENTION_DAYS")
+        .ok()
+        .and_then(|days| days.parse::<i64>().ok())
+        .unwrap_or(30);
+    // incidents are kept longer, they are the record for post-mortems
+    let incident_retention_days = env::var("INCIDENT_RETENTION_DAYS")
+        .ok()
+        .and_then(|days| days.parse::<i64>().ok())
+        .unwrap_or(365);
+
+    // set interval to 5 minutes flush cpu info
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(300));
+        loop {
+            interval.tick().await;
+            if let Err(e) = storage.flush_cpu_info().await {
+                eprintln!("Error flushing CPU info: {}", e);
+            }
+            let before = now() - TimeDelta::days(retention_days);
+            if let Err(e) = storage.flush_url_checks(before).await {
+                eprintln!("Error flushing URL checks: {}", e);
+            }
+            if let Err(e) = storage.flush_deliveries(before).await {
+                eprintln!("Error flushing notification deliveries: {}", e);
+            }
+            if let Err(e) = storage.flush_alerts(before).await {
+                eprintln!("Error flushing alerts: {}", e);
+            }
+            let before = now() - TimeDelta::days(incident_retention_days);
+            if let Err(e) = storage.flush_incidents(before).await {
+                eprintln!("Error flushing incidents: {}", e);
+            }
+        }
+    });
+}
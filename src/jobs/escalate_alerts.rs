@@ -0,0 +1,19 @@
+use std::time::Duration;
+
+use crate::{
+    database::storage::SharedStorage,
+    services::{alerts::escalation, notifications::notifier::Notifier},
+};
+
+/// How often due escalation steps are looked for, which bounds how late a step can be.
+const ESCALATION_INTERVAL: Duration = Duration::from_secs(5);
+
+pub fn escalate_alerts(storage: SharedStorage, notifier: Notifier) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ESCALATION_INTERVAL);
+        loop {
+            interval.tick().await;
+            escalation::run_due(&storage, &notifier).await;
+        }
+    });
+}
@@ -0,0 +1,86 @@
+use std::{env, time::Duration};
+
+use crate::{
+    database::{
+        models::{AlertSource, AlertState},
+        storage::SharedStorage,
+        time::now,
+    },
+    services::{
+        alerts::{
+            evaluation::next_state, expression::format_condition, lifecycle, metrics::MetricSampler,
+        },
+        notifications::{notification::Notification, notifier::Notifier},
+    },
+};
+
+pub fn evaluate_alerts(storage: SharedStorage, notifier: Notifier) {
+    let every = env::var("ALERT_EVALUATION_INTERVAL")
+        .ok()
+        .and_then(|seconds| seconds.parse::<u64>().ok())
+        .filter(|seconds| *seconds > 0)
+        .unwrap_or(15);
+
+    tokio::spawn(async move {
+        let mut sampler = MetricSampler::new();
+        let mut interval = tokio::time::interval(Duration::from_secs(every));
+        // The first tick completes immediately, before any CPU usage can be measured
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let rules = match storage.get_alert_rules().await {
+                Ok(rules) => rules,
+                Err(e) => {
+                    eprintln!("Error loading alert rules: {}", e);
+                    continue;
+                }
+            };
+            let metrics = sampler.sample();
+            let evaluated_at = now();
+
+            for rule in rules {
+                let Some(value) = metrics.get(&rule.condition.metric).copied() else {
+                    continue;
+                };
+                let state = next_state(&rule, value, evaluated_at);
+                if state != rule.state {
+                    println!(
+                        "Alert '{}' ({}) is {}: value {:.1}",
+                        rule.name,
+                        format_condition(&rule.condition),
+                        state.as_str(),
+                        value
+                    );
+                }
+                let source = AlertSource::Rule(rule.id);
+                match (rule.state, state) {
+                    (AlertState::Firing, AlertState::Firing) => {}
+                    (_, AlertState::Firing) => {
+                        lifecycle::raise(
+                            &storage,
+                            &notifier,
+                            source,
+                            rule.name.clone(),
+                            lifecycle::rule_labels(&rule),
+                            Some(Notification::alert(&rule, state, value)),
+                        )
+                        .await
+                    }
+                    (AlertState::Firing, AlertState::Resolved) => {
+                        lifecycle::resolve(
+                            &storage,
+                            &notifier,
+                            source,
+                            Some(Notification::alert(&rule, state, value)),
+                        )
+                        .await
+                    }
+                    _ => {}
+                }
+                if let Err(e) = storage.save_alert_state(rule.id, state, value).await {
+                    eprintln!("Error saving state of alert '{}': {}", rule.name, e);
+                }
+            }
+        }
+    });
+}
@@ -1,36 +1,252 @@
-use crate::database;
-use rusqlite::Connection;
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-pub fn make_request(connection: Arc<Mutex<Connection>>) {
-    let request_connection: Arc<Mutex<Connection>> = connection.clone();
+use tokio::sync::Semaphore;
 
+use crate::{
+    database::{
+        models::{MaintenanceMode, MonitorStatus, MonitorType, UrlData},
+        storage::SharedStorage,
+        time::now,
+    },
+    services::{
+        monitor::{
+            certificate,
+            certificate::CertificateChecker,
+            heartbeat,
+            http_check::HttpChecker,
+            maintenance,
+            probe::probe,
+            status::{update_status, FlapDetection},
+        },
+        notifications::{notification::Notification, notifier::Notifier},
+    },
+};
+
+/// How often the scheduler looks for monitors whose interval has elapsed.
+const SCHEDULER_TICK: Duration = Duration::from_secs(1);
+
+pub fn make_request(storage: SharedStorage, notifier: Notifier) {
+    let concurrency = env::var("CHECK_CONCURRENCY")
+        .ok()
+        .and_then(|concurrency| concurrency.parse::<usize>().ok())
+        .filter(|concurrency| *concurrency > 0)
+        .unwrap_or(16);
+    schedule(storage, notifier, concurrency);
+}
+
+/// Checks each monitor once its interval elapsed, at most `concurrency` at a time.
+fn schedule(storage: SharedStorage, notifier: Notifier, concurrency: usize) {
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        let checker = match HttpChecker::new() {
+            Ok(checker) => checker,
+            Err(e) => {
+                eprintln!("Error creating HTTP client: {}", e);
+                return;
+            }
+        };
+        let certificates = match CertificateChecker::new() {
+            Ok(certificates) => certificates,
+            Err(e) => {
+                eprintln!("Error creating TLS client: {}", e);
+                return;
+            }
+        };
+        let flap_detection = FlapDetection::from_env();
+        let permits = Arc::new(Semaphore::new(concurrency));
+        // Monitors with a check queued or running, so a slow check is never started twice
+        let in_flight: Arc<Mutex<HashSet<i32>>> = Arc::new(Mutex::new(HashSet::new()));
+        let mut last_checks: HashMap<i32, Instant> = HashMap::new();
+        let mut interval = tokio::time::interval(SCHEDULER_TICK);
         loop {
             interval.tick().await;
-            let urls = database::sqlite::urls::get_urls(&request_connection);
+            let urls = match storage.get_urls().await {
+                Ok(urls) => urls,
+                Err(e) => {
+                    eprintln!("Error loading URLs: {}", e);
+                    continue;
+                }
+            };
+            last_checks.retain(|id, _| urls.iter().any(|url_data| url_data.id == *id));
+            let windows = storage.get_maintenance_windows().await.unwrap_or_else(|e| {
+                eprintln!("Error loading maintenance windows: {}", e);
+                Vec::new()
+            });
+            // Schedules are expanded once per tick, not once per monitor
+            let active_windows = maintenance::active_windows(&windows, now());
 
             for url_data in urls {
-                let url = url_data.url;
-                let id = url_data.id;
-                println!("make request {}", url);
-                let response = reqwest::get(&url).await;
-
-                match response {
-                    Ok(resp) => {
-                        let status_code = resp.status().as_u16() as i32;
-                        println!("URL: {}, Status Code: {}", url, status_code);
-                        let conn = connection.lock().unwrap();
-                        database::sqlite::urls::update_url_status(&conn, id, status_code).unwrap();
-                    }
-                    Err(err) => {
-                        eprintln!("Error making request to {}: {}", url, err);
-                        let conn = connection.lock().unwrap();
-                        database::sqlite::urls::update_url_status(&conn, id, 404).unwrap();
-                    }
+                // Status changes waiting for confirmation are checked again sooner
+                let every = if url_data.unconfirmed_checks > 0 {
+                    url_data
+                        .settings
+                        .retry_interval
+                        .min(url_data.settings.interval)
+                } else {
+                    url_data.settings.interval
+                };
+                let every = Duration::from_secs(every as u64);
+                // Heartbeat monitors are pinged instead, they are only due once a ping is late
+                let due = if url_data.settings.monitor_type == MonitorType::Heartbeat {
+                    url_data
+                        .heartbeat
+                        .as_ref()
+                        .is_some_and(|heartbeat| heartbeat::is_late(heartbeat, now()))
+                } else {
+                    last_checks
+                        .get(&url_data.id)
+                        .is_none_or(|last| last.elapsed() >= every)
+                };
+                let in_maintenance = match maintenance::covering(&active_windows, &url_data) {
+                    Some(window) if window.settings.mode == MaintenanceMode::Pause => continue,
+                    Some(_) => true,
+                    None => false,
+                };
+                if !due || !in_flight.lock().unwrap().insert(url_data.id) {
+                    continue;
                 }
+                last_checks.insert(url_data.id, Instant::now());
+
+                let checker = checker.clone();
+                let certificates = certificates.clone();
+                let notifier = notifier.clone();
+                let storage = storage.clone();
+                let permits = permits.clone();
+                let in_flight = in_flight.clone();
+                tokio::spawn(async move {
+                    if let Ok(_permit) = permits.acquire().await {
+                        println!("make request {}", url_data.url);
+                        let mut result = probe(&checker, &url_data).await;
+                        result.maintenance = in_maintenance;
+                        // During maintenance the status is left as it was
+                        let status = if in_maintenance {
+                            MonitorStatus {
+                                up: url_data.up,
+                                unconfirmed_checks: url_data.unconfirmed_checks,
+                                flapping: url_data.flapping,
+                            }
+                        } else {
+                            update_status(&storage, &notifier, flap_detection, &url_data, &result)
+                                .await
+                        };
+                        if let Err(e) = storage.save_check_result(url_data.id, result, status).await
+                        {
+                            eprintln!("Error saving status of {}: {}", url_data.url, e);
+                        }
+                        if let Some(heartbeat) = &url_data.heartbeat {
+                            let missed = heartbeat::missed(heartbeat, &url_data.settings, now());
+                            if let Err(e) = storage
+                                .save_missed_heartbeat(
+                                    url_data.id,
+                                    heartbeat.expected_at.clone(),
+                                    missed,
+                                )
+                                .await
+                            {
+                                eprintln!("Error saving heartbeat of {}: {}", url_data.url, e);
+                            }
+                        }
+                        if certificates.is_due(&url_data.url, url_data.certificate.as_ref()) {
+                            check_certificate(&certificates, &url_data, &storage, &notifier).await;
+                        }
+                    }
+                    in_flight.lock().unwrap().remove(&url_data.id);
+                });
             }
         }
     });
 }
+
+/// Reads and saves the chain, notifying when the certificate enters the expiry warning
+/// period and again once it is renewed.
+async fn check_certificate(
+    certificates: &CertificateChecker,
+    url_data: &UrlData,
+    storage: &SharedStorage,
+    notifier: &Notifier,
+) {
+    let timeout = Duration::from_secs(url_data.settings.timeout as u64);
+    let certificate = match certificates.fetch(&url_data.url, timeout).await {
+        Ok(certificate) => certificate,
+        Err(e) => {
+            eprintln!("Error reading certificate of {}: {}", url_data.url, e);
+            return;
+        }
+    };
+    let change = certificate::expiry_change(url_data.certificate.as_ref(), &certificate);
+    if certificate::expiring_soon(&certificate) {
+        eprintln!(
+            "Certificate of {} expires in {} days",
+            url_data.url,
+            certificate::days_until_expiry(&certificate).unwrap_or_default()
+        );
+    }
+    if let Some(expiring) = change {
+        notifier.notify(Notification::certificate_expiring(
+            url_data,
+            &certificate,
+            expiring,
+        ));
+    }
+    if let Err(e) = storage.save_certificate(url_data.id, certificate).await {
+        eprintln!("Error saving certificate of {}: {}", url_data.url, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{memory::storage::MemoryStorage, models::UrlSettings},
+        test_support::HttpStandIn,
+    };
+
+    /// Monitors checked every `interval` seconds against a server that takes `delay` to
+    /// answer, with the scheduler started.
+    async fn scheduled(
+        monitors: usize,
+        interval: u32,
+        delay: Duration,
+        concurrency: usize,
+    ) -> HttpStandIn {
+        let server = HttpStandIn::start_delayed(&[], delay).await;
+        let storage: SharedStorage = Arc::new(MemoryStorage::new());
+        for index in 0..monitors {
+            let settings = UrlSettings {
+                interval,
+                ..UrlSettings::default()
+            };
+            storage
+                .create_url(server.url(&format!("/{}", index)), settings, None)
+                .await
+                .unwrap();
+        }
+        let notifier = Notifier::new(storage.clone()).unwrap();
+        schedule(storage, notifier, concurrency);
+        server
+    }
+
+    #[tokio::test]
+    async fn checks_run_at_most_concurrency_at_a_time() {
+        let server = scheduled(4, 60, Duration::from_millis(1000), 2).await;
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(server.take_requests().len(), 2);
+        // The other two start once the first ones answer
+        tokio::time::sleep(Duration::from_millis(1000)).await;
+        assert_eq!(server.take_requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn slow_check_is_not_started_again_while_running() {
+        let server = scheduled(1, 1, Duration::from_millis(4000), 4).await;
+
+        // Due again at every tick, but the first check is still waiting for its answer
+        tokio::time::sleep(Duration::from_millis(3500)).await;
+        assert_eq!(server.take_requests().len(), 1);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod clear_database;
+pub mod escalate_alerts;
+pub mod evaluate_alerts;
+pub mod make_request;
@@ -2,25 +2,26 @@ mod database;
 mod jobs;
 mod server;
 mod services;
-
-use std::sync::{Arc, Mutex};
+#[cfg(test)]
+mod test_support;
 
 use dotenv::dotenv;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenv().ok();
-    let connection: rusqlite::Connection =
-        database::sqlite::connection::connection_database().await?;
-    database::sqlite::create_database::create_database(&connection).await?;
-    let connection = Arc::new(Mutex::new(connection));
+    let storage = database::storage::open_storage().await?;
 
-    database::sqlite::flush::flush_cpu_info(connection.clone()).await?;
+    storage.flush_cpu_info().await?;
+    server::urls::normalize_stored_urls(&storage).await?;
 
-    jobs::clear_database::clear_database(connection.clone());
-    jobs::make_request::make_request(connection.clone());
+    jobs::clear_database::clear_database(storage.clone());
+    let notifier = services::notifications::notifier::Notifier::new(storage.clone())?;
+    jobs::make_request::make_request(storage.clone(), notifier.clone());
+    jobs::evaluate_alerts::evaluate_alerts(storage.clone(), notifier.clone());
+    jobs::escalate_alerts::escalate_alerts(storage.clone(), notifier.clone());
 
-    server::http::start_http_server(connection).await?;
+    server::http::start_http_server(storage, notifier).await?;
 
     Ok(())
 }
@@ -0,0 +1,705 @@
+use std::{collections::BTreeMap, convert::Infallible};
+
+use chrono::TimeDelta;
+use http::{HeaderValue, Method, Request, Response, StatusCode};
+use http_body_util::Full;
+use hyper::body::Bytes;
+
+use crate::{
+    database::{
+        models::{Alert, AlertCondition, AlertMatcher, AlertRule, Silence},
+        storage::SharedStorage,
+        time::{format_timestamp, now, parse_timestamp},
+    },
+    server::request::{error_response, query_param, read_json, read_optional_json, response},
+    services::alerts::{
+        expression::{format_condition, parse_condition, parse_duration},
+        lifecycle::RESERVED_LABELS,
+    },
+};
+
+const DEFAULT_LIMIT: u32 = 100;
+const MAX_LIMIT: u32 = 1000;
+const MAX_LABELS: usize = 20;
+
+pub fn rule_json(rule: &AlertRule) -> serde_json::Value {
+    let condition = &rule.condition;
+    serde_json::json!({
+        "id": rule.id,
+        "name": rule.name,
+        "expression": format_condition(condition),
+        "metric": condition.metric.as_str(),
+        "operator": condition.comparison.as_str(),
+        "threshold": condition.threshold,
+        "for": condition.duration,
+        "labels": rule.labels,
+        "state": rule.state.as_str(),
+        "state_changed_at": rule.state_changed_at,
+        "value": rule.value,
+        "created_at": rule.created_at,
+    })
+}
+
+/// `silences` are the ones in effect, an active alert matched by any of them is silenced.
+fn alert_json(alert: &Alert, silences: &[Silence]) -> serde_json::Value {
+    let silenced_by = if alert.is_active() {
+        silences
+            .iter()
+            .filter(|silence| silence.matcher.matches(alert))
+            .map(|silence| silence.id)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    serde_json::json!({
+        "id": alert.id,
+        "status": if alert.is_active() { "active" } else { "resolved" },
+        "source": alert.source.as_str(),
+        "rule_id": alert.source.rule_id(),
+        "url_id": alert.source.url_id(),
+        "name": alert.name,
+        "labels": alert.labels,
+        "started_at": alert.started_at,
+        "resolved_at": alert.resolved_at,
+        "acknowledged": alert.acknowledged_at.is_some(),
+        "acknowledged_at": alert.acknowledged_at,
+        "ack_comment": alert.ack_comment,
+        "silenced": !silenced_by.is_empty(),
+        "silenced_by": silenced_by,
+    })
+}
+
+fn silence_json(silence: &Silence) -> serde_json::Value {
+    serde_json::json!({
+        "id": silence.id,
+        "rule_id": silence.matcher.rule_id,
+        "url_id": silence.matcher.url_id,
+        "labels": silence.matcher.labels,
+        "comment": silence.comment,
+        "expires_at": silence.expires_at,
+        "active": silence.expires_at > format_timestamp(&now()),
+        "created_at": silence.created_at,
+    })
+}
+
+/// Reads an optional `labels` object of string values. Names are letters, digits, `_`, `-`
+/// or `.`, and the ones added to every alert are reserved.
+pub fn labels_field(json: &serde_json::Value) -> Result<Option<BTreeMap<String, String>>, String> {
+    let Some(labels) = json.get("labels") else {
+        return Ok(None);
+    };
+    let Some(labels) = labels.as_object() else {
+        return Err("Campo 'labels' deve ser um objeto".into());
+    };
+    if labels.len() > MAX_LABELS {
+        return Err(format!("No máximo {} labels", MAX_LABELS));
+    }
+    let mut parsed = BTreeMap::new();
+    for (name, value) in labels {
+        let valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.');
+        if !valid_name {
+            return Err(format!("Nome de label inválido: '{}'", name));
+        }
+        if RESERVED_LABELS.contains(&name.as_str()) {
+            return Err(format!("Label '{}' é reservado", name));
+        }
+        let Some(value) = value.as_str() else {
+            return Err(format!("Label '{}' deve ser uma string", name));
+        };
+        parsed.insert(name.clone(), value.to_string());
+    }
+    Ok(Some(parsed))
+}
+
+/// Validated fields of a rule, as sent to the storage.
+struct RuleFields {
+    name: String,
+    condition: AlertCondition,
+    labels: BTreeMap<String, String>,
+}
+
+/// Reads `name`, `expression` and `labels`, keeping the values of `current` for the ones
+/// missing.
+fn rule_fields(
+    json: &serde_json::Value,
+    current: Option<&AlertRule>,
+) -> Result<RuleFields, String> {
+    let name = match (json.get("name"), current) {
+        (Some(serde_json::Value::String(name)), _) if !name.trim().is_empty() => {
+            name.trim().to_string()
+        }
+        (Some(_), _) => return Err("Campo 'name' deve ser uma string não vazia".into()),
+        (None, Some(current)) => current.name.clone(),
+        (None, None) => return Err("Campo 'name' ausente".into()),
+    };
+    let condition = match (json.get("expression"), current) {
+        (Some(serde_json::Value::String(expression)), _) => parse_condition(expression)?,
+        (Some(_), _) => return Err("Campo 'expression' deve ser uma string".into()),
+        (None, Some(current)) => current.condition,
+        (None, None) => return Err("Campo 'expression' ausente".into()),
+    };
+    let labels = match (labels_field(json)?, current) {
+        (Some(labels), _) => labels,
+        (None, Some(current)) => current.labels.clone(),
+        (None, None) => BTreeMap::new(),
+    };
+    Ok(RuleFields {
+        name,
+        condition,
+        labels,
+    })
+}
+
+/// Routes `/alerts`, `/alerts/{id}`, `/alerts/{id}/ack`, `/alerts/rules`, `/alerts/rules/{id}`,
+/// `/alerts/silences` and `/alerts/silences/{id}`.
+pub async fn alerts_request(
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let path = request.uri().path().trim_end_matches('/').to_string();
+    let method = request.method().clone();
+
+    if path == "/alerts" {
+        return match method {
+            Method::GET => list_alerts(&request, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        };
+    }
+
+    if path == "/alerts/rules" {
+        return match method {
+            Method::GET => list_rules(storage).await,
+            Method::POST => create_rule(request, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        };
+    }
+
+    if let Some(id) = path.strip_prefix("/alerts/rules/") {
+        let Ok(id) = id.parse::<i64>() else {
+            return error_response("Regra não encontrada", StatusCode::NOT_FOUND).await;
+        };
+        return match method {
+            Method::GET => get_rule(id, storage).await,
+            Method::PUT => update_rule(id, request, storage, true).await,
+            Method::PATCH => update_rule(id, request, storage, false).await,
+            Method::DELETE => delete_rule(id, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        };
+    }
+
+    if path == "/alerts/silences" {
+        return match method {
+            Method::GET => list_silences(&request, storage).await,
+            Method::POST => create_silence(request, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        };
+    }
+
+    if let Some(id) = path.strip_prefix("/alerts/silences/") {
+        let Ok(id) = id.parse::<i64>() else {
+            return error_response("Silêncio não encontrado", StatusCode::NOT_FOUND).await;
+        };
+        return match method {
+            Method::GET => get_silence(id, storage).await,
+            Method::DELETE => delete_silence(id, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        };
+    }
+
+    let Some(rest) = path.strip_prefix("/alerts/") else {
+        return error_response("Recurso não encontrado", StatusCode::NOT_FOUND).await;
+    };
+    let mut segments = rest.split('/');
+    let Some(id) = segments.next().and_then(|id| id.parse::<i64>().ok()) else {
+        return error_response("Alerta não encontrado", StatusCode::NOT_FOUND).await;
+    };
+    match (segments.next(), segments.next()) {
+        (None, _) => match method {
+            Method::GET => get_alert(id, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        },
+        (Some("ack"), None) => match method {
+            Method::POST => acknowledge_alert(id, request, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        },
+        _ => error_response("Recurso não encontrado", StatusCode::NOT_FOUND).await,
+    }
+}
+
+async fn list_rules(storage: SharedStorage) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.get_alert_rules().await {
+        Ok(rules) => {
+            let rules_data = rules
+                .iter()
+                .map(rule_json)
+                .collect::<Vec<serde_json::Value>>();
+            response(
+                &serde_json::json!({ "data": rules_data }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Err(e) => {
+            eprintln!("Error listing alert rules: {}", e);
+            error_response("Erro ao listar regras", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+async fn get_rule(id: i64, storage: SharedStorage) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.get_alert_rule(id).await {
+        Ok(Some(rule)) => {
+            response(
+                &serde_json::json!({ "data": rule_json(&rule) }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Ok(None) => error_response("Regra não encontrada", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error loading alert rule {}: {}", id, e);
+            error_response("Erro ao buscar regra", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+async fn create_rule(
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let json = match read_json(request).await {
+        Ok(json) => json,
+        Err(res) => return Ok(res),
+    };
+    let fields = match rule_fields(&json, None) {
+        Ok(fields) => fields,
+        Err(message) => return error_response(&message, StatusCode::BAD_REQUEST).await,
+    };
+
+    match storage
+        .create_alert_rule(fields.name, fields.condition, fields.labels)
+        .await
+    {
+        Ok(rule) => {
+            let mut res = response(
+                &serde_json::json!({
+                    "message": "Regra criada com sucesso",
+                    "data": rule_json(&rule),
+                })
+                .to_string(),
+                StatusCode::CREATED,
+            )
+            .await?;
+            res.headers_mut().insert(
+                "Location",
+                HeaderValue::from_str(&format!("/alerts/rules/{}", rule.id)).unwrap(),
+            );
+            Ok(res)
+        }
+        Err(e) => {
+            eprintln!("Error creating alert rule: {}", e);
+            error_response("Erro ao criar regra", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+/// PUT requires `name` and `expression`, PATCH keeps the current value of the fields omitted.
+async fn update_rule(
+    id: i64,
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+    replace: bool,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let json = match read_json(request).await {
+        Ok(json) => json,
+        Err(res) => return Ok(res),
+    };
+    let current = match storage.get_alert_rule(id).await {
+        Ok(Some(current)) => current,
+        Ok(None) => return error_response("Regra não encontrada", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error loading alert rule {}: {}", id, e);
+            return error_response("Erro ao atualizar regra", StatusCode::INTERNAL_SERVER_ERROR)
+                .await;
+        }
+    };
+    let fields = if replace {
+        rule_fields(&json, None)
+    } else {
+        rule_fields(&json, Some(&current))
+    };
+    let fields = match fields {
+        Ok(fields) => fields,
+        Err(message) => return error_response(&message, StatusCode::BAD_REQUEST).await,
+    };
+
+    match storage
+        .update_alert_rule(id, fields.name, fields.condition, fields.labels)
+        .await
+    {
+        Ok(Some(rule)) => {
+            response(
+                &serde_json::json!({ "data": rule_json(&rule) }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Ok(None) => error_response("Regra não encontrada", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error updating alert rule {}: {}", id, e);
+            error_response("Erro ao atualizar regra", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+async fn delete_rule(id: i64, storage: SharedStorage) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.delete_alert_rule(id).await {
+        Ok(true) => {
+            response(
+                &serde_json::json!({
+                    "message": format!("Regra com ID {} deletada com sucesso", id)
+                })
+                .to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Ok(false) => error_response("Regra não encontrada", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error deleting alert rule {}: {}", id, e);
+            error_response("Erro ao deletar regra", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+async fn active_silences(storage: &SharedStorage) -> Vec<Silence> {
+    storage.get_silences(Some(now())).await.unwrap_or_else(|e| {
+        eprintln!("Error loading silences: {}", e);
+        Vec::new()
+    })
+}
+
+/// `?status=active|resolved` filters, `?limit=` caps the number of alerts returned.
+async fn list_alerts(
+    request: &Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let active = match query_param(request, "status").as_deref() {
+        None => None,
+        Some("active") => Some(true),
+        Some("resolved") => Some(false),
+        Some(_) => {
+            return error_response(
+                "Parâmetro 'status' deve ser 'active' ou 'resolved'",
+                StatusCode::BAD_REQUEST,
+            )
+            .await
+        }
+    };
+    let limit = match query_param(request, "limit").map(|limit| limit.parse::<u32>()) {
+        Some(Ok(limit)) if (1..=MAX_LIMIT).contains(&limit) => limit,
+        Some(_) => {
+            return error_response(
+                &format!("Parâmetro 'limit' deve estar entre 1 e {}", MAX_LIMIT),
+                StatusCode::BAD_REQUEST,
+            )
+            .await
+        }
+        None => DEFAULT_LIMIT,
+    };
+
+    match storage.get_alerts(active, limit).await {
+        Ok(alerts) => {
+            let silences = active_silences(&storage).await;
+            let alerts_data = alerts
+                .iter()
+                .map(|alert| alert_json(alert, &silences))
+                .collect::<Vec<serde_json::Value>>();
+            response(
+                &serde_json::json!({ "data": alerts_data }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Err(e) => {
+            eprintln!("Error listing alerts: {}", e);
+            error_response("Erro ao listar alertas", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+async fn get_alert(id: i64, storage: SharedStorage) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.get_alert(id).await {
+        Ok(Some(alert)) => {
+            let silences = active_silences(&storage).await;
+            response(
+                &serde_json::json!({ "data": alert_json(&alert, &silences) }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Ok(None) => error_response("Alerta não encontrado", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error loading alert {}: {}", id, e);
+            error_response("Erro ao buscar alerta", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+/// Only active alerts that nobody acknowledged yet can be acknowledged. The body is
+/// optional, an empty one acknowledges without a comment.
+async fn acknowledge_alert(
+    id: i64,
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let json = match read_optional_json(request).await {
+        Ok(json) => json,
+        Err(res) => return Ok(res),
+    };
+    let comment = match json.get("comment") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::String(comment)) if !comment.trim().is_empty() => {
+            Some(comment.trim().to_string())
+        }
+        Some(_) => {
+            return error_response(
+                "Campo 'comment' deve ser uma string não vazia",
+                StatusCode::BAD_REQUEST,
+            )
+            .await
+        }
+    };
+
+    match storage.acknowledge_alert(id, comment).await {
+        Ok(Some(alert)) => {
+            println!("Alert '{}' acknowledged", alert.name);
+            let silences = active_silences(&storage).await;
+            response(
+                &serde_json::json!({
+                    "message": "Alerta reconhecido",
+                    "data": alert_json(&alert, &silences),
+                })
+                .to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        // Nothing was acknowledged, the alert tells why
+        Ok(None) => match storage.get_alert(id).await {
+            Ok(Some(alert)) if !alert.is_active() => {
+                error_response("Alerta já resolvido", StatusCode::CONFLICT).await
+            }
+            Ok(Some(_)) => error_response("Alerta já reconhecido", StatusCode::CONFLICT).await,
+            Ok(None) => error_response("Alerta não encontrado", StatusCode::NOT_FOUND).await,
+            Err(e) => {
+                eprintln!("Error loading alert {}: {}", id, e);
+                error_response(
+                    "Erro ao reconhecer alerta",
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                )
+                .await
+            }
+        },
+        Err(e) => {
+            eprintln!("Error acknowledging alert {}: {}", id, e);
+            error_response(
+                "Erro ao reconhecer alerta",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
+
+/// Active silences, or every silence not yet flushed with `?all=true`.
+async fn list_silences(
+    request: &Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let at = if query_param(request, "all").is_some_and(|value| value == "true") {
+        None
+    } else {
+        Some(now())
+    };
+    match storage.get_silences(at).await {
+        Ok(silences) => {
+            let silences_data = silences
+                .iter()
+                .map(silence_json)
+                .collect::<Vec<serde_json::Value>>();
+            response(
+                &serde_json::json!({ "data": silences_data }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Err(e) => {
+            eprintln!("Error listing silences: {}", e);
+            error_response(
+                "Erro ao listar silêncios",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
+
+async fn get_silence(id: i64, storage: SharedStorage) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.get_silence(id).await {
+        Ok(Some(silence)) => {
+            response(
+                &serde_json::json!({ "data": silence_json(&silence) }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Ok(None) => error_response("Silêncio não encontrado", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error loading silence {}: {}", id, e);
+            error_response("Erro ao buscar silêncio", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+/// Reads the optional `rule_id`, `url_id` and `labels` an alert must match.
+pub fn matcher_fields(json: &serde_json::Value) -> Result<AlertMatcher, String> {
+    let id_field = |name: &str| match json.get(name) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(id) => id
+            .as_i64()
+            .map(Some)
+            .ok_or(format!("Campo '{}' deve ser um número", name)),
+    };
+    Ok(AlertMatcher {
+        rule_id: id_field("rule_id")?,
+        url_id: id_field("url_id")?
+            .map(|id| i32::try_from(id).map_err(|_| "Campo 'url_id' inválido".to_string()))
+            .transpose()?,
+        labels: labels_field(json)?.unwrap_or_default(),
+    })
+}
+
+/// Checks that the rule and the monitor named by the matcher exist.
+pub async fn missing_matcher_source(
+    matcher: &AlertMatcher,
+    storage: &SharedStorage,
+) -> Option<&'static str> {
+    if let Some(rule_id) = matcher.rule_id {
+        if !matches!(storage.get_alert_rule(rule_id).await, Ok(Some(_))) {
+            return Some("Regra não encontrada");
+        }
+    }
+    if let Some(url_id) = matcher.url_id {
+        if !matches!(storage.get_url(url_id).await, Ok(Some(_))) {
+            return Some("URL não encontrada");
+        }
+    }
+    None
+}
+
+/// Reads the matcher, the comment and either `duration` or `expires_at`.
+fn silence_fields(
+    json: &serde_json::Value,
+) -> Result<(AlertMatcher, String, chrono::NaiveDateTime), String> {
+    let matcher = matcher_fields(json)?;
+    if matcher.is_empty() {
+        return Err("Informe ao menos um de 'rule_id', 'url_id' ou 'labels'".into());
+    }
+
+    let comment = match json.get("comment") {
+        Some(serde_json::Value::String(comment)) if !comment.trim().is_empty() => {
+            comment.trim().to_string()
+        }
+        Some(_) => return Err("Campo 'comment' deve ser uma string não vazia".into()),
+        None => return Err("Campo 'comment' ausente".into()),
+    };
+
+    let expires_at = match (json.get("duration"), json.get("expires_at")) {
+        (Some(_), Some(_)) => return Err("Use 'duration' ou 'expires_at', não ambos".into()),
+        (Some(duration), None) => {
+            let seconds = parse_duration(duration.as_str().unwrap_or_default())?;
+            now() + TimeDelta::seconds(seconds as i64)
+        }
+        (None, Some(expires_at)) => expires_at
+            .as_str()
+            .and_then(parse_timestamp)
+            .ok_or("Campo 'expires_at' inválido")?,
+        (None, None) => return Err("Campo 'duration' ou 'expires_at' ausente".into()),
+    };
+    if expires_at <= now() {
+        return Err("O silêncio deve expirar no futuro".into());
+    }
+    Ok((matcher, comment, expires_at))
+}
+
+async fn create_silence(
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let json = match read_json(request).await {
+        Ok(json) => json,
+        Err(res) => return Ok(res),
+    };
+    let (matcher, comment, expires_at) = match silence_fields(&json) {
+        Ok(fields) => fields,
+        Err(message) => return error_response(&message, StatusCode::BAD_REQUEST).await,
+    };
+    if let Some(message) = missing_matcher_source(&matcher, &storage).await {
+        return error_response(message, StatusCode::BAD_REQUEST).await;
+    }
+
+    match storage.create_silence(matcher, comment, expires_at).await {
+        Ok(silence) => {
+            let mut res = response(
+                &serde_json::json!({
+                    "message": "Silêncio criado com sucesso",
+                    "data": silence_json(&silence),
+                })
+                .to_string(),
+                StatusCode::CREATED,
+            )
+            .await?;
+            res.headers_mut().insert(
+                "Location",
+                HeaderValue::from_str(&format!("/alerts/silences/{}", silence.id)).unwrap(),
+            );
+            Ok(res)
+        }
+        Err(e) => {
+            eprintln!("Error creating silence: {}", e);
+            error_response("Erro ao criar silêncio", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+/// Ends the silence right away.
+async fn delete_silence(
+    id: i64,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.delete_silence(id).await {
+        Ok(true) => {
+            response(
+                &serde_json::json!({
+                    "message": format!("Silêncio com ID {} deletado com sucesso", id)
+                })
+                .to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Ok(false) => error_response("Silêncio não encontrado", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error deleting silence {}: {}", id, e);
+            error_response(
+                "Erro ao deletar silêncio",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use std::convert::Infallible;
+
+use chrono::{NaiveDateTime, TimeDelta};
+use http::{Request, Response, StatusCode};
+use http_body_util::Full;
+use hyper::body::Bytes;
+
+use crate::{
+    database::{
+        models::{CheckResult, UrlCheck, UrlData},
+        storage::SharedStorage,
+        time::{format_timestamp, now, parse_timestamp},
+    },
+    server::request::{error_response, query_param, response},
+    services::monitor::{maintenance, uptime::uptime_report},
+};
+
+const DEFAULT_LIMIT: u32 = 100;
+const MAX_LIMIT: u32 = 1000;
+
+pub fn result_json(result: &CheckResult) -> serde_json::Value {
+    serde_json::json!({
+        "status_code": result.status_code,
+        "up": result.up,
+        "outcome": result.outcome.as_str(),
+        "response_time_ms": result.response_time_ms,
+        "response_size": result.response_size,
+        "error": result.error,
+        "failed_assertion": result.failed_assertion,
+        "maintenance": result.maintenance,
+    })
+}
+
+pub fn check_json(check: &UrlCheck) -> serde_json::Value {
+    let mut json = result_json(&check.result);
+    json["id"] = check.id.into();
+    json["url_id"] = check.url_id.into();
+    json["checked_at"] = check.checked_at.clone().into();
+    json
+}
+
+/// Reads an optional timestamp query parameter, `Err` holds the message for a bad value.
+pub fn timestamp_param(
+    request: &Request<hyper::body::Incoming>,
+    name: &str,
+) -> Result<Option<NaiveDateTime>, String> {
+    match query_param(request, name) {
+        None => Ok(None),
+        Some(value) => parse_timestamp(&value)
+            .map(Some)
+            .ok_or(format!("Parâmetro '{}' inválido", name)),
+    }
+}
+
+/// Parses windows such as `24h`, `7d` or `30d`.
+fn parse_window(value: &str) -> Option<TimeDelta> {
+    let (amount, unit) = value.split_at(value.len().checked_sub(1)?);
+    let amount = amount.parse::<i64>().ok().filter(|amount| *amount > 0)?;
+    match unit {
+        "h" if amount <= 24 * 366 => Some(TimeDelta::hours(amount)),
+        "d" if amount <= 366 => Some(TimeDelta::days(amount)),
+        _ => None,
+    }
+}
+
+async fn find_url(
+    url_id: i32,
+    storage: &SharedStorage,
+) -> Result<UrlData, Result<Response<Full<Bytes>>, Infallible>> {
+    match storage.get_url(url_id).await {
+        Ok(Some(url_data)) => Ok(url_data),
+        Ok(None) => Err(error_response("URL não encontrada", StatusCode::NOT_FOUND).await),
+        Err(e) => {
+            eprintln!("Error loading URL {}: {}", url_id, e);
+            Err(error_response("Erro ao buscar URL", StatusCode::INTERNAL_SERVER_ERROR).await)
+        }
+    }
+}
+
+/// `GET /urls/{id}/checks?from=&to=&limit=`
+pub async fn list_checks(
+    url_id: i32,
+    request: &Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let (from, to) = match (
+        timestamp_param(request, "from"),
+        timestamp_param(request, "to"),
+    ) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(message), _) | (_, Err(message)) => {
+            return error_response(&message, StatusCode::BAD_REQUEST).await
+        }
+    };
+    let limit = match query_param(request, "limit").map(|limit| limit.parse::<u32>()) {
+        None => DEFAULT_LIMIT,
+        Some(Ok(limit)) if (1..=MAX_LIMIT).contains(&limit) => limit,
+        Some(_) => {
+            return error_response(
+                &format!("Parâmetro 'limit' deve estar entre 1 e {}", MAX_LIMIT),
+                StatusCode::BAD_REQUEST,
+            )
+            .await
+        }
+    };
+
+    if let Err(res) = find_url(url_id, &storage).await {
+        return res;
+    }
+
+    match storage.get_url_checks(url_id, from, to, limit).await {
+        Ok(checks) => {
+            let checks_data = checks
+                .iter()
+                .map(check_json)
+                .collect::<Vec<serde_json::Value>>();
+            response(
+                &serde_json::json!({ "data": checks_data }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Err(e) => {
+            eprintln!("Error loading checks of URL {}: {}", url_id, e);
+            error_response(
+                "Erro ao buscar histórico de verificações",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
+
+/// `GET /urls/{id}/uptime?window=24h|7d|30d` or `?from=&to=` for a custom range.
+pub async fn uptime(
+    url_id: i32,
+    request: &Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let (from, to) = match (
+        timestamp_param(request, "from"),
+        timestamp_param(request, "to"),
+    ) {
+        (Ok(from), Ok(to)) => (from, to.unwrap_or(now()).min(now())),
+        (Err(message), _) | (_, Err(message)) => {
+            return error_response(&message, StatusCode::BAD_REQUEST).await
+        }
+    };
+    let from = match from {
+        Some(from) => from,
+        None => {
+            let window = query_param(request, "window").unwrap_or("24h".into());
+            match parse_window(&window) {
+                Some(window) => to - window,
+                None => {
+                    return error_response(
+                        "Parâmetro 'window' inválido, use por exemplo 24h, 7d ou 30d",
+                        StatusCode::BAD_REQUEST,
+                    )
+                    .await
+                }
+            }
+        }
+    };
+    if from >= to {
+        return error_response(
+            "O início do período deve ser anterior ao fim",
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+    }
+
+    let url_data = match find_url(url_id, &storage).await {
+        Ok(url_data) => url_data,
+        Err(res) => return res,
+    };
+    let windows = match storage.get_maintenance_windows().await {
+        Ok(windows) => windows,
+        Err(e) => {
+            eprintln!("Error loading maintenance windows: {}", e);
+            return error_response(
+                "Erro ao calcular disponibilidade",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await;
+        }
+    };
+
+    let checks = match storage
+        .get_url_checks(url_id, Some(from), Some(to), u32::MAX)
+        .await
+    {
+        Ok(checks) => checks,
+        Err(e) => {
+            eprintln!("Error loading checks of URL {}: {}", url_id, e);
+            return error_response(
+                "Erro ao calcular disponibilidade",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await;
+        }
+    };
+    let mut samples = checks
+        .iter()
+        .filter_map(|check| {
+            Some((
+                parse_timestamp(&check.checked_at)?,
+                check.result.up,
+                check.result.maintenance,
+            ))
+        })
+        .collect::<Vec<(NaiveDateTime, bool, bool)>>();
+    samples.reverse();
+
+    // Current windows of the monitor, plus the checks marked during windows since removed
+    let mut excluded = windows
+        .iter()
+        .filter(|window| maintenance::applies_to(&window.settings, &url_data))
+        .flat_map(|window| maintenance::intervals(&window.settings.schedule, from, to))
+        .collect::<Vec<(NaiveDateTime, NaiveDateTime)>>();
+    for (index, (checked_at, _, in_maintenance)) in samples.iter().enumerate() {
+        if *in_maintenance {
+            let next = samples.get(index + 1).map(|sample| sample.0).unwrap_or(to);
+            excluded.push((*checked_at, next));
+        }
+    }
+    let samples = samples
+        .iter()
+        .filter(|(_, _, in_maintenance)| !in_maintenance)
+        .map(|(checked_at, up, _)| (*checked_at, *up))
+        .collect::<Vec<(NaiveDateTime, bool)>>();
+
+    let report = uptime_report(&samples, to, &excluded);
+    response(
+        &serde_json::json!({
+            "data": {
+                "url_id": url_id,
+                "from": format_timestamp(&from),
+                "to": format_timestamp(&to),
+                "checks": report.checks,
+                "availability": report.availability(),
+                "monitored_seconds": report.monitored.num_seconds(),
+                "total_downtime_seconds": report.downtime.num_seconds(),
+                "incidents": report.incidents,
+                "mttr_seconds": report.mean_time_to_recovery.map(|mttr| mttr.num_seconds()),
+            }
+        })
+        .to_string(),
+        StatusCode::OK,
+    )
+    .await
+}
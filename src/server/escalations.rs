@@ -0,0 +1,351 @@
+use std::convert::Infallible;
+
+use http::{HeaderValue, Method, Request, Response, StatusCode};
+use http_body_util::Full;
+use hyper::body::Bytes;
+
+use crate::{
+    database::{
+        models::{EscalationPolicy, EscalationSettings, EscalationStep},
+        storage::SharedStorage,
+    },
+    server::{
+        alerts::{matcher_fields, missing_matcher_source},
+        request::{error_response, read_json, response},
+    },
+    services::alerts::expression::parse_duration,
+};
+
+const MAX_STEPS: usize = 10;
+
+fn policy_json(policy: &EscalationPolicy) -> serde_json::Value {
+    let settings = &policy.settings;
+    serde_json::json!({
+        "id": policy.id,
+        "name": settings.name,
+        "rule_id": settings.matcher.rule_id,
+        "url_id": settings.matcher.url_id,
+        "labels": settings.matcher.labels,
+        "steps": settings
+            .steps
+            .iter()
+            .map(EscalationStep::to_json)
+            .collect::<Vec<serde_json::Value>>(),
+        "created_at": policy.created_at,
+    })
+}
+
+/// A step is `{"after": "10m", "channel_ids": [1, 2]}`, `after` defaulting to right away.
+/// `after` may also be given in seconds, as the API returns it.
+fn step_field(json: &serde_json::Value) -> Result<EscalationStep, String> {
+    let after = match json.get("after") {
+        Some(serde_json::Value::Number(seconds)) => seconds
+            .as_u64()
+            .and_then(|seconds| u32::try_from(seconds).ok())
+            .ok_or("Campo 'after' inválido")?,
+        Some(after) => parse_duration(after.as_str().unwrap_or_default())?,
+        None => 0,
+    };
+    let channel_ids = json
+        .get("channel_ids")
+        .and_then(|ids| ids.as_array())
+        .and_then(|ids| {
+            ids.iter()
+                .map(|id| id.as_i64())
+                .collect::<Option<Vec<i64>>>()
+        })
+        .filter(|ids| !ids.is_empty())
+        .ok_or("Cada passo precisa de uma lista 'channel_ids' não vazia")?;
+    Ok(EscalationStep { after, channel_ids })
+}
+
+/// Reads the policy fields from `json`, keeping the values of `current` for the ones
+/// missing. Any of `rule_id`, `url_id` and `labels` replaces the current matcher entirely.
+fn policy_fields(
+    json: &serde_json::Value,
+    current: Option<&EscalationSettings>,
+) -> Result<EscalationSettings, String> {
+    let name = match (json.get("name"), current) {
+        (Some(serde_json::Value::String(name)), _) if !name.trim().is_empty() => {
+            name.trim().to_string()
+        }
+        (Some(_), _) => return Err("Campo 'name' deve ser uma string não vazia".into()),
+        (None, Some(current)) => current.name.clone(),
+        (None, None) => return Err("Campo 'name' ausente".into()),
+    };
+
+    let matcher_sent = ["rule_id", "url_id", "labels"]
+        .iter()
+        .any(|name| json.get(name).is_some());
+    let matcher = match current {
+        Some(current) if !matcher_sent => current.matcher.clone(),
+        _ => matcher_fields(json)?,
+    };
+
+    let steps = match (json.get("steps"), current) {
+        (Some(steps), _) => steps
+            .as_array()
+            .ok_or("Campo 'steps' deve ser uma lista")?
+            .iter()
+            .map(step_field)
+            .collect::<Result<Vec<EscalationStep>, String>>()?,
+        (None, Some(current)) => current.steps.clone(),
+        (None, None) => return Err("Campo 'steps' ausente".into()),
+    };
+    if steps.is_empty() || steps.len() > MAX_STEPS {
+        return Err(format!("Informe de 1 a {} passos", MAX_STEPS));
+    }
+    if steps.windows(2).any(|pair| pair[1].after < pair[0].after) {
+        return Err("Os passos devem estar em ordem crescente de 'after'".into());
+    }
+
+    Ok(EscalationSettings {
+        name,
+        matcher,
+        steps,
+    })
+}
+
+/// Routes `/alerts/escalations` and `/alerts/escalations/{id}`.
+pub async fn escalations_request(
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let path = request.uri().path().trim_end_matches('/').to_string();
+    let method = request.method().clone();
+
+    if path == "/alerts/escalations" {
+        return match method {
+            Method::GET => list_policies(storage).await,
+            Method::POST => create_policy(request, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        };
+    }
+
+    let Some(id) = path
+        .strip_prefix("/alerts/escalations/")
+        .and_then(|id| id.parse::<i64>().ok())
+    else {
+        return error_response(
+            "Política de escalonamento não encontrada",
+            StatusCode::NOT_FOUND,
+        )
+        .await;
+    };
+    match method {
+        Method::GET => get_policy(id, storage).await,
+        Method::PUT => update_policy(id, request, storage, true).await,
+        Method::PATCH => update_policy(id, request, storage, false).await,
+        Method::DELETE => delete_policy(id, storage).await,
+        _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+    }
+}
+
+/// Why the policy cannot be saved as is: a missing rule, monitor or channel.
+async fn invalid_reference(
+    settings: &EscalationSettings,
+    storage: &SharedStorage,
+) -> Option<String> {
+    if let Some(message) = missing_matcher_source(&settings.matcher, storage).await {
+        return Some(message.to_string());
+    }
+    for step in &settings.steps {
+        for channel_id in &step.channel_ids {
+            if !matches!(storage.get_channel(*channel_id).await, Ok(Some(_))) {
+                return Some(format!("Canal com ID {} não encontrado", channel_id));
+            }
+        }
+    }
+    None
+}
+
+async fn list_policies(storage: SharedStorage) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.get_escalation_policies().await {
+        Ok(policies) => {
+            let policies_data = policies
+                .iter()
+                .map(policy_json)
+                .collect::<Vec<serde_json::Value>>();
+            response(
+                &serde_json::json!({ "data": policies_data }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Err(e) => {
+            eprintln!("Error listing escalation policies: {}", e);
+            error_response(
+                "Erro ao listar políticas de escalonamento",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
+
+async fn get_policy(id: i64, storage: SharedStorage) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.get_escalation_policy(id).await {
+        Ok(Some(policy)) => {
+            response(
+                &serde_json::json!({ "data": policy_json(&policy) }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Ok(None) => {
+            error_response(
+                "Política de escalonamento não encontrada",
+                StatusCode::NOT_FOUND,
+            )
+            .await
+        }
+        Err(e) => {
+            eprintln!("Error loading escalation policy {}: {}", id, e);
+            error_response(
+                "Erro ao buscar política de escalonamento",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
+
+async fn create_policy(
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let json = match read_json(request).await {
+        Ok(json) => json,
+        Err(res) => return Ok(res),
+    };
+    let settings = match policy_fields(&json, None) {
+        Ok(settings) => settings,
+        Err(message) => return error_response(&message, StatusCode::BAD_REQUEST).await,
+    };
+    if let Some(message) = invalid_reference(&settings, &storage).await {
+        return error_response(&message, StatusCode::BAD_REQUEST).await;
+    }
+
+    match storage.create_escalation_policy(settings).await {
+        Ok(policy) => {
+            let mut res = response(
+                &serde_json::json!({
+                    "message": "Política de escalonamento criada com sucesso",
+                    "data": policy_json(&policy),
+                })
+                .to_string(),
+                StatusCode::CREATED,
+            )
+            .await?;
+            res.headers_mut().insert(
+                "Location",
+                HeaderValue::from_str(&format!("/alerts/escalations/{}", policy.id)).unwrap(),
+            );
+            Ok(res)
+        }
+        Err(e) => {
+            eprintln!("Error creating escalation policy: {}", e);
+            error_response(
+                "Erro ao criar política de escalonamento",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
+
+/// PUT replaces the policy, resetting omitted fields to their defaults, PATCH only changes
+/// the fields sent.
+async fn update_policy(
+    id: i64,
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+    replace: bool,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let json = match read_json(request).await {
+        Ok(json) => json,
+        Err(res) => return Ok(res),
+    };
+    let current = match storage.get_escalation_policy(id).await {
+        Ok(Some(current)) => current,
+        Ok(None) => {
+            return error_response(
+                "Política de escalonamento não encontrada",
+                StatusCode::NOT_FOUND,
+            )
+            .await
+        }
+        Err(e) => {
+            eprintln!("Error loading escalation policy {}: {}", id, e);
+            return error_response(
+                "Erro ao atualizar política de escalonamento",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await;
+        }
+    };
+    let settings = match policy_fields(&json, (!replace).then_some(&current.settings)) {
+        Ok(settings) => settings,
+        Err(message) => return error_response(&message, StatusCode::BAD_REQUEST).await,
+    };
+    if let Some(message) = invalid_reference(&settings, &storage).await {
+        return error_response(&message, StatusCode::BAD_REQUEST).await;
+    }
+
+    match storage.update_escalation_policy(id, settings).await {
+        Ok(Some(policy)) => {
+            response(
+                &serde_json::json!({ "data": policy_json(&policy) }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Ok(None) => {
+            error_response(
+                "Política de escalonamento não encontrada",
+                StatusCode::NOT_FOUND,
+            )
+            .await
+        }
+        Err(e) => {
+            eprintln!("Error updating escalation policy {}: {}", id, e);
+            error_response(
+                "Erro ao atualizar política de escalonamento",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
+
+/// Also stops the escalations in progress of the policy.
+async fn delete_policy(
+    id: i64,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.delete_escalation_policy(id).await {
+        Ok(true) => response(
+            &serde_json::json!({
+                "message": format!("Política de escalonamento com ID {} deletada com sucesso", id)
+            })
+            .to_string(),
+            StatusCode::OK,
+        )
+        .await,
+        Ok(false) => {
+            error_response(
+                "Política de escalonamento não encontrada",
+                StatusCode::NOT_FOUND,
+            )
+            .await
+        }
+        Err(e) => {
+            eprintln!("Error deleting escalation policy {}: {}", id, e);
+            error_response(
+                "Erro ao deletar política de escalonamento",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::convert::Infallible;
+
+use chrono::NaiveDateTime;
+use http::{Method, Request, Response, StatusCode};
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+
+use crate::{
+    database::{
+        error::StorageResult,
+        models::{Heartbeat, MaintenanceMode, MonitorStatus, UrlData},
+        storage::SharedStorage,
+        time::now,
+    },
+    server::request::{error_response, response},
+    services::{
+        monitor::{
+            heartbeat::{self, Ping},
+            maintenance,
+            status::{update_status, FlapDetection},
+        },
+        notifications::notifier::Notifier,
+    },
+};
+
+/// Routes the pings of heartbeat monitors: `/heartbeat/{token}` when a run succeeds,
+/// `/heartbeat/{token}/start` when it starts and `/heartbeat/{token}/fail` when it fails,
+/// with the body of a fail ping as the error. GET is accepted too so that a bare `curl` in
+/// a crontab is enough.
+pub async fn heartbeat_request(
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+    notifier: Notifier,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let path = request.uri().path().trim_end_matches('/').to_string();
+    let mut segments = path.trim_start_matches("/heartbeat/").split('/');
+    let token = segments.next().unwrap_or_default().to_string();
+    let ping = match (Ping::parse(segments.next()), segments.next()) {
+        (Some(ping), None) if !token.is_empty() => ping,
+        _ => return error_response("Heartbeat não encontrado", StatusCode::NOT_FOUND).await,
+    };
+    if !matches!(*request.method(), Method::GET | Method::POST) {
+        return error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await;
+    }
+    let message = match request.into_body().collect().await {
+        Ok(body) => String::from_utf8_lossy(&body.to_bytes()).into_owned(),
+        Err(_) => {
+            return error_response("Erro ao ler o corpo da requisição", StatusCode::BAD_REQUEST)
+                .await
+        }
+    };
+
+    let url_data = match storage.get_url_by_heartbeat_token(token).await {
+        Ok(Some(url_data)) => url_data,
+        Ok(None) => return error_response("Heartbeat não encontrado", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error loading heartbeat monitor: {}", e);
+            return error_response("Erro ao registrar ping", StatusCode::INTERNAL_SERVER_ERROR)
+                .await;
+        }
+    };
+    let Some(current) = url_data.heartbeat.clone() else {
+        return error_response("Heartbeat não encontrado", StatusCode::NOT_FOUND).await;
+    };
+    let at = now();
+
+    if ping != Ping::Start {
+        if let Err(e) = record(&storage, &notifier, &url_data, &current, ping, at, message).await {
+            eprintln!("Error saving ping of {}: {}", url_data.url, e);
+            return error_response("Erro ao registrar ping", StatusCode::INTERNAL_SERVER_ERROR)
+                .await;
+        }
+    }
+    let heartbeat = heartbeat::pinged(&current, &url_data.settings, ping, at);
+    if let Err(e) = storage
+        .save_heartbeat(url_data.id, Some(heartbeat.clone()))
+        .await
+    {
+        eprintln!("Error saving heartbeat of {}: {}", url_data.url, e);
+        return error_response("Erro ao registrar ping", StatusCode::INTERNAL_SERVER_ERROR).await;
+    }
+
+    response(
+        &serde_json::json!({
+            "message": "Ping registrado",
+            "data": {
+                "url_id": url_data.id,
+                "started_at": heartbeat.started_at,
+                "last_ping_at": heartbeat.last_ping_at,
+                "expected_at": heartbeat.expected_at,
+            },
+        })
+        .to_string(),
+        StatusCode::OK,
+    )
+    .await
+}
+
+/// Stores the check of a success or fail ping and updates the status of the monitor like a
+/// probe would, maintenance windows included.
+async fn record(
+    storage: &SharedStorage,
+    notifier: &Notifier,
+    url_data: &UrlData,
+    current: &Heartbeat,
+    ping: Ping,
+    at: NaiveDateTime,
+    message: String,
+) -> StorageResult<()> {
+    let windows = storage.get_maintenance_windows().await?;
+    let mut result = heartbeat::ping_result(current, ping, at, Some(message));
+    let status = match maintenance::active_window(&windows, url_data, at) {
+        Some(window) if window.settings.mode == MaintenanceMode::Pause => return Ok(()),
+        Some(_) => {
+            result.maintenance = true;
+            MonitorStatus {
+                up: url_data.up,
+                unconfirmed_checks: url_data.unconfirmed_checks,
+                flapping: url_data.flapping,
+            }
+        }
+        None => {
+            update_status(
+                storage,
+                notifier,
+                FlapDetection::from_env(),
+                url_data,
+                &result,
+            )
+            .await
+        }
+    };
+    storage.save_check_result(url_data.id, result, status).await
+}
@@ -1,16 +1,15 @@
-use std::{
-    env,
-    sync::{Arc, Mutex},
-};
+use std::env;
 
 use hyper::{server::conn::http1, service::service_fn};
 use hyper_util::rt::TokioIo;
-use rusqlite::Connection;
 use std::net::SocketAddr;
 use sysinfo::System;
 use tokio::net::TcpListener;
 
-use crate::server::request::received_request;
+use crate::{
+    database::storage::SharedStorage, server::request::received_request,
+    services::notifications::notifier::Notifier,
+};
 
 async fn create_host_addr() -> Result<TcpListener, Box<dyn std::error::Error>> {
     let host: [u8; 4] = env::var("HOST")
@@ -28,18 +27,20 @@ async fn create_host_addr() -> Result<TcpListener, Box<dyn std::error::Error>> {
     let listener = TcpListener::bind(addr).await?;
     println!("Listening on http://{}", addr);
 
-    return Ok(listener);
+    Ok(listener)
 }
 
 pub async fn start_http_server(
-    connection: Arc<Mutex<Connection>>,
+    storage: SharedStorage,
+    notifier: Notifier,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let listener = create_host_addr().await?;
 
     loop {
         let (stream, _) = listener.accept().await?;
         let sys: System = System::new_all();
-        let connection_thread = Arc::clone(&connection);
+        let storage_thread = storage.clone();
+        let notifier_thread = notifier.clone();
 
         let io = TokioIo::new(stream);
         tokio::task::spawn(async move {
@@ -48,7 +49,9 @@ pub async fn start_http_server(
             if let Err(err) = builder
                 .serve_connection(
                     io,
-                    service_fn(|req| received_request(req, &sys, connection_thread.clone())),
+                    service_fn(|req| {
+                        received_request(req, &sys, storage_thread.clone(), notifier_thread.clone())
+                    }),
                 )
                 .await
             {
@@ -0,0 +1,272 @@
+use std::{convert::Infallible, str::FromStr};
+
+use http::{HeaderValue, Method, Request, Response, StatusCode};
+use http_body_util::Full;
+use hyper::body::Bytes;
+
+use crate::{
+    database::{
+        models::{AlertSource, Incident, IncidentEvent, IncidentEventKind},
+        storage::SharedStorage,
+        time::{now, parse_timestamp},
+    },
+    server::request::{error_response, query_param, read_json, response},
+};
+
+const DEFAULT_LIMIT: u32 = 100;
+const MAX_LIMIT: u32 = 1000;
+const MAX_COMMENT_LENGTH: usize = 1000;
+
+/// `duration_seconds` runs up to now while the incident is open.
+fn incident_json(incident: &Incident) -> serde_json::Value {
+    let ended_at = incident
+        .resolved_at
+        .as_deref()
+        .and_then(parse_timestamp)
+        .unwrap_or_else(now);
+    let duration = parse_timestamp(&incident.started_at)
+        .map(|started_at| (ended_at - started_at).num_seconds().max(0));
+    serde_json::json!({
+        "id": incident.id,
+        "status": if incident.is_open() { "open" } else { "resolved" },
+        "alert_id": incident.alert_id,
+        "source": incident.source.as_str(),
+        "rule_id": incident.source.rule_id(),
+        "url_id": incident.source.url_id(),
+        "name": incident.name,
+        "labels": incident.labels,
+        "started_at": incident.started_at,
+        "acknowledged_at": incident.acknowledged_at,
+        "resolved_at": incident.resolved_at,
+        "duration_seconds": duration,
+    })
+}
+
+fn event_json(event: &IncidentEvent) -> serde_json::Value {
+    serde_json::json!({
+        "id": event.id,
+        "incident_id": event.incident_id,
+        "kind": event.kind.as_str(),
+        "message": event.message,
+        "created_at": event.created_at,
+    })
+}
+
+/// Reads an optional id from the query string, or the message of the 400 to return.
+fn id_param<T: FromStr>(
+    request: &Request<hyper::body::Incoming>,
+    name: &str,
+) -> Result<Option<T>, String> {
+    match query_param(request, name).map(|id| id.parse::<T>()) {
+        Some(Ok(id)) => Ok(Some(id)),
+        Some(Err(_)) => Err(format!("Parâmetro '{}' inválido", name)),
+        None => Ok(None),
+    }
+}
+
+/// Routes `/incidents`, `/incidents/{id}` and `/incidents/{id}/comments`.
+pub async fn incidents_request(
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let path = request.uri().path().trim_end_matches('/').to_string();
+    let method = request.method().clone();
+
+    if path == "/incidents" {
+        return match method {
+            Method::GET => list_incidents(&request, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        };
+    }
+
+    let Some(rest) = path.strip_prefix("/incidents/") else {
+        return error_response("Recurso não encontrado", StatusCode::NOT_FOUND).await;
+    };
+    let mut segments = rest.split('/');
+    let Some(id) = segments.next().and_then(|id| id.parse::<i64>().ok()) else {
+        return error_response("Incidente não encontrado", StatusCode::NOT_FOUND).await;
+    };
+    match (segments.next(), segments.next()) {
+        (None, _) => match method {
+            Method::GET => get_incident(id, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        },
+        (Some("comments"), None) => match method {
+            Method::POST => add_comment(id, request, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        },
+        _ => error_response("Recurso não encontrado", StatusCode::NOT_FOUND).await,
+    }
+}
+
+async fn list_incidents(
+    request: &Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let open = match query_param(request, "status").as_deref() {
+        None => None,
+        Some("open") => Some(true),
+        Some("resolved") => Some(false),
+        Some(_) => {
+            return error_response(
+                "Parâmetro 'status' deve ser 'open' ou 'resolved'",
+                StatusCode::BAD_REQUEST,
+            )
+            .await
+        }
+    };
+    let source = match (id_param(request, "rule_id"), id_param(request, "url_id")) {
+        (Err(message), _) | (_, Err(message)) => {
+            return error_response(&message, StatusCode::BAD_REQUEST).await
+        }
+        (Ok(Some(_)), Ok(Some(_))) => {
+            return error_response(
+                "Informe apenas um dos parâmetros 'rule_id' e 'url_id'",
+                StatusCode::BAD_REQUEST,
+            )
+            .await
+        }
+        (Ok(Some(rule_id)), Ok(None)) => Some(AlertSource::Rule(rule_id)),
+        (Ok(None), Ok(Some(url_id))) => Some(AlertSource::Monitor(url_id)),
+        (Ok(None), Ok(None)) => None,
+    };
+    let limit = match query_param(request, "limit").map(|limit| limit.parse::<u32>()) {
+        Some(Ok(limit)) if (1..=MAX_LIMIT).contains(&limit) => limit,
+        Some(_) => {
+            return error_response(
+                &format!("Parâmetro 'limit' deve estar entre 1 e {}", MAX_LIMIT),
+                StatusCode::BAD_REQUEST,
+            )
+            .await
+        }
+        None => DEFAULT_LIMIT,
+    };
+
+    match storage.get_incidents(open, source, limit).await {
+        Ok(incidents) => {
+            let incidents_data = incidents
+                .iter()
+                .map(incident_json)
+                .collect::<Vec<serde_json::Value>>();
+            response(
+                &serde_json::json!({ "data": incidents_data }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Err(e) => {
+            eprintln!("Error listing incidents: {}", e);
+            error_response(
+                "Erro ao listar incidentes",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
+
+/// The incident with its timeline, oldest event first.
+async fn get_incident(
+    id: i64,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let incident = match storage.get_incident(id).await {
+        Ok(Some(incident)) => incident,
+        Ok(None) => return error_response("Incidente não encontrado", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error loading incident {}: {}", id, e);
+            return error_response(
+                "Erro ao buscar incidente",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await;
+        }
+    };
+
+    match storage.get_incident_events(id).await {
+        Ok(events) => {
+            let mut incident_data = incident_json(&incident);
+            incident_data["timeline"] = events.iter().map(event_json).collect();
+            response(
+                &serde_json::json!({ "data": incident_data }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Err(e) => {
+            eprintln!("Error loading timeline of incident {}: {}", id, e);
+            error_response(
+                "Erro ao buscar incidente",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
+
+/// Adds a note to the timeline, resolved incidents included so post-mortem findings can be
+/// kept with them.
+async fn add_comment(
+    id: i64,
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let json = match read_json(request).await {
+        Ok(json) => json,
+        Err(res) => return Ok(res),
+    };
+    let comment = match json.get("comment") {
+        Some(serde_json::Value::String(comment)) if !comment.trim().is_empty() => {
+            comment.trim().to_string()
+        }
+        Some(_) => {
+            return error_response(
+                "Campo 'comment' deve ser uma string não vazia",
+                StatusCode::BAD_REQUEST,
+            )
+            .await
+        }
+        None => return error_response("Campo 'comment' ausente", StatusCode::BAD_REQUEST).await,
+    };
+    if comment.chars().count() > MAX_COMMENT_LENGTH {
+        return error_response(
+            &format!(
+                "Campo 'comment' deve ter no máximo {} caracteres",
+                MAX_COMMENT_LENGTH
+            ),
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+    }
+
+    match storage
+        .add_incident_event(id, IncidentEventKind::Comment, comment)
+        .await
+    {
+        Ok(Some(event)) => {
+            let mut res = response(
+                &serde_json::json!({
+                    "message": "Comentário adicionado com sucesso",
+                    "data": event_json(&event),
+                })
+                .to_string(),
+                StatusCode::CREATED,
+            )
+            .await?;
+            res.headers_mut().insert(
+                "Location",
+                HeaderValue::from_str(&format!("/incidents/{}", id)).unwrap(),
+            );
+            Ok(res)
+        }
+        Ok(None) => error_response("Incidente não encontrado", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error commenting on incident {}: {}", id, e);
+            error_response(
+                "Erro ao adicionar comentário",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
@@ -0,0 +1,389 @@
+use std::convert::Infallible;
+
+use http::{HeaderValue, Method, Request, Response, StatusCode};
+use http_body_util::Full;
+use hyper::body::Bytes;
+
+use crate::{
+    database::{
+        models::{MaintenanceMode, MaintenanceSchedule, MaintenanceSettings, MaintenanceWindow},
+        storage::SharedStorage,
+        time::{format_timestamp, now, parse_timestamp},
+    },
+    server::request::{error_response, read_json, response},
+    services::{alerts::expression::parse_duration, monitor::maintenance},
+};
+
+fn window_json(window: &MaintenanceWindow) -> serde_json::Value {
+    let settings = &window.settings;
+    let (starts_at, ends_at, cron, duration) = match &settings.schedule {
+        MaintenanceSchedule::Once { starts_at, ends_at } => {
+            (Some(starts_at), Some(ends_at), None, None)
+        }
+        MaintenanceSchedule::Recurring { cron, duration } => {
+            (None, None, Some(cron), Some(duration))
+        }
+    };
+    serde_json::json!({
+        "id": window.id,
+        "name": settings.name,
+        "mode": settings.mode.as_str(),
+        "starts_at": starts_at,
+        "ends_at": ends_at,
+        "cron": cron,
+        "duration": duration,
+        "url_ids": settings.url_ids,
+        "hosts": settings.hosts,
+        "active": maintenance::is_active(&settings.schedule, now()),
+        "next_start": maintenance::next_start(&settings.schedule, now())
+            .map(|start| format_timestamp(&start)),
+        "created_at": window.created_at,
+    })
+}
+
+fn timestamp_field(json: &serde_json::Value, name: &str) -> Result<String, String> {
+    match json.get(name) {
+        Some(value) => value
+            .as_str()
+            .and_then(parse_timestamp)
+            .map(|timestamp| format_timestamp(&timestamp))
+            .ok_or(format!("Campo '{}' inválido", name)),
+        None => Err(format!("Campo '{}' ausente", name)),
+    }
+}
+
+/// `starts_at` and `ends_at` for a one-off window, `cron` and `duration` for a recurring one.
+fn schedule_field(json: &serde_json::Value) -> Result<MaintenanceSchedule, String> {
+    if json.get("cron").is_some() {
+        if json.get("starts_at").is_some() || json.get("ends_at").is_some() {
+            return Err("Use 'cron' ou 'starts_at' e 'ends_at', não ambos".into());
+        }
+        let Some(cron) = json["cron"].as_str().map(str::trim) else {
+            return Err("Campo 'cron' deve ser uma string".into());
+        };
+        maintenance::parse_cron(cron)?;
+        let duration = match json.get("duration") {
+            Some(duration) => parse_duration(duration.as_str().unwrap_or_default())?,
+            None => return Err("Campo 'duration' ausente".into()),
+        };
+        if duration == 0 {
+            return Err("Campo 'duration' deve ser maior que zero".into());
+        }
+        return Ok(MaintenanceSchedule::Recurring {
+            cron: cron.to_string(),
+            duration,
+        });
+    }
+
+    if json.get("duration").is_some() {
+        return Err("Campo 'duration' exige 'cron'".into());
+    }
+    let starts_at = timestamp_field(json, "starts_at")?;
+    let ends_at = timestamp_field(json, "ends_at")?;
+    if ends_at <= starts_at {
+        return Err("O fim da janela deve ser posterior ao início".into());
+    }
+    Ok(MaintenanceSchedule::Once { starts_at, ends_at })
+}
+
+/// Reads the window fields from `json`, keeping the values of `current` for the ones
+/// missing. Any schedule field replaces the current schedule entirely.
+fn window_fields(
+    json: &serde_json::Value,
+    current: Option<&MaintenanceSettings>,
+) -> Result<MaintenanceSettings, String> {
+    let name = match (json.get("name"), current) {
+        (Some(serde_json::Value::String(name)), _) if !name.trim().is_empty() => {
+            name.trim().to_string()
+        }
+        (Some(_), _) => return Err("Campo 'name' deve ser uma string não vazia".into()),
+        (None, Some(current)) => current.name.clone(),
+        (None, None) => return Err("Campo 'name' ausente".into()),
+    };
+
+    let mode = match (json.get("mode"), current) {
+        (Some(mode), _) => mode
+            .as_str()
+            .and_then(MaintenanceMode::parse)
+            .ok_or("Campo 'mode' deve ser 'pause' ou 'mark'")?,
+        (None, Some(current)) => current.mode,
+        (None, None) => MaintenanceMode::Pause,
+    };
+
+    let schedule_sent = ["starts_at", "ends_at", "cron", "duration"]
+        .iter()
+        .any(|name| json.get(name).is_some());
+    let schedule = match current {
+        Some(current) if !schedule_sent => current.schedule.clone(),
+        _ => schedule_field(json)?,
+    };
+
+    let url_ids = match (json.get("url_ids"), current) {
+        (Some(url_ids), _) => url_ids
+            .as_array()
+            .and_then(|url_ids| {
+                url_ids
+                    .iter()
+                    .map(|id| id.as_i64().and_then(|id| i32::try_from(id).ok()))
+                    .collect::<Option<Vec<i32>>>()
+            })
+            .ok_or("Campo 'url_ids' deve ser uma lista de IDs")?,
+        (None, Some(current)) => current.url_ids.clone(),
+        (None, None) => Vec::new(),
+    };
+
+    let hosts = match (json.get("hosts"), current) {
+        (Some(hosts), _) => hosts
+            .as_array()
+            .and_then(|hosts| {
+                hosts
+                    .iter()
+                    .map(|host| {
+                        host.as_str()
+                            .map(|host| host.trim().to_ascii_lowercase())
+                            .filter(|host| !host.is_empty())
+                    })
+                    .collect::<Option<Vec<String>>>()
+            })
+            .ok_or("Campo 'hosts' deve ser uma lista de hosts")?,
+        (None, Some(current)) => current.hosts.clone(),
+        (None, None) => Vec::new(),
+    };
+    if url_ids.is_empty() && hosts.is_empty() {
+        return Err("Informe ao menos um de 'url_ids' ou 'hosts'".into());
+    }
+
+    Ok(MaintenanceSettings {
+        name,
+        mode,
+        schedule,
+        url_ids,
+        hosts,
+    })
+}
+
+/// Routes `/maintenance` and `/maintenance/{id}`.
+pub async fn maintenance_request(
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let path = request.uri().path().trim_end_matches('/').to_string();
+    let method = request.method().clone();
+
+    if path == "/maintenance" {
+        return match method {
+            Method::GET => list_windows(storage).await,
+            Method::POST => create_window(request, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        };
+    }
+
+    let Some(id) = path
+        .strip_prefix("/maintenance/")
+        .and_then(|id| id.parse::<i64>().ok())
+    else {
+        return error_response("Janela de manutenção não encontrada", StatusCode::NOT_FOUND).await;
+    };
+    match method {
+        Method::GET => get_window(id, storage).await,
+        Method::PUT => update_window(id, request, storage, true).await,
+        Method::PATCH => update_window(id, request, storage, false).await,
+        Method::DELETE => delete_window(id, storage).await,
+        _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+    }
+}
+
+/// The first of `url_ids` without a monitor, if any.
+async fn missing_url(url_ids: &[i32], storage: &SharedStorage) -> Option<i32> {
+    for url_id in url_ids {
+        if !matches!(storage.get_url(*url_id).await, Ok(Some(_))) {
+            return Some(*url_id);
+        }
+    }
+    None
+}
+
+async fn list_windows(storage: SharedStorage) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.get_maintenance_windows().await {
+        Ok(windows) => {
+            let windows_data = windows
+                .iter()
+                .map(window_json)
+                .collect::<Vec<serde_json::Value>>();
+            response(
+                &serde_json::json!({ "data": windows_data }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Err(e) => {
+            eprintln!("Error listing maintenance windows: {}", e);
+            error_response(
+                "Erro ao listar janelas de manutenção",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
+
+async fn get_window(id: i64, storage: SharedStorage) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.get_maintenance_window(id).await {
+        Ok(Some(window)) => {
+            response(
+                &serde_json::json!({ "data": window_json(&window) }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Ok(None) => {
+            error_response("Janela de manutenção não encontrada", StatusCode::NOT_FOUND).await
+        }
+        Err(e) => {
+            eprintln!("Error loading maintenance window {}: {}", id, e);
+            error_response(
+                "Erro ao buscar janela de manutenção",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
+
+async fn create_window(
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let json = match read_json(request).await {
+        Ok(json) => json,
+        Err(res) => return Ok(res),
+    };
+    let settings = match window_fields(&json, None) {
+        Ok(settings) => settings,
+        Err(message) => return error_response(&message, StatusCode::BAD_REQUEST).await,
+    };
+    if let Some(url_id) = missing_url(&settings.url_ids, &storage).await {
+        return error_response(
+            &format!("URL com ID {} não encontrada", url_id),
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+    }
+
+    match storage.create_maintenance_window(settings).await {
+        Ok(window) => {
+            let mut res = response(
+                &serde_json::json!({
+                    "message": "Janela de manutenção criada com sucesso",
+                    "data": window_json(&window),
+                })
+                .to_string(),
+                StatusCode::CREATED,
+            )
+            .await?;
+            res.headers_mut().insert(
+                "Location",
+                HeaderValue::from_str(&format!("/maintenance/{}", window.id)).unwrap(),
+            );
+            Ok(res)
+        }
+        Err(e) => {
+            eprintln!("Error creating maintenance window: {}", e);
+            error_response(
+                "Erro ao criar janela de manutenção",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
+
+/// PUT replaces the window, resetting omitted fields to their defaults, PATCH only changes
+/// the fields sent.
+async fn update_window(
+    id: i64,
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+    replace: bool,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let json = match read_json(request).await {
+        Ok(json) => json,
+        Err(res) => return Ok(res),
+    };
+    let current = match storage.get_maintenance_window(id).await {
+        Ok(Some(current)) => current,
+        Ok(None) => {
+            return error_response("Janela de manutenção não encontrada", StatusCode::NOT_FOUND)
+                .await
+        }
+        Err(e) => {
+            eprintln!("Error loading maintenance window {}: {}", id, e);
+            return error_response(
+                "Erro ao atualizar janela de manutenção",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await;
+        }
+    };
+    let settings = match window_fields(&json, (!replace).then_some(&current.settings)) {
+        Ok(settings) => settings,
+        Err(message) => return error_response(&message, StatusCode::BAD_REQUEST).await,
+    };
+    if let Some(url_id) = missing_url(&settings.url_ids, &storage).await {
+        return error_response(
+            &format!("URL com ID {} não encontrada", url_id),
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+    }
+
+    match storage.update_maintenance_window(id, settings).await {
+        Ok(Some(window)) => {
+            response(
+                &serde_json::json!({ "data": window_json(&window) }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Ok(None) => {
+            error_response("Janela de manutenção não encontrada", StatusCode::NOT_FOUND).await
+        }
+        Err(e) => {
+            eprintln!("Error updating maintenance window {}: {}", id, e);
+            error_response(
+                "Erro ao atualizar janela de manutenção",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
+
+async fn delete_window(
+    id: i64,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.delete_maintenance_window(id).await {
+        Ok(true) => {
+            response(
+                &serde_json::json!({
+                    "message": format!("Janela de manutenção com ID {} deletada com sucesso", id)
+                })
+                .to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Ok(false) => {
+            error_response("Janela de manutenção não encontrada", StatusCode::NOT_FOUND).await
+        }
+        Err(e) => {
+            eprintln!("Error deleting maintenance window {}: {}", id, e);
+            error_response(
+                "Erro ao deletar janela de manutenção",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+        }
+    }
+}
@@ -1,2 +1,10 @@
+pub mod alerts;
+pub mod checks;
+pub mod escalations;
+pub mod heartbeat;
 pub mod http;
-pub mod request;
\ No newline at end of file
+pub mod incidents;
+pub mod maintenance;
+pub mod notifications;
+pub mod request;
+pub mod urls;
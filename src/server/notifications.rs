@@ -0,0 +1,575 @@
+use std::convert::Infallible;
+
+use http::{HeaderValue, Method, Request, Response, StatusCode};
+use http_body_util::Full;
+use hyper::body::Bytes;
+
+use crate::{
+    database::{
+        models::{
+            ChannelConfig, DeliveryResult, EmailConfig, EventKind, NotificationChannel,
+            NotificationDelivery, TelegramConfig, WebhookConfig,
+        },
+        storage::SharedStorage,
+    },
+    server::request::{error_response, query_param, read_json, response},
+    services::notifications::{
+        email::parse_mailbox, notification::Notification, notifier::Notifier,
+    },
+};
+
+const DEFAULT_LIMIT: u32 = 100;
+const MAX_LIMIT: u32 = 1000;
+const WEBHOOK_METHODS: [&str; 4] = ["POST", "PUT", "PATCH", "GET"];
+const SECRET_FIELDS: [&str; 2] = ["password", "bot_token"];
+
+/// The SMTP password, the Telegram bot token and the incoming webhook URL of chat channels
+/// are never sent back, only whether there is one. Webhook headers often carry credentials,
+/// only their names are.
+pub fn channel_json(channel: &NotificationChannel) -> serde_json::Value {
+    let mut config = channel.config.to_json();
+    let secret_fields = match channel.config {
+        ChannelConfig::Slack(_) | ChannelConfig::Discord(_) | ChannelConfig::Teams(_) => {
+            &["url"][..]
+        }
+        _ => &SECRET_FIELDS[..],
+    };
+    if let Some(config) = config.as_object_mut() {
+        for secret in secret_fields {
+            if let Some(value) = config.remove(*secret) {
+                config.insert(format!("{}_set", secret), (!value.is_null()).into());
+            }
+        }
+        if let Some(serde_json::Value::Object(headers)) = config.remove("headers") {
+            config.insert(
+                "header_names".into(),
+                headers.keys().cloned().collect::<Vec<String>>().into(),
+            );
+        }
+    }
+    serde_json::json!({
+        "id": channel.id,
+        "name": channel.name,
+        "type": channel.config.kind(),
+        "config": config,
+        "events": channel
+            .events
+            .iter()
+            .map(EventKind::as_str)
+            .collect::<Vec<&str>>(),
+        "enabled": channel.enabled,
+        "created_at": channel.created_at,
+    })
+}
+
+fn result_json(result: &DeliveryResult) -> serde_json::Value {
+    serde_json::json!({
+        "event": result.event.as_str(),
+        "subject": result.subject,
+        "attempt": result.attempt,
+        "success": result.success,
+        "status_code": result.status_code,
+        "error": result.error,
+    })
+}
+
+fn delivery_json(delivery: &NotificationDelivery) -> serde_json::Value {
+    let mut json = result_json(&delivery.result);
+    json["id"] = delivery.id.into();
+    json["channel_id"] = delivery.channel_id.into();
+    json["created_at"] = delivery.created_at.clone().into();
+    json
+}
+
+/// Validated fields of a channel, as sent to the storage.
+struct ChannelFields {
+    name: String,
+    config: ChannelConfig,
+    events: Vec<EventKind>,
+    enabled: bool,
+}
+
+fn http_url_field(value: &str, name: &str) -> Result<(), String> {
+    let url =
+        url::Url::parse(value).map_err(|_| format!("Campo '{}' inválido: {}", name, value))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(format!("Campo '{}' deve usar http:// ou https://", name));
+    }
+    Ok(())
+}
+
+/// A config without `headers` keeps the ones of `current`, since the API never shows their
+/// values.
+fn webhook_config(
+    json: &serde_json::Value,
+    current: Option<&ChannelConfig>,
+) -> Result<WebhookConfig, String> {
+    let ChannelConfig::Webhook(mut webhook) = ChannelConfig::from_json("webhook", json)? else {
+        unreachable!("webhook config parsed as another channel type");
+    };
+    if json.get("headers").is_none() {
+        if let Some(ChannelConfig::Webhook(current)) = current {
+            webhook.headers = current.headers.clone();
+        }
+    }
+    http_url_field(&webhook.url, "url")?;
+    webhook.method = webhook.method.to_uppercase();
+    if !WEBHOOK_METHODS.contains(&webhook.method.as_str()) {
+        return Err(format!(
+            "Campo 'method' deve ser um de {}",
+            WEBHOOK_METHODS.join(", ")
+        ));
+    }
+    for (name, value) in &webhook.headers {
+        if http::HeaderName::from_bytes(name.as_bytes()).is_err()
+            || HeaderValue::from_str(value).is_err()
+        {
+            return Err(format!("Cabeçalho '{}' inválido", name));
+        }
+    }
+    Ok(webhook)
+}
+
+/// A config without `password` keeps the one of `current`, since the API never shows it.
+fn email_config(
+    json: &serde_json::Value,
+    current: Option<&ChannelConfig>,
+) -> Result<EmailConfig, String> {
+    let ChannelConfig::Email(mut email) = ChannelConfig::from_json("email", json)? else {
+        unreachable!("email config parsed as another channel type");
+    };
+    email.host = email.host.trim().to_string();
+    if email.host.is_empty() {
+        return Err("Campo 'host' vazio".into());
+    }
+    parse_mailbox(&email.from)?;
+    if email.to.is_empty() {
+        return Err("Campo 'to' deve ter ao menos um endereço".into());
+    }
+    for address in &email.to {
+        parse_mailbox(address)?;
+    }
+    if json.get("password").is_none() {
+        if let Some(ChannelConfig::Email(current)) = current {
+            email.password = current.password.clone();
+        }
+    }
+    if email.password.is_some() && email.username.is_none() {
+        return Err("Campo 'password' exige 'username'".into());
+    }
+    Ok(email)
+}
+
+/// Slack, Discord and Teams channels only need the incoming webhook URL. A config without
+/// `url` keeps the one of `current` when it is of the same type, since the API never shows
+/// it.
+fn chat_config(
+    kind: &str,
+    json: &serde_json::Value,
+    current: Option<&ChannelConfig>,
+) -> Result<ChannelConfig, String> {
+    let mut json = json.clone();
+    if json.get("url").is_none() {
+        if let Some(
+            ChannelConfig::Slack(current)
+            | ChannelConfig::Discord(current)
+            | ChannelConfig::Teams(current),
+        ) = current.filter(|current| current.kind() == kind)
+        {
+            json["url"] = current.url.clone().into();
+        }
+    }
+    let config = ChannelConfig::from_json(kind, &json)?;
+    if let ChannelConfig::Slack(chat) | ChannelConfig::Discord(chat) | ChannelConfig::Teams(chat) =
+        &config
+    {
+        http_url_field(&chat.url, "url")?;
+    }
+    Ok(config)
+}
+
+/// A config without `bot_token` keeps the one of `current`, since the API never shows it.
+fn telegram_config(
+    json: &serde_json::Value,
+    current: Option<&ChannelConfig>,
+) -> Result<TelegramConfig, String> {
+    let mut json = json.clone();
+    if json.get("bot_token").is_none() {
+        if let Some(ChannelConfig::Telegram(current)) = current {
+            json["bot_token"] = current.bot_token.clone().into();
+        }
+    }
+    let ChannelConfig::Telegram(mut telegram) = ChannelConfig::from_json("telegram", &json)? else {
+        unreachable!("telegram config parsed as another channel type");
+    };
+    telegram.bot_token = telegram.bot_token.trim().to_string();
+    if telegram.bot_token.is_empty() || telegram.bot_token.contains('/') {
+        return Err("Campo 'bot_token' inválido".into());
+    }
+    telegram.chat_id = telegram.chat_id.trim().to_string();
+    if telegram.chat_id.is_empty() {
+        return Err("Campo 'chat_id' vazio".into());
+    }
+    http_url_field(&telegram.api_url, "api_url")?;
+    Ok(telegram)
+}
+
+/// Reads the channel fields from `json`, keeping the values of `current` for the ones
+/// missing. A new `config` replaces the current one entirely.
+fn channel_fields(
+    json: &serde_json::Value,
+    current: Option<&NotificationChannel>,
+) -> Result<ChannelFields, String> {
+    let name = match (json.get("name"), current) {
+        (Some(serde_json::Value::String(name)), _) if !name.trim().is_empty() => {
+            name.trim().to_string()
+        }
+        (Some(_), _) => return Err("Campo 'name' deve ser uma string não vazia".into()),
+        (None, Some(current)) => current.name.clone(),
+        (None, None) => return Err("Campo 'name' ausente".into()),
+    };
+
+    let kind = match (json.get("type"), current) {
+        (Some(kind), _) => kind.as_str().unwrap_or_default().to_string(),
+        (None, Some(current)) => current.config.kind().to_string(),
+        (None, None) => "webhook".to_string(),
+    };
+    let config = match (json.get("config"), current) {
+        (Some(config), _) if config.is_object() => match kind.as_str() {
+            "webhook" => ChannelConfig::Webhook(webhook_config(
+                config,
+                current.map(|current| &current.config),
+            )?),
+            "email" => ChannelConfig::Email(email_config(
+                config,
+                current.map(|current| &current.config),
+            )?),
+            "slack" | "discord" | "teams" => {
+                chat_config(&kind, config, current.map(|current| &current.config))?
+            }
+            "telegram" => ChannelConfig::Telegram(telegram_config(
+                config,
+                current.map(|current| &current.config),
+            )?),
+            _ => {
+                return Err(format!(
+                    "Tipo de canal inválido, use um de {}",
+                    ChannelConfig::KINDS.join(", ")
+                ))
+            }
+        },
+        (Some(_), _) => return Err("Campo 'config' deve ser um objeto".into()),
+        (None, Some(current)) if current.config.kind() == kind => current.config.clone(),
+        (None, _) => return Err("Campo 'config' ausente".into()),
+    };
+
+    let events = match (json.get("events"), current) {
+        (Some(events), _) => {
+            let Some(events) = events.as_array() else {
+                return Err("Campo 'events' deve ser uma lista".into());
+            };
+            events
+                .iter()
+                .map(|event| {
+                    event.as_str().and_then(EventKind::parse).ok_or(format!(
+                        "Evento inválido, use um de {}",
+                        EventKind::ALL
+                            .iter()
+                            .map(EventKind::as_str)
+                            .collect::<Vec<&str>>()
+                            .join(", ")
+                    ))
+                })
+                .collect::<Result<Vec<EventKind>, String>>()?
+        }
+        (None, Some(current)) => current.events.clone(),
+        (None, None) => Vec::new(),
+    };
+
+    let enabled = match (json.get("enabled"), current) {
+        (Some(enabled), _) => enabled
+            .as_bool()
+            .ok_or("Campo 'enabled' deve ser booleano")?,
+        (None, Some(current)) => current.enabled,
+        (None, None) => true,
+    };
+
+    Ok(ChannelFields {
+        name,
+        config,
+        events,
+        enabled,
+    })
+}
+
+/// Routes `/notifications/channels`, `/notifications/channels/{id}` and
+/// `/notifications/deliveries`.
+pub async fn notifications_request(
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+    notifier: Notifier,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let path = request.uri().path().trim_end_matches('/').to_string();
+    let method = request.method().clone();
+
+    if path == "/notifications/deliveries" {
+        return match method {
+            Method::GET => list_deliveries(&request, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        };
+    }
+
+    if path == "/notifications/channels" {
+        return match method {
+            Method::GET => list_channels(storage).await,
+            Method::POST => create_channel(request, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        };
+    }
+
+    let Some(rest) = path.strip_prefix("/notifications/channels/") else {
+        return error_response("Recurso não encontrado", StatusCode::NOT_FOUND).await;
+    };
+    let mut segments = rest.split('/');
+    let Some(id) = segments.next().and_then(|id| id.parse::<i64>().ok()) else {
+        return error_response("Canal não encontrado", StatusCode::NOT_FOUND).await;
+    };
+
+    match (segments.next(), segments.next()) {
+        (None, _) => match method {
+            Method::GET => get_channel(id, storage).await,
+            Method::PUT => update_channel(id, request, storage, true).await,
+            Method::PATCH => update_channel(id, request, storage, false).await,
+            Method::DELETE => delete_channel(id, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        },
+        (Some("test"), None) => match method {
+            Method::POST => test_channel(id, storage, &notifier).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        },
+        _ => error_response("Recurso não encontrado", StatusCode::NOT_FOUND).await,
+    }
+}
+
+async fn list_channels(storage: SharedStorage) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.get_channels().await {
+        Ok(channels) => {
+            let channels_data = channels
+                .iter()
+                .map(channel_json)
+                .collect::<Vec<serde_json::Value>>();
+            response(
+                &serde_json::json!({ "data": channels_data }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Err(e) => {
+            eprintln!("Error listing notification channels: {}", e);
+            error_response("Erro ao listar canais", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+async fn get_channel(id: i64, storage: SharedStorage) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.get_channel(id).await {
+        Ok(Some(channel)) => {
+            response(
+                &serde_json::json!({ "data": channel_json(&channel) }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Ok(None) => error_response("Canal não encontrado", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error loading notification channel {}: {}", id, e);
+            error_response("Erro ao buscar canal", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+async fn create_channel(
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let json = match read_json(request).await {
+        Ok(json) => json,
+        Err(res) => return Ok(res),
+    };
+    let fields = match channel_fields(&json, None) {
+        Ok(fields) => fields,
+        Err(message) => return error_response(&message, StatusCode::BAD_REQUEST).await,
+    };
+
+    match storage
+        .create_channel(fields.name, fields.config, fields.events, fields.enabled)
+        .await
+    {
+        Ok(channel) => {
+            let mut res = response(
+                &serde_json::json!({
+                    "message": "Canal criado com sucesso",
+                    "data": channel_json(&channel),
+                })
+                .to_string(),
+                StatusCode::CREATED,
+            )
+            .await?;
+            res.headers_mut().insert(
+                "Location",
+                HeaderValue::from_str(&format!("/notifications/channels/{}", channel.id)).unwrap(),
+            );
+            Ok(res)
+        }
+        Err(e) => {
+            eprintln!("Error creating notification channel: {}", e);
+            error_response("Erro ao criar canal", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+/// PUT replaces the channel, resetting omitted fields to their defaults, PATCH only changes
+/// the fields sent.
+async fn update_channel(
+    id: i64,
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+    replace: bool,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let json = match read_json(request).await {
+        Ok(json) => json,
+        Err(res) => return Ok(res),
+    };
+    let current = match storage.get_channel(id).await {
+        Ok(Some(current)) => current,
+        Ok(None) => return error_response("Canal não encontrado", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error loading notification channel {}: {}", id, e);
+            return error_response("Erro ao atualizar canal", StatusCode::INTERNAL_SERVER_ERROR)
+                .await;
+        }
+    };
+    let fields = match channel_fields(&json, (!replace).then_some(&current)) {
+        Ok(fields) => fields,
+        Err(message) => return error_response(&message, StatusCode::BAD_REQUEST).await,
+    };
+
+    match storage
+        .update_channel(
+            id,
+            fields.name,
+            fields.config,
+            fields.events,
+            fields.enabled,
+        )
+        .await
+    {
+        Ok(Some(channel)) => {
+            response(
+                &serde_json::json!({ "data": channel_json(&channel) }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Ok(None) => error_response("Canal não encontrado", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error updating notification channel {}: {}", id, e);
+            error_response("Erro ao atualizar canal", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+async fn delete_channel(
+    id: i64,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.delete_channel(id).await {
+        Ok(true) => {
+            response(
+                &serde_json::json!({
+                    "message": format!("Canal com ID {} deletado com sucesso", id)
+                })
+                .to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Ok(false) => error_response("Canal não encontrado", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error deleting notification channel {}: {}", id, e);
+            error_response("Erro ao deletar canal", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+/// Sends a test notification once, without retries, and returns the attempt.
+async fn test_channel(
+    id: i64,
+    storage: SharedStorage,
+    notifier: &Notifier,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let channel = match storage.get_channel(id).await {
+        Ok(Some(channel)) => channel,
+        Ok(None) => return error_response("Canal não encontrado", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error loading notification channel {}: {}", id, e);
+            return error_response("Erro ao testar canal", StatusCode::INTERNAL_SERVER_ERROR).await;
+        }
+    };
+
+    let result = notifier
+        .send(&channel, &Notification::test(&channel.name), 1)
+        .await;
+    let status = if result.success {
+        StatusCode::OK
+    } else {
+        StatusCode::BAD_GATEWAY
+    };
+    response(
+        &serde_json::json!({ "data": result_json(&result) }).to_string(),
+        status,
+    )
+    .await
+}
+
+async fn list_deliveries(
+    request: &Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let channel_id = match query_param(request, "channel_id").map(|id| id.parse::<i64>()) {
+        Some(Ok(id)) => Some(id),
+        Some(Err(_)) => {
+            return error_response("Parâmetro 'channel_id' inválido", StatusCode::BAD_REQUEST).await
+        }
+        None => None,
+    };
+    let limit = match query_param(request, "limit").map(|limit| limit.parse::<u32>()) {
+        Some(Ok(limit)) if (1..=MAX_LIMIT).contains(&limit) => limit,
+        Some(_) => {
+            return error_response(
+                &format!("Parâmetro 'limit' deve estar entre 1 e {}", MAX_LIMIT),
+                StatusCode::BAD_REQUEST,
+            )
+            .await
+        }
+        None => DEFAULT_LIMIT,
+    };
+
+    match storage.get_deliveries(channel_id, limit).await {
+        Ok(deliveries) => {
+            let deliveries_data = deliveries
+                .iter()
+                .map(delivery_json)
+                .collect::<Vec<serde_json::Value>>();
+            response(
+                &serde_json::json!({ "data": deliveries_data }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Err(e) => {
+            eprintln!("Error listing notification deliveries: {}", e);
+            error_response("Erro ao listar entregas", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
@@ -1,15 +1,17 @@
-use std::{
-    convert::Infallible,
-    sync::{Arc, Mutex},
-};
+use std::convert::Infallible;
 
 use http::{HeaderValue, Method, Request, Response, StatusCode};
 use http_body_util::{BodyExt, Full};
-use hyper::body::{Bytes, Frame};
-use rusqlite::Connection;
+use hyper::body::Bytes;
 use sysinfo::System;
 
-use crate::{database, services};
+use crate::{
+    database::storage::SharedStorage,
+    server,
+    services::{self, notifications::notifier::Notifier},
+};
+
+const ALLOW_METHODS: &str = "GET, POST, PUT, PATCH, DELETE, OPTIONS";
 
 fn request_options(
     allow_origin: HeaderValue,
@@ -27,12 +29,12 @@ fn request_options(
         headers.insert("Access-Control-Allow-Headers", allow_headers.clone());
         // headers.insert("Access-Control-Allow-Credentials", HeaderValue::from_static("true"));
     }
-    return Ok(res);
+    Ok(res)
 }
 
-async fn response(data: &str, status: StatusCode) -> Result<Response<Full<Bytes>>, Infallible> {
+pub async fn response(data: &str, status: StatusCode) -> Result<Response<Full<Bytes>>, Infallible> {
     let allow_origin = HeaderValue::from_static("*");
-    let allow_methods = HeaderValue::from_static("GET, POST, DELETE, OPTIONS");
+    let allow_methods = HeaderValue::from_static(ALLOW_METHODS);
     let allow_headers = HeaderValue::from_static("Content-Type, Authorization");
 
     let res = Response::builder()
@@ -47,13 +49,71 @@ async fn response(data: &str, status: StatusCode) -> Result<Response<Full<Bytes>
     Ok(res)
 }
 
+pub async fn error_response(
+    message: &str,
+    status: StatusCode,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    response(&serde_json::json!({ "error": message }).to_string(), status).await
+}
+
+/// Returns the percent-decoded value of `name` in the request query string, if present.
+pub fn query_param(request: &Request<hyper::body::Incoming>, name: &str) -> Option<String> {
+    url::form_urlencoded::parse(request.uri().query()?.as_bytes())
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+}
+
+/// Reads the request body as JSON, or builds the 400 response to return when it is not.
+pub async fn read_json(
+    request: Request<hyper::body::Incoming>,
+) -> Result<serde_json::Value, Response<Full<Bytes>>> {
+    read_body(request, false).await
+}
+
+/// Like `read_json`, an empty body reading as an empty object, for requests whose fields are
+/// all optional.
+pub async fn read_optional_json(
+    request: Request<hyper::body::Incoming>,
+) -> Result<serde_json::Value, Response<Full<Bytes>>> {
+    read_body(request, true).await
+}
+
+async fn read_body(
+    request: Request<hyper::body::Incoming>,
+    allow_empty: bool,
+) -> Result<serde_json::Value, Response<Full<Bytes>>> {
+    let body = match request.into_body().collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(_) => {
+            return Err(response(
+                "{\"error\": \"Erro ao ler o corpo da requisição\"}",
+                StatusCode::BAD_REQUEST,
+            )
+            .await
+            .unwrap())
+        }
+    };
+    if allow_empty && body.trim_ascii().is_empty() {
+        return Ok(serde_json::json!({}));
+    }
+    match serde_json::from_slice::<serde_json::Value>(&body) {
+        Ok(json) if json.is_object() => Ok(json),
+        _ => Err(
+            response("{\"error\": \"JSON inválido\"}", StatusCode::BAD_REQUEST)
+                .await
+                .unwrap(),
+        ),
+    }
+}
+
 pub async fn received_request(
     request: Request<hyper::body::Incoming>,
     sys: &System,
-    con: Arc<Mutex<Connection>>,
+    storage: SharedStorage,
+    notifier: Notifier,
 ) -> Result<Response<Full<Bytes>>, Infallible> {
     let allow_origin = HeaderValue::from_static("*");
-    let allow_methods = HeaderValue::from_static("GET, POST, DELETE, OPTIONS");
+    let allow_methods = HeaderValue::from_static(ALLOW_METHODS);
     let allow_headers = HeaderValue::from_static("Content-Type, Authorization");
 
     if request.method() == Method::OPTIONS {
@@ -61,7 +121,7 @@ pub async fn received_request(
     }
 
     if request.uri().path() == "/memory" {
-        let memory_info = services::os::memory::print_memory_info(&sys);
+        let memory_info = services::os::memory::print_memory_info(sys);
         let response_data = format!(
             "{{\"total_memory\": {},\"used_memory\": {},\"free_memory\": {}}}",
             memory_info[0], memory_info[1], memory_info[2]
@@ -75,19 +135,23 @@ pub async fn received_request(
     }
 
     if request.uri().path() == "/cpu" {
-        let cpu_info = services::os::cpu::print_cpu_info(&sys);
-        database::sqlite::query::save_cpu_info(
-            con,
-            cpu_info.total_cpus,
-            cpu_info.total_cpu_usage,
-            cpu_info.cores_usage.clone(),
-        )
-        .await
-        .unwrap();
-        let cpu_data = format!(
-            "{{\"total_cpus\": {},\"total_cpu_usage\": {},\"cores_usage\": {}}}",
-            cpu_info.total_cpus, cpu_info.total_cpu_usage, cpu_info.cores_usage
-        );
+        let cpu_info = services::os::cpu::print_cpu_info(sys);
+        if let Err(e) = storage
+            .save_cpu_info(
+                cpu_info.total_cpus,
+                cpu_info.total_cpu_usage,
+                cpu_info.cores_usage.clone(),
+            )
+            .await
+        {
+            eprintln!("Error saving CPU info: {}", e);
+        }
+        let cpu_data = serde_json::json!({
+            "total_cpus": cpu_info.total_cpus,
+            "total_cpu_usage": cpu_info.total_cpu_usage,
+            "cores_usage": cpu_info.cores_usage,
+        })
+        .to_string();
         let mut res = Response::new(Bytes::from(cpu_data));
         res.headers_mut()
             .insert("Content-Type", HeaderValue::from_static("application/json"));
@@ -108,18 +172,64 @@ pub async fn received_request(
     }
 
     if request.uri().path() == "/cpu/history" {
-        let cpu_history = database::sqlite::query::get_cpu_history(con).await.unwrap();
-        let cpu_data = cpu_history
-            .into_iter()
-            .map(|(id, total_cpus, total_cpu_usage, cores_usage, created_at)| {
-                format!(
-                    "{{\"id\": {}, \"total_cpus\": {}, \"total_cpu_usage\": {}, \"cores_usage\": {}, \"created_at\": \"{}\"}}",
-                    id, total_cpus, total_cpu_usage, cores_usage, created_at
+        let core = match query_param(&request, "core").map(|core| core.parse::<u32>()) {
+            Some(Ok(core)) => Some(core),
+            Some(Err(_)) => {
+                return response(
+                    "{\"error\": \"Parâmetro 'core' inválido\"}",
+                    StatusCode::BAD_REQUEST,
                 )
-            })
-            .collect::<Vec<String>>()
-            .join(",");
-        let response_data = format!("{{\"data\": [{}]}}", cpu_data);
+                .await;
+            }
+            None => None,
+        };
+
+        let cpu_data = match core {
+            Some(core) => match storage.get_core_history(core).await {
+                Ok(samples) => samples
+                    .into_iter()
+                    .map(|sample| {
+                        serde_json::json!({
+                            "id": sample.cpu_id,
+                            "core": sample.core,
+                            "usage": sample.usage,
+                            "created_at": sample.created_at,
+                        })
+                    })
+                    .collect::<Vec<serde_json::Value>>(),
+                Err(e) => {
+                    eprintln!("Error loading history of core {}: {}", core, e);
+                    return error_response(
+                        "Erro ao buscar histórico do núcleo",
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                    )
+                    .await;
+                }
+            },
+            None => match storage.get_cpu_history().await {
+                Ok(samples) => samples
+                    .into_iter()
+                    .map(|sample| {
+                        serde_json::json!({
+                            "id": sample.id,
+                            "total_cpus": sample.total_cpus,
+                            "total_cpu_usage": sample.total_cpu_usage,
+                            "cores_usage": sample.cores_usage,
+                            "created_at": sample.created_at,
+                        })
+                    })
+                    .collect::<Vec<serde_json::Value>>(),
+                Err(e) => {
+                    eprintln!("Error loading cpu history: {}", e);
+                    return error_response(
+                        "Erro ao buscar histórico da CPU",
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                    )
+                    .await;
+                }
+            },
+        };
+        let response_data = serde_json::json!({ "data": cpu_data }).to_string();
         let mut res = Response::new(Bytes::from(response_data));
         res.headers_mut()
             .insert("Content-Type", HeaderValue::from_static("application/json"));
@@ -128,105 +238,34 @@ pub async fn received_request(
         return Ok(res.map(Full::new));
     }
 
-    if request.uri().path() == "/urls" && request.method() == Method::GET {
-        let response = database::sqlite::urls::get_urls(&con);
-        let urls_data = response
-            .into_iter()
-            .map(|url_data| {
-                format!(
-                    "{{\"id\": {}, \"url\": \"{}\", \"status_code\": \"{:?}\", \"created_at\": \"{}\"}}",
-                    url_data.id, url_data.url, url_data.status_code, url_data.created_at
-                )
-            })
-            .collect::<Vec<String>>()
-            .join(",");
-        let response_data = format!("{{\"data\": [{}]}}", urls_data);
-        let mut res = Response::new(Bytes::from(response_data));
-        res.headers_mut()
-            .insert("Content-Type", HeaderValue::from_static("application/json"));
-        res.headers_mut()
-            .insert("Access-Control-Allow-Origin", allow_origin.clone());
-        return Ok(res.map(Full::new));
+    if request.uri().path().starts_with("/heartbeat/") {
+        return server::heartbeat::heartbeat_request(request, storage, notifier).await;
     }
 
-    if request.uri().path() == "/urls" && request.method() == Method::POST {
-        let body_bytes = request.into_body().collect().await.unwrap().to_bytes();
-        let body_str = String::from_utf8(body_bytes.to_vec()).unwrap();
-
-        // Parse manual do JSON para extrair a URL
-        if let Some(start) = body_str.find("\"url\"") {
-            if let Some(colon_pos) = body_str[start..].find(':') {
-                let after_colon = &body_str[start + colon_pos + 1..];
-                if let Some(quote_start) = after_colon.find('"') {
-                    if let Some(quote_end) = after_colon[quote_start + 1..].find('"') {
-                        let url = &after_colon[quote_start + 1..quote_start + 1 + quote_end];
-
-                        // Salvar a URL no banco de dados
-                        let result = database::sqlite::urls::create_url(con, url, None);
-
-                        let response_data = match result {
-                            Ok(_) => format!(
-                                "{{\"message\": \"URL criada com sucesso\", \"url\": \"{}\"}}",
-                                url
-                            ),
-                            Err(_) => "{\"error\": \"Erro ao criar URL\"}".to_string(),
-                        };
-
-                        let mut res = Response::new(Bytes::from(response_data));
-                        res.headers_mut()
-                            .insert("Content-Type", HeaderValue::from_static("application/json"));
-                        res.headers_mut()
-                            .insert("Access-Control-Allow-Origin", allow_origin.clone());
-                        return Ok(res.map(Full::new));
-                    }
-                }
-            }
-        }
+    if request.uri().path().starts_with("/notifications/") {
+        return server::notifications::notifications_request(request, storage, notifier).await;
+    }
 
-        // Resposta de erro se JSON inválido ou sem campo 'url'
-        let error_response = "{\"error\": \"JSON inválido ou campo 'url' ausente\"}";
-        let mut res = Response::new(Bytes::from(error_response));
-        res.headers_mut()
-            .insert("Content-Type", HeaderValue::from_static("application/json"));
-        res.headers_mut()
-            .insert("Access-Control-Allow-Origin", allow_origin.clone());
-        return Ok(res.map(Full::new));
+    if request.uri().path() == "/incidents" || request.uri().path().starts_with("/incidents/") {
+        return server::incidents::incidents_request(request, storage).await;
     }
 
-    if request.uri().path() == "/urls" && request.method() == Method::DELETE {
-        let body_bytes = request.into_body().collect().await.unwrap().to_bytes();
-        let json = serde_json::from_slice::<serde_json::Value>(&body_bytes);
+    if request.uri().path() == "/alerts/escalations"
+        || request.uri().path().starts_with("/alerts/escalations/")
+    {
+        return server::escalations::escalations_request(request, storage).await;
+    }
 
-        if json.is_err() {
-            let response = response(
-                "{\"error\": \"JSON inválido\"}",
-                StatusCode::INTERNAL_SERVER_ERROR,
-            )
-            .await;
-            return response;
-        }
-        let json = json.unwrap();
-        let id = json.get("id");
-        if id.is_none() || !id.unwrap().is_i64() {
-            let response = response(
-                "{\"error\": \"ID inválido ou ausente\"}",
-                StatusCode::BAD_REQUEST,
-            )
-            .await;
-            return response;
-        }
+    if request.uri().path() == "/alerts" || request.uri().path().starts_with("/alerts/") {
+        return server::alerts::alerts_request(request, storage).await;
+    }
 
-        let id = id.unwrap().as_i64().unwrap() as i32;
-        let result = database::sqlite::urls::delete_url(&con.lock().unwrap(), id);
-        let response_data = match result {
-            Ok(_) => format!(
-                "{{\"message\": \"URL com ID {} deletada com sucesso\"}}",
-                id
-            ),
-            Err(_) => "{\"error\": \"Erro ao deletar URL\"}".to_string(),
-        };
-        let response = response(&response_data, StatusCode::OK).await;
-        return response;
+    if request.uri().path() == "/maintenance" || request.uri().path().starts_with("/maintenance/") {
+        return server::maintenance::maintenance_request(request, storage).await;
+    }
+
+    if request.uri().path() == "/urls" || request.uri().path().starts_with("/urls/") {
+        return server::urls::urls_request(request, storage).await;
     }
 
     Ok(Response::new(Full::new(Bytes::from("Hello, World!"))))
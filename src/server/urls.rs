@@ -0,0 +1,731 @@
+use std::convert::Infallible;
+
+use http::{HeaderValue, Method, Request, Response, StatusCode};
+use http_body_util::Full;
+use hyper::body::Bytes;
+
+use crate::{
+    database::{
+        error::{StorageError, StorageResult},
+        models::{
+            Assertion, CertificateInfo, CheckResult, Heartbeat, MonitorType, UrlCertificate,
+            UrlData, UrlSettings,
+        },
+        storage::SharedStorage,
+        time::now,
+    },
+    server::{
+        self,
+        alerts::labels_field,
+        checks::result_json,
+        request::{error_response, query_param, read_json, response},
+    },
+    services::monitor::{
+        certificate, expected_status::ExpectedStatus, heartbeat, http_check::HttpChecker,
+        probe::probe, tcp_check,
+    },
+};
+
+const MAX_ASSERTIONS: usize = 20;
+const METHODS: [&str; 7] = ["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"];
+const SCHEMES: [&str; 4] = ["http", "https", "tcp", "heartbeat"];
+const MAX_INTERVAL: u32 = 86400;
+/// Heartbeat monitors may follow weekly jobs.
+const MAX_HEARTBEAT_INTERVAL: u32 = 7 * 86400;
+
+pub fn url_json(url_data: &UrlData) -> serde_json::Value {
+    let settings = &url_data.settings;
+    serde_json::json!({
+        "id": url_data.id,
+        "url": url_data.url,
+        "type": settings.monitor_type.as_str(),
+        "status_code": url_data.status_code,
+        "up": url_data.up,
+        "outcome": url_data.outcome.map(|outcome| outcome.as_str()),
+        "unconfirmed_checks": url_data.unconfirmed_checks,
+        "flapping": url_data.flapping,
+        "interval": settings.interval,
+        "grace": settings.grace,
+        "timeout": settings.timeout,
+        "method": settings.method,
+        "headers": settings.headers,
+        "body": settings.body,
+        "follow_redirects": settings.follow_redirects,
+        "expected_status": settings.expected_status,
+        "assertions": settings
+            .assertions
+            .iter()
+            .map(Assertion::to_json)
+            .collect::<Vec<serde_json::Value>>(),
+        "labels": settings.labels,
+        "confirmations_down": settings.confirmations_down,
+        "confirmations_up": settings.confirmations_up,
+        "retry_interval": settings.retry_interval,
+        "certificate": url_data.certificate.as_ref().map(certificate_json),
+        "heartbeat": url_data.heartbeat.as_ref().map(heartbeat_json),
+        "created_at": url_data.created_at,
+    })
+}
+
+/// Leaf certificate fields at the top level, the full chain under `chain`.
+fn certificate_json(certificate: &UrlCertificate) -> serde_json::Value {
+    let leaf = certificate.chain.first();
+    serde_json::json!({
+        "subject": leaf.map(|leaf| &leaf.subject),
+        "issuer": leaf.map(|leaf| &leaf.issuer),
+        "not_after": leaf.map(|leaf| &leaf.not_after),
+        "days_until_expiry": certificate::days_until_expiry(certificate),
+        "expiring_soon": certificate::expiring_soon(certificate),
+        "chain": certificate
+            .chain
+            .iter()
+            .map(CertificateInfo::to_json)
+            .collect::<Vec<serde_json::Value>>(),
+        "checked_at": certificate.checked_at,
+    })
+}
+
+fn heartbeat_json(heartbeat: &Heartbeat) -> serde_json::Value {
+    serde_json::json!({
+        "ping_url": format!("/heartbeat/{}", heartbeat.token),
+        "last_ping_at": heartbeat.last_ping_at,
+        "started_at": heartbeat.started_at,
+        "expected_at": heartbeat.expected_at,
+    })
+}
+
+fn u32_field(
+    json: &serde_json::Value,
+    name: &str,
+    min: u32,
+    max: u32,
+) -> Result<Option<u32>, String> {
+    match json.get(name) {
+        None => Ok(None),
+        Some(value) => match value.as_u64() {
+            Some(number) if (min as u64..=max as u64).contains(&number) => Ok(Some(number as u32)),
+            _ => Err(format!(
+                "Campo '{}' deve estar entre {} e {}",
+                name, min, max
+            )),
+        },
+    }
+}
+
+/// Copies the settings present in `json` over `settings`, validating each one.
+fn apply_settings(json: &serde_json::Value, settings: &mut UrlSettings) -> Result<(), String> {
+    // Checked against the monitor type in `apply_monitor_type`
+    if let Some(interval) = u32_field(json, "interval", 10, MAX_HEARTBEAT_INTERVAL)? {
+        settings.interval = interval;
+    }
+    if let Some(grace) = u32_field(json, "grace", 0, 86400)? {
+        settings.grace = grace;
+    }
+    if let Some(timeout) = u32_field(json, "timeout", 1, 300)? {
+        settings.timeout = timeout;
+    }
+    if let Some(method) = json.get("method") {
+        let method = method.as_str().unwrap_or_default().to_uppercase();
+        if !METHODS.contains(&method.as_str()) {
+            return Err(format!(
+                "Campo 'method' deve ser um de {}",
+                METHODS.join(", ")
+            ));
+        }
+        settings.method = method;
+    }
+    if let Some(headers) = json.get("headers") {
+        let Some(headers) = headers.as_object() else {
+            return Err("Campo 'headers' deve ser um objeto".into());
+        };
+        settings.headers.clear();
+        for (name, value) in headers {
+            let Some(value) = value.as_str() else {
+                return Err(format!("Cabeçalho '{}' deve ser uma string", name));
+            };
+            if http::HeaderName::from_bytes(name.as_bytes()).is_err()
+                || HeaderValue::from_str(value).is_err()
+            {
+                return Err(format!("Cabeçalho '{}' inválido", name));
+            }
+            settings.headers.insert(name.clone(), value.to_string());
+        }
+    }
+    match json.get("body") {
+        None => {}
+        Some(serde_json::Value::Null) => settings.body = None,
+        Some(serde_json::Value::String(body)) => settings.body = Some(body.clone()),
+        Some(_) => return Err("Campo 'body' deve ser uma string".into()),
+    }
+    if let Some(follow_redirects) = json.get("follow_redirects") {
+        let Some(follow_redirects) = follow_redirects.as_bool() else {
+            return Err("Campo 'follow_redirects' deve ser booleano".into());
+        };
+        settings.follow_redirects = follow_redirects;
+    }
+    if let Some(expected_status) = json.get("expected_status") {
+        let Some(expected_status) = expected_status.as_str() else {
+            return Err("Campo 'expected_status' deve ser uma string".into());
+        };
+        ExpectedStatus::parse(expected_status)?;
+        settings.expected_status = expected_status.to_string();
+    }
+    if let Some(assertions) = json.get("assertions") {
+        let Some(assertions) = assertions.as_array() else {
+            return Err("Campo 'assertions' deve ser uma lista".into());
+        };
+        if assertions.len() > MAX_ASSERTIONS {
+            return Err(format!("No máximo {} asserções por URL", MAX_ASSERTIONS));
+        }
+        settings.assertions = assertions
+            .iter()
+            .map(parse_assertion)
+            .collect::<Result<Vec<Assertion>, String>>()?;
+    }
+    if let Some(labels) = labels_field(json)? {
+        settings.labels = labels;
+    }
+    if let Some(confirmations) = u32_field(json, "confirmations_down", 1, 10)? {
+        settings.confirmations_down = confirmations;
+    }
+    if let Some(confirmations) = u32_field(json, "confirmations_up", 1, 10)? {
+        settings.confirmations_up = confirmations;
+    }
+    if let Some(retry_interval) = u32_field(json, "retry_interval", 1, 3600)? {
+        settings.retry_interval = retry_interval;
+    }
+    Ok(())
+}
+
+/// Parses `url` into the form it is stored and compared in: lowercase scheme and host, no
+/// default port, no fragment and `/` as the path of HTTP URLs without one.
+fn normalize_url(url: &str) -> Result<String, String> {
+    if url.trim().is_empty() {
+        return Err("Campo 'url' vazio".into());
+    }
+    let mut parsed = url::Url::parse(url.trim()).map_err(|_| format!("URL inválida: {}", url))?;
+    if !SCHEMES.contains(&parsed.scheme()) {
+        return Err("A URL deve usar http://, https://, tcp:// ou heartbeat://".into());
+    }
+    let Some(host) = parsed.host_str().filter(|host| !host.is_empty()) else {
+        return Err("A URL deve ter um host".into());
+    };
+    // Only hosts of http(s) URLs are lowercased by the parser
+    let host = host.to_ascii_lowercase();
+    parsed
+        .set_host(Some(&host))
+        .map_err(|_| format!("URL inválida: {}", url))?;
+    parsed.set_fragment(None);
+    Ok(parsed.to_string())
+}
+
+/// Brings URLs saved before they were normalized into their normalized form, since duplicates
+/// are found by exact match. A monitor whose normalized URL is already taken by another one
+/// is left as it is and reported, so one of the two can be removed.
+pub async fn normalize_stored_urls(storage: &SharedStorage) -> StorageResult<()> {
+    for url_data in storage.get_urls().await? {
+        let url = match normalize_url(&url_data.url) {
+            Ok(url) if url != url_data.url => url,
+            Ok(_) => continue,
+            Err(message) => {
+                eprintln!(
+                    "Monitor {} has a URL that cannot be normalized ({}): {}",
+                    url_data.id, url_data.url, message
+                );
+                continue;
+            }
+        };
+        match storage.rename_url(url_data.id, url.clone()).await {
+            Ok(_) => println!(
+                "Normalized URL of monitor {}: {} -> {}",
+                url_data.id, url_data.url, url
+            ),
+            Err(StorageError::Duplicate(existing)) => eprintln!(
+                "Monitor {} ({}) checks the same URL as monitor {} ({}), remove one of them",
+                url_data.id, url_data.url, existing, url
+            ),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Runs a one-off check of a monitor before it is saved. `Err` holds the response to return:
+/// 422 with the failed check, or 500 when the check cannot run.
+async fn verify(url: &str, settings: &UrlSettings) -> Result<CheckResult, Response<Full<Bytes>>> {
+    if settings.monitor_type == MonitorType::Heartbeat {
+        return Err(error_response(
+            "Monitores heartbeat não podem ser verificados",
+            StatusCode::BAD_REQUEST,
+        )
+        .await
+        .unwrap());
+    }
+    let checker = match HttpChecker::new() {
+        Ok(checker) => checker,
+        Err(e) => {
+            eprintln!("Error creating HTTP client: {}", e);
+            return Err(
+                error_response("Erro ao verificar URL", StatusCode::INTERNAL_SERVER_ERROR)
+                    .await
+                    .unwrap(),
+            );
+        }
+    };
+    let url_data = UrlData {
+        id: 0,
+        url: url.to_string(),
+        settings: settings.clone(),
+        status_code: None,
+        up: None,
+        outcome: None,
+        unconfirmed_checks: 0,
+        flapping: false,
+        certificate: None,
+        heartbeat: None,
+        created_at: String::new(),
+    };
+    let result = probe(&checker, &url_data).await;
+    if result.up {
+        return Ok(result);
+    }
+    Err(response(
+        &serde_json::json!({
+            "error": "A verificação da URL falhou",
+            "check": result_json(&result),
+        })
+        .to_string(),
+        StatusCode::UNPROCESSABLE_ENTITY,
+    )
+    .await
+    .unwrap())
+}
+
+async fn duplicate_response(id: i32) -> Result<Response<Full<Bytes>>, Infallible> {
+    response(
+        &serde_json::json!({ "error": "URL já monitorada", "id": id }).to_string(),
+        StatusCode::CONFLICT,
+    )
+    .await
+}
+
+/// Sets the monitor type from the URL scheme, `tcp://` and `heartbeat://` being the non-HTTP
+/// ones. A `type` sent by the client must agree with it.
+fn apply_monitor_type(
+    json: &serde_json::Value,
+    url: &str,
+    settings: &mut UrlSettings,
+) -> Result<(), String> {
+    let url_lowercase = url.to_ascii_lowercase();
+    let monitor_type = if url_lowercase.starts_with("tcp://") {
+        MonitorType::Tcp
+    } else if url_lowercase.starts_with("heartbeat://") {
+        MonitorType::Heartbeat
+    } else {
+        MonitorType::Http
+    };
+    if let Some(requested) = json.get("type") {
+        match requested.as_str().and_then(MonitorType::parse) {
+            Some(requested) if requested == monitor_type => {}
+            Some(_) => return Err("Campo 'type' não corresponde ao esquema da URL".into()),
+            None => return Err("Campo 'type' deve ser 'http', 'tcp' ou 'heartbeat'".into()),
+        }
+    }
+    if monitor_type != MonitorType::Heartbeat && settings.interval > MAX_INTERVAL {
+        return Err(format!(
+            "Campo 'interval' deve estar entre 10 e {}",
+            MAX_INTERVAL
+        ));
+    }
+    if monitor_type == MonitorType::Tcp {
+        if tcp_check::host_and_port(url).is_none() {
+            return Err("URL TCP deve ter o formato tcp://host:porta".into());
+        }
+        if settings.assertions.iter().any(|assertion| {
+            matches!(
+                assertion,
+                Assertion::HeaderPresent(_) | Assertion::HeaderEquals { .. }
+            )
+        }) {
+            return Err("Asserções de cabeçalho não se aplicam a monitores TCP".into());
+        }
+    }
+    settings.monitor_type = monitor_type;
+    Ok(())
+}
+
+fn parse_assertion(json: &serde_json::Value) -> Result<Assertion, String> {
+    let assertion = Assertion::from_json(json)?;
+    match &assertion {
+        Assertion::JsonPointerEquals { pointer, .. }
+            if !pointer.is_empty() && !pointer.starts_with('/') =>
+        {
+            return Err(format!("Ponteiro JSON inválido: {}", pointer));
+        }
+        Assertion::HeaderPresent(name) | Assertion::HeaderEquals { name, .. }
+            if http::HeaderName::from_bytes(name.as_bytes()).is_err() =>
+        {
+            return Err(format!("Cabeçalho '{}' inválido", name));
+        }
+        _ => {}
+    }
+    Ok(assertion)
+}
+
+/// Ping state for a monitor that becomes a heartbeat monitor. `Err` holds the 500 response
+/// to return when no token can be generated.
+async fn new_heartbeat(settings: &UrlSettings) -> Result<Heartbeat, Response<Full<Bytes>>> {
+    match heartbeat::new(settings, now()) {
+        Ok(heartbeat) => Ok(heartbeat),
+        Err(e) => {
+            eprintln!("Error generating heartbeat token: {}", e);
+            Err(error_response(
+                "Erro ao gerar token do heartbeat",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .await
+            .unwrap())
+        }
+    }
+}
+
+/// Routes `/urls`, `/urls/{id}` and the resources below it.
+pub async fn urls_request(
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let path = request.uri().path().trim_end_matches('/').to_string();
+    let method = request.method().clone();
+
+    if path == "/urls" {
+        return match method {
+            Method::GET => list_urls(&request, storage).await,
+            Method::POST => create_url(request, storage).await,
+            // Kept for clients that still send the id in the body
+            Method::DELETE => delete_url_from_body(request, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        };
+    }
+
+    let mut segments = path.trim_start_matches("/urls/").split('/');
+    let Some(id) = segments.next().and_then(|id| id.parse::<i32>().ok()) else {
+        return error_response("URL não encontrada", StatusCode::NOT_FOUND).await;
+    };
+
+    match (segments.next(), segments.next()) {
+        (None, _) => match method {
+            Method::GET => get_url(id, storage).await,
+            Method::PUT => update_url(id, request, storage, true).await,
+            Method::PATCH => update_url(id, request, storage, false).await,
+            Method::DELETE => delete_url(id, storage).await,
+            _ => error_response("Método não permitido", StatusCode::METHOD_NOT_ALLOWED).await,
+        },
+        (Some("checks"), None) if method == Method::GET => {
+            server::checks::list_checks(id, &request, storage).await
+        }
+        (Some("uptime"), None) if method == Method::GET => {
+            server::checks::uptime(id, &request, storage).await
+        }
+        _ => error_response("URL não encontrada", StatusCode::NOT_FOUND).await,
+    }
+}
+
+/// `?certificate_expiring=true` keeps only monitors whose certificate is about to expire.
+async fn list_urls(
+    request: &Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let expiring_only =
+        query_param(request, "certificate_expiring").is_some_and(|value| value == "true");
+    match storage.get_urls().await {
+        Ok(urls) => {
+            let urls_data = urls
+                .iter()
+                .filter(|url_data| {
+                    !expiring_only
+                        || url_data
+                            .certificate
+                            .as_ref()
+                            .is_some_and(certificate::expiring_soon)
+                })
+                .map(url_json)
+                .collect::<Vec<serde_json::Value>>();
+            response(
+                &serde_json::json!({ "data": urls_data }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Err(e) => {
+            eprintln!("Error listing URLs: {}", e);
+            error_response("Erro ao listar URLs", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+async fn get_url(id: i32, storage: SharedStorage) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.get_url(id).await {
+        Ok(Some(url_data)) => {
+            response(
+                &serde_json::json!({ "data": url_json(&url_data) }).to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Ok(None) => error_response("URL não encontrada", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error loading URL {}: {}", id, e);
+            error_response("Erro ao buscar URL", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+async fn create_url(
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let verify_first = query_param(&request, "verify").is_some_and(|verify| verify == "true");
+    let json = match read_json(request).await {
+        Ok(json) => json,
+        Err(res) => return Ok(res),
+    };
+    let Some(url) = json.get("url").and_then(|url| url.as_str()) else {
+        return error_response("Campo 'url' ausente", StatusCode::BAD_REQUEST).await;
+    };
+    let url = match normalize_url(url) {
+        Ok(url) => url,
+        Err(message) => return error_response(&message, StatusCode::BAD_REQUEST).await,
+    };
+    let mut settings = UrlSettings::default();
+    if let Err(message) = apply_settings(&json, &mut settings)
+        .and_then(|_| apply_monitor_type(&json, &url, &mut settings))
+    {
+        return error_response(&message, StatusCode::BAD_REQUEST).await;
+    }
+    let verification = if verify_first {
+        match verify(&url, &settings).await {
+            Ok(result) => Some(result_json(&result)),
+            Err(res) => return Ok(res),
+        }
+    } else {
+        None
+    };
+    let heartbeat = if settings.monitor_type == MonitorType::Heartbeat {
+        match new_heartbeat(&settings).await {
+            Ok(heartbeat) => Some(heartbeat),
+            Err(res) => return Ok(res),
+        }
+    } else {
+        None
+    };
+
+    match storage.create_url(url.clone(), settings, heartbeat).await {
+        Ok(url_data) => {
+            let mut body = serde_json::json!({
+                "message": "URL criada com sucesso",
+                "data": url_json(&url_data),
+            });
+            if let Some(verification) = verification {
+                body["check"] = verification;
+            }
+            let mut res = response(&body.to_string(), StatusCode::CREATED).await?;
+            res.headers_mut().insert(
+                "Location",
+                HeaderValue::from_str(&format!("/urls/{}", url_data.id)).unwrap(),
+            );
+            Ok(res)
+        }
+        Err(StorageError::Duplicate(existing)) => duplicate_response(existing).await,
+        Err(e) => {
+            eprintln!("Error creating URL {}: {}", url, e);
+            error_response("Erro ao criar URL", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+/// PUT replaces the resource, resetting omitted settings to their defaults, PATCH only
+/// changes the fields sent.
+async fn update_url(
+    id: i32,
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+    replace: bool,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let verify_first = query_param(&request, "verify").is_some_and(|verify| verify == "true");
+    let json = match read_json(request).await {
+        Ok(json) => json,
+        Err(res) => return Ok(res),
+    };
+
+    let url = match json.get("url") {
+        Some(serde_json::Value::String(url)) => match normalize_url(url) {
+            Ok(url) => Some(url),
+            Err(message) => return error_response(&message, StatusCode::BAD_REQUEST).await,
+        },
+        Some(_) => return error_response("Campo 'url' inválido", StatusCode::BAD_REQUEST).await,
+        None if replace => {
+            return error_response("Campo 'url' ausente", StatusCode::BAD_REQUEST).await
+        }
+        None => None,
+    };
+
+    let current = match storage.get_url(id).await {
+        Ok(Some(current)) => current,
+        Ok(None) => return error_response("URL não encontrada", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error loading URL {}: {}", id, e);
+            return error_response("Erro ao atualizar URL", StatusCode::INTERNAL_SERVER_ERROR)
+                .await;
+        }
+    };
+    let (interval, grace) = (current.settings.interval, current.settings.grace);
+    let mut settings = if replace {
+        UrlSettings::default()
+    } else {
+        current.settings
+    };
+    let url = url.unwrap_or(current.url);
+    if let Err(message) = apply_settings(&json, &mut settings)
+        .and_then(|_| apply_monitor_type(&json, &url, &mut settings))
+    {
+        return error_response(&message, StatusCode::BAD_REQUEST).await;
+    }
+    let verification = if verify_first {
+        match verify(&url, &settings).await {
+            Ok(result) => Some(result_json(&result)),
+            Err(res) => return Ok(res),
+        }
+    } else {
+        None
+    };
+    // The token is kept across updates, only a new period moves the deadline
+    let heartbeat = match (&settings.monitor_type, &current.heartbeat) {
+        (MonitorType::Heartbeat, Some(heartbeat))
+            if (settings.interval, settings.grace) == (interval, grace) =>
+        {
+            Some(heartbeat.clone())
+        }
+        (MonitorType::Heartbeat, Some(heartbeat)) => {
+            Some(heartbeat::rescheduled(heartbeat, &settings, now()))
+        }
+        (MonitorType::Heartbeat, None) => match new_heartbeat(&settings).await {
+            Ok(heartbeat) => Some(heartbeat),
+            Err(res) => return Ok(res),
+        },
+        _ => None,
+    };
+
+    match storage.update_url(id, url, settings, heartbeat).await {
+        Ok(Some(url_data)) => {
+            let mut body = serde_json::json!({ "data": url_json(&url_data) });
+            if let Some(verification) = verification {
+                body["check"] = verification;
+            }
+            response(&body.to_string(), StatusCode::OK).await
+        }
+        Ok(None) => error_response("URL não encontrada", StatusCode::NOT_FOUND).await,
+        Err(StorageError::Duplicate(existing)) => duplicate_response(existing).await,
+        Err(e) => {
+            eprintln!("Error updating URL {}: {}", id, e);
+            error_response("Erro ao atualizar URL", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+async fn delete_url(id: i32, storage: SharedStorage) -> Result<Response<Full<Bytes>>, Infallible> {
+    match storage.delete_url(id).await {
+        Ok(true) => {
+            response(
+                &serde_json::json!({
+                    "message": format!("URL com ID {} deletada com sucesso", id)
+                })
+                .to_string(),
+                StatusCode::OK,
+            )
+            .await
+        }
+        Ok(false) => error_response("URL não encontrada", StatusCode::NOT_FOUND).await,
+        Err(e) => {
+            eprintln!("Error deleting URL {}: {}", id, e);
+            error_response("Erro ao deletar URL", StatusCode::INTERNAL_SERVER_ERROR).await
+        }
+    }
+}
+
+async fn delete_url_from_body(
+    request: Request<hyper::body::Incoming>,
+    storage: SharedStorage,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let json = match read_json(request).await {
+        Ok(json) => json,
+        Err(res) => return Ok(res),
+    };
+    // Ids past i32 would wrap around onto another monitor
+    match json
+        .get("id")
+        .and_then(|id| id.as_i64())
+        .and_then(|id| i32::try_from(id).ok())
+    {
+        Some(id) => delete_url(id, storage).await,
+        None => error_response("ID inválido ou ausente", StatusCode::BAD_REQUEST).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::memory::storage::MemoryStorage;
+
+    #[test]
+    fn normalize_url_lowercases_and_drops_defaults() {
+        assert_eq!(
+            normalize_url(" HTTPS://Example.COM:443/Path?q=1#top ").unwrap(),
+            "https://example.com/Path?q=1"
+        );
+        assert_eq!(
+            normalize_url("http://example.com").unwrap(),
+            "http://example.com/"
+        );
+        assert_eq!(
+            normalize_url("tcp://DB.example.com:5432").unwrap(),
+            "tcp://db.example.com:5432"
+        );
+        assert!(normalize_url("ftp://example.com/").is_err());
+        assert!(normalize_url("").is_err());
+    }
+
+    #[tokio::test]
+    async fn stored_urls_are_normalized_and_collisions_left_alone() {
+        let storage: SharedStorage = std::sync::Arc::new(MemoryStorage::new());
+        let mut ids = Vec::new();
+        for url in [
+            "HTTPS://Example.com:443/health",
+            "https://example.com/health#status",
+            "tcp://DB.example.com:5432",
+            "https://ok.example.com/",
+        ] {
+            let url_data = storage
+                .create_url(url.into(), UrlSettings::default(), None)
+                .await
+                .unwrap();
+            ids.push(url_data.id);
+        }
+
+        normalize_stored_urls(&storage).await.unwrap();
+
+        let mut urls = Vec::new();
+        for id in ids {
+            urls.push(storage.get_url(id).await.unwrap().unwrap().url);
+        }
+        assert_eq!(
+            urls,
+            [
+                "https://example.com/health",
+                // Same address as the first monitor once normalized, kept for removal by hand
+                "https://example.com/health#status",
+                "tcp://db.example.com:5432",
+                "https://ok.example.com/",
+            ]
+        );
+    }
+}
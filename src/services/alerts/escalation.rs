@@ -0,0 +1,451 @@
+use chrono::TimeDelta;
+
+use crate::{
+    database::{
+        models::{Alert, Escalation, EscalationPolicy, Silence},
+        storage::SharedStorage,
+        time::{format_duration, format_timestamp, now, parse_timestamp},
+    },
+    services::notifications::{notification::Notification, notifier::Notifier},
+};
+
+/// Starts every policy that applies to a newly opened alert. Each step sends `notification`
+/// again, to the channels of the step.
+pub async fn start(storage: &SharedStorage, alert: &Alert, notification: &Notification) {
+    let policies = match storage.get_escalation_policies().await {
+        Ok(policies) => policies,
+        Err(e) => {
+            eprintln!("Error loading escalation policies: {}", e);
+            return;
+        }
+    };
+    let Some(started_at) = parse_timestamp(&alert.started_at) else {
+        return;
+    };
+    let mut notification = notification.clone();
+    notification
+        .fields
+        .insert("alert_id".into(), alert.id.into());
+
+    for policy in policies
+        .iter()
+        .filter(|policy| policy.settings.matcher.matches(alert))
+    {
+        let Some(first) = policy.settings.steps.first() else {
+            continue;
+        };
+        let due_at = started_at + TimeDelta::seconds(first.after as i64);
+        if let Err(e) = storage
+            .start_escalation(alert.id, policy.id, due_at, notification.to_json())
+            .await
+        {
+            eprintln!(
+                "Error starting escalation policy '{}' for alert {}: {}",
+                policy.settings.name, alert.id, e
+            );
+        }
+    }
+}
+
+/// Notifies the steps that are due. Escalations whose alert was acknowledged or resolved in
+/// the meantime are dropped instead.
+pub async fn run_due(storage: &SharedStorage, notifier: &Notifier) {
+    let escalations = match storage.get_escalations(None).await {
+        Ok(escalations) => escalations,
+        Err(e) => {
+            eprintln!("Error loading escalations: {}", e);
+            return;
+        }
+    };
+    let current = format_timestamp(&now());
+    let due = escalations
+        .iter()
+        .take_while(|escalation| escalation.due_at <= current)
+        .collect::<Vec<&Escalation>>();
+    if due.is_empty() {
+        return;
+    }
+    let silences = match storage.get_silences(Some(now())).await {
+        Ok(silences) => silences,
+        Err(e) => {
+            eprintln!("Error loading silences: {}", e);
+            Vec::new()
+        }
+    };
+    for escalation in due {
+        escalate(storage, notifier, escalation, &silences).await;
+    }
+}
+
+async fn escalate(
+    storage: &SharedStorage,
+    notifier: &Notifier,
+    escalation: &Escalation,
+    silences: &[Silence],
+) {
+    let alert = match storage.get_alert(escalation.alert_id).await {
+        Ok(Some(alert)) if alert.is_active() && alert.acknowledged_at.is_none() => alert,
+        Ok(_) => {
+            println!(
+                "Escalation of alert {} stopped, it was acknowledged or resolved",
+                escalation.alert_id
+            );
+            stop(storage, escalation).await;
+            return;
+        }
+        Err(e) => {
+            eprintln!("Error loading alert {}: {}", escalation.alert_id, e);
+            return;
+        }
+    };
+    let policy = match storage.get_escalation_policy(escalation.policy_id).await {
+        Ok(Some(policy)) => policy,
+        Ok(None) => {
+            stop(storage, escalation).await;
+            return;
+        }
+        Err(e) => {
+            eprintln!(
+                "Error loading escalation policy {}: {}",
+                escalation.policy_id, e
+            );
+            return;
+        }
+    };
+    let index = escalation.step as usize;
+    let Some(step) = policy.settings.steps.get(index) else {
+        stop(storage, escalation).await;
+        return;
+    };
+
+    if let Some(silence) = silences
+        .iter()
+        .find(|silence| silence.matcher.matches(&alert))
+    {
+        println!(
+            "Escalation step {} of alert {} silenced by silence {}",
+            index + 1,
+            alert.id,
+            silence.id
+        );
+    } else if let Some(notification) = Notification::from_json(&escalation.notification) {
+        let notification = escalated(notification, &policy, index);
+        println!(
+            "Escalating alert {} to step {} of policy '{}'",
+            alert.id,
+            index + 1,
+            policy.settings.name
+        );
+        for channel_id in &step.channel_ids {
+            match storage.get_channel(*channel_id).await {
+                // A step due right away would repeat what `raise` broadcast to the channel
+                Ok(Some(channel)) if step.after == 0 && channel.accepts(notification.event) => {}
+                Ok(Some(channel)) if channel.enabled => {
+                    let notifier = notifier.clone();
+                    let notification = notification.clone();
+                    tokio::spawn(async move { notifier.deliver(&channel, &notification).await });
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Error loading notification channel {}: {}", channel_id, e),
+            }
+        }
+    }
+
+    let next = policy
+        .settings
+        .steps
+        .get(index + 1)
+        .zip(parse_timestamp(&alert.started_at));
+    match next {
+        Some((next, started_at)) => {
+            let due_at = started_at + TimeDelta::seconds(next.after as i64);
+            if let Err(e) = storage
+                .advance_escalation(escalation.id, index as u32 + 1, due_at)
+                .await
+            {
+                eprintln!("Error advancing escalation {}: {}", escalation.id, e);
+            }
+        }
+        None => stop(storage, escalation).await,
+    }
+}
+
+async fn stop(storage: &SharedStorage, escalation: &Escalation) {
+    if let Err(e) = storage.delete_escalation(escalation.id).await {
+        eprintln!("Error deleting escalation {}: {}", escalation.id, e);
+    }
+}
+
+/// Tells how long the alert has gone unacknowledged and which step is notifying.
+fn escalated(
+    mut notification: Notification,
+    policy: &EscalationPolicy,
+    index: usize,
+) -> Notification {
+    let after = policy.settings.steps[index].after;
+    if after > 0 {
+        let prefix = format!("Sem reconhecimento há {}", format_duration(after.into()));
+        notification.subject = format!("{}: {}", prefix, notification.subject);
+        notification.message = format!("{}: {}", prefix, notification.message);
+    }
+    notification.fields.insert(
+        "escalation_policy".into(),
+        policy.settings.name.clone().into(),
+    );
+    notification
+        .fields
+        .insert("escalation_step".into(), (index + 1).into());
+    notification
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+    use super::*;
+    use crate::{
+        database::{
+            memory::storage::MemoryStorage,
+            models::{
+                AlertCondition, AlertMatcher, AlertRule, AlertSource, AlertState, ChannelConfig,
+                Comparison, EscalationSettings, EscalationStep, EventKind, Metric, WebhookConfig,
+            },
+        },
+        services::alerts::lifecycle,
+        test_support::{HttpStandIn, RecordedRequest},
+    };
+
+    struct Setup {
+        storage: SharedStorage,
+        notifier: Notifier,
+        /// Subscribed to every event, so it gets the alert when it fires.
+        subscribed: HttpStandIn,
+        subscribed_id: i64,
+        /// Only subscribed to tests, reached by escalation alone.
+        on_call: HttpStandIn,
+        on_call_id: i64,
+    }
+
+    async fn setup() -> Setup {
+        let storage: SharedStorage = Arc::new(MemoryStorage::new());
+        let notifier = Notifier::new(storage.clone()).unwrap();
+        let mut ids = Vec::new();
+        let mut stand_ins = Vec::new();
+        for events in [Vec::new(), vec![EventKind::Test]] {
+            let stand_in = HttpStandIn::start(&[]).await;
+            let config = ChannelConfig::Webhook(WebhookConfig {
+                url: stand_in.url("/hook"),
+                method: "POST".into(),
+                headers: BTreeMap::new(),
+                body_template: None,
+            });
+            let channel = storage
+                .create_channel("ops".into(), config, events, true)
+                .await
+                .unwrap();
+            ids.push(channel.id);
+            stand_ins.push(stand_in);
+        }
+        let on_call = stand_ins.pop().unwrap();
+        let subscribed = stand_ins.pop().unwrap();
+        Setup {
+            storage,
+            notifier,
+            subscribed,
+            subscribed_id: ids[0],
+            on_call,
+            on_call_id: ids[1],
+        }
+    }
+
+    async fn policy(storage: &SharedStorage, steps: Vec<EscalationStep>) {
+        storage
+            .create_escalation_policy(EscalationSettings {
+                name: "plantão".into(),
+                matcher: AlertMatcher::default(),
+                steps,
+            })
+            .await
+            .unwrap();
+    }
+
+    async fn rule(storage: &SharedStorage, name: &str) -> AlertRule {
+        let condition = AlertCondition {
+            metric: Metric::TotalCpuUsage,
+            comparison: Comparison::Above,
+            threshold: 90.0,
+            duration: 0,
+        };
+        storage
+            .create_alert_rule(name.into(), condition, BTreeMap::new())
+            .await
+            .unwrap()
+    }
+
+    async fn fire(setup: &Setup, rule: &AlertRule) -> Alert {
+        lifecycle::raise(
+            &setup.storage,
+            &setup.notifier,
+            AlertSource::Rule(rule.id),
+            rule.name.clone(),
+            lifecycle::rule_labels(rule),
+            Some(Notification::alert(rule, AlertState::Firing, 95.0)),
+        )
+        .await;
+        setup
+            .storage
+            .get_alerts(Some(true), 100)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|alert| alert.name == rule.name)
+            .unwrap()
+    }
+
+    /// Deliveries run in the background, gives them time to arrive.
+    async fn received(stand_in: &HttpStandIn) -> Vec<RecordedRequest> {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        stand_in.take_requests()
+    }
+
+    #[tokio::test]
+    async fn immediate_step_skips_channels_the_alert_already_reached() {
+        let setup = setup().await;
+        policy(
+            &setup.storage,
+            vec![EscalationStep {
+                after: 0,
+                channel_ids: vec![setup.subscribed_id, setup.on_call_id],
+            }],
+        )
+        .await;
+        let alert = fire(&setup, &rule(&setup.storage, "cpu").await).await;
+        run_due(&setup.storage, &setup.notifier).await;
+
+        let subscribed = received(&setup.subscribed).await;
+        assert_eq!(subscribed.len(), 1);
+        assert_eq!(
+            subscribed[0].json()["escalation_step"],
+            serde_json::Value::Null
+        );
+        let on_call = received(&setup.on_call).await;
+        assert_eq!(on_call.len(), 1);
+        assert_eq!(on_call[0].json()["escalation_step"], 1);
+        assert_eq!(on_call[0].json()["alert_id"], alert.id);
+        assert!(setup
+            .storage
+            .get_escalations(None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn steps_are_notified_once_due_and_then_advance() {
+        let setup = setup().await;
+        policy(
+            &setup.storage,
+            vec![
+                EscalationStep {
+                    after: 60,
+                    channel_ids: vec![setup.on_call_id],
+                },
+                EscalationStep {
+                    after: 3600,
+                    channel_ids: vec![setup.subscribed_id, setup.on_call_id],
+                },
+            ],
+        )
+        .await;
+        let alert = fire(&setup, &rule(&setup.storage, "cpu").await).await;
+        let started_at = parse_timestamp(&alert.started_at).unwrap();
+        assert_eq!(received(&setup.subscribed).await.len(), 1);
+
+        let escalations = setup.storage.get_escalations(None).await.unwrap();
+        assert_eq!(escalations.len(), 1);
+        assert_eq!(escalations[0].step, 0);
+        assert_eq!(
+            escalations[0].due_at,
+            format_timestamp(&(started_at + TimeDelta::seconds(60)))
+        );
+        run_due(&setup.storage, &setup.notifier).await;
+        assert!(received(&setup.on_call).await.is_empty());
+
+        // Both steps come due, the first is notified and the escalation moves to the second
+        let escalation = &escalations[0];
+        setup
+            .storage
+            .advance_escalation(escalation.id, 0, now())
+            .await
+            .unwrap();
+        run_due(&setup.storage, &setup.notifier).await;
+        let on_call = received(&setup.on_call).await;
+        assert_eq!(on_call.len(), 1);
+        assert_eq!(on_call[0].json()["escalation_step"], 1);
+        assert!(on_call[0].json()["subject"]
+            .as_str()
+            .unwrap()
+            .starts_with("Sem reconhecimento há 1m:"));
+        let escalations = setup.storage.get_escalations(None).await.unwrap();
+        assert_eq!(escalations[0].step, 1);
+        assert_eq!(
+            escalations[0].due_at,
+            format_timestamp(&(started_at + TimeDelta::seconds(3600)))
+        );
+
+        setup
+            .storage
+            .advance_escalation(escalation.id, 1, now())
+            .await
+            .unwrap();
+        run_due(&setup.storage, &setup.notifier).await;
+        assert_eq!(received(&setup.subscribed).await.len(), 1);
+        assert_eq!(received(&setup.on_call).await.len(), 1);
+        assert!(setup
+            .storage
+            .get_escalations(None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn acknowledging_or_resolving_stops_the_escalation() {
+        let setup = setup().await;
+        policy(
+            &setup.storage,
+            vec![EscalationStep {
+                after: 0,
+                channel_ids: vec![setup.on_call_id],
+            }],
+        )
+        .await;
+        let acknowledged = fire(&setup, &rule(&setup.storage, "cpu").await).await;
+        let resolved_rule = rule(&setup.storage, "memória").await;
+        fire(&setup, &resolved_rule).await;
+        assert_eq!(setup.storage.get_escalations(None).await.unwrap().len(), 2);
+
+        setup
+            .storage
+            .acknowledge_alert(acknowledged.id, None)
+            .await
+            .unwrap()
+            .unwrap();
+        lifecycle::resolve(
+            &setup.storage,
+            &setup.notifier,
+            AlertSource::Rule(resolved_rule.id),
+            None,
+        )
+        .await;
+        run_due(&setup.storage, &setup.notifier).await;
+
+        assert!(received(&setup.on_call).await.is_empty());
+        assert!(setup
+            .storage
+            .get_escalations(None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
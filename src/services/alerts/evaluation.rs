@@ -0,0 +1,121 @@
+use chrono::NaiveDateTime;
+
+use crate::database::{
+    models::{AlertRule, AlertState},
+    time::parse_timestamp,
+};
+
+/// State of `rule` once `value` is taken into account. A breached condition is pending until
+/// it has held for the rule's duration, then firing; a firing rule resolves as soon as its
+/// condition stops holding.
+pub fn next_state(rule: &AlertRule, value: f64, now: NaiveDateTime) -> AlertState {
+    let condition = &rule.condition;
+    let breached = condition.comparison.holds(value, condition.threshold);
+    let held_long_enough = |since: Option<&String>| {
+        since
+            .and_then(|since| parse_timestamp(since))
+            .is_some_and(|since| (now - since).num_seconds() >= condition.duration as i64)
+    };
+
+    match (rule.state, breached) {
+        (AlertState::Firing, true) => AlertState::Firing,
+        (AlertState::Firing, false) => AlertState::Resolved,
+        (AlertState::Pending, true) if held_long_enough(rule.state_changed_at.as_ref()) => {
+            AlertState::Firing
+        }
+        (AlertState::Pending, true) => AlertState::Pending,
+        (AlertState::Pending, false) => AlertState::Inactive,
+        (AlertState::Inactive | AlertState::Resolved, true) if condition.duration == 0 => {
+            AlertState::Firing
+        }
+        (AlertState::Inactive | AlertState::Resolved, true) => AlertState::Pending,
+        (state, false) => state,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::{NaiveDate, TimeDelta};
+
+    use super::*;
+    use crate::database::{
+        models::{AlertCondition, Comparison, Metric},
+        time::format_timestamp,
+    };
+
+    fn at(seconds: i64) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            + TimeDelta::seconds(seconds)
+    }
+
+    /// `total_cpu_usage > 90` for `duration` seconds, in `state` since `since`.
+    fn rule(duration: u32, state: AlertState, since: Option<i64>) -> AlertRule {
+        AlertRule {
+            id: 1,
+            name: "cpu".into(),
+            condition: AlertCondition {
+                metric: Metric::TotalCpuUsage,
+                comparison: Comparison::Above,
+                threshold: 90.0,
+                duration,
+            },
+            labels: BTreeMap::new(),
+            state,
+            state_changed_at: since.map(|since| format_timestamp(&at(since))),
+            value: None,
+            created_at: format_timestamp(&at(0)),
+        }
+    }
+
+    #[test]
+    fn state_follows_the_condition_and_its_duration() {
+        use AlertState::*;
+        // (state, since, value, now, next)
+        let cases = [
+            // Crossing the threshold starts the wait
+            (Inactive, None, 95.0, 0, Pending),
+            (Inactive, None, 90.0, 0, Inactive),
+            (Resolved, Some(0), 95.0, 60, Pending),
+            // Still under the duration
+            (Pending, Some(0), 95.0, 299, Pending),
+            // Dropping back before the duration cancels it
+            (Pending, Some(0), 80.0, 120, Inactive),
+            // Held for the whole duration
+            (Pending, Some(0), 95.0, 300, Firing),
+            (Pending, Some(0), 95.0, 900, Firing),
+            (Firing, Some(300), 95.0, 600, Firing),
+            // Resolves as soon as the condition stops holding
+            (Firing, Some(300), 90.0, 310, Resolved),
+            (Resolved, Some(310), 50.0, 400, Resolved),
+        ];
+        for (state, since, value, now, next) in cases {
+            assert_eq!(
+                next_state(&rule(300, state, since), value, at(now)),
+                next,
+                "{:?} since {:?} with {} at {}",
+                state,
+                since,
+                value,
+                now
+            );
+        }
+    }
+
+    #[test]
+    fn rule_without_duration_fires_right_away() {
+        let rule = rule(0, AlertState::Inactive, None);
+        assert_eq!(next_state(&rule, 95.0, at(0)), AlertState::Firing);
+        assert_eq!(next_state(&rule, 80.0, at(0)), AlertState::Inactive);
+    }
+
+    #[test]
+    fn pending_without_a_start_keeps_waiting() {
+        let rule = rule(300, AlertState::Pending, None);
+        assert_eq!(next_state(&rule, 95.0, at(3600)), AlertState::Pending);
+    }
+}
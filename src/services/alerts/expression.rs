@@ -0,0 +1,156 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::database::{
+    models::{AlertCondition, Comparison, Metric},
+    time::format_duration,
+};
+
+/// Parses rules such as `total_cpu_usage > 90 for 5m` or `free_memory < 500 MiB`. Memory
+/// thresholds accept B, KiB, MiB, GiB and TiB and are stored in MiB; percentages may be
+/// written with `%`. The duration is given in seconds, minutes, hours or days, see
+/// `parse_duration`.
+pub fn parse_condition(expression: &str) -> Result<AlertCondition, String> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| {
+        Regex::new(
+            r"(?i)^\s*([a-z_]+)\s*(>=|<=|>|<)\s*(-?\d+(?:\.\d+)?)\s*(%|b|kib|mib|gib|tib)?(?:\s+for\s+((?:\d+\s*[smhd]\s*)+))?\s*$",
+        )
+        .unwrap()
+    });
+    let Some(captures) = pattern.captures(expression) else {
+        return Err(
+            "Expressão inválida, use o formato 'métrica > valor [unidade] [for 5m]'".into(),
+        );
+    };
+
+    let metric = Metric::parse(&captures[1].to_lowercase()).ok_or(format!(
+        "Métrica inválida, use uma de {}",
+        Metric::ALL
+            .iter()
+            .map(Metric::as_str)
+            .collect::<Vec<&str>>()
+            .join(", ")
+    ))?;
+    let comparison = Comparison::parse(&captures[2]).unwrap();
+    let value = captures[3].parse::<f64>().map_err(|e| e.to_string())?;
+    let unit = captures.get(4).map(|unit| unit.as_str().to_lowercase());
+    let threshold = match (metric.is_percentage(), unit.as_deref()) {
+        (true, None | Some("%")) => value,
+        (false, None | Some("mib")) => value,
+        (false, Some("b")) => value / 1024.0 / 1024.0,
+        (false, Some("kib")) => value / 1024.0,
+        (false, Some("gib")) => value * 1024.0,
+        (false, Some("tib")) => value * 1024.0 * 1024.0,
+        (true, Some(_)) => return Err(format!("'{}' é medida em %", metric.as_str())),
+        (false, Some(_)) => {
+            return Err(format!(
+                "'{}' é medida em bytes (B, KiB, MiB, GiB, TiB)",
+                metric.as_str()
+            ))
+        }
+    };
+
+    let duration = match captures.get(5) {
+        Some(duration) => duration_seconds(duration.as_str())?,
+        None => 0,
+    };
+
+    Ok(AlertCondition {
+        metric,
+        comparison,
+        threshold,
+        duration,
+    })
+}
+
+/// Sums the `amount unit` parts of a duration already matched by one of the patterns.
+fn duration_seconds(duration: &str) -> Result<u32, String> {
+    static PART: OnceLock<Regex> = OnceLock::new();
+    let part = PART.get_or_init(|| Regex::new(r"(?i)(\d+)\s*([smhd])").unwrap());
+    let mut total: u64 = 0;
+    for captures in part.captures_iter(duration) {
+        let amount = captures[1].parse::<u64>().map_err(|e| e.to_string())?;
+        let seconds = match captures[2].to_lowercase().as_str() {
+            "s" => Some(amount),
+            "m" => amount.checked_mul(60),
+            "h" => amount.checked_mul(3600),
+            _ => amount.checked_mul(86400),
+        };
+        total = seconds
+            .and_then(|seconds| total.checked_add(seconds))
+            .ok_or("Duração muito longa")?;
+    }
+    u32::try_from(total).map_err(|_| "Duração muito longa".to_string())
+}
+
+/// Parses durations such as `90s`, `15m`, `1h 30m` or `1d` into seconds.
+pub fn parse_duration(duration: &str) -> Result<u32, String> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| Regex::new(r"(?i)^\s*(?:\d+\s*[smhd]\s*)+$").unwrap());
+    if !pattern.is_match(duration) {
+        return Err("Duração inválida, use o formato '30m', '1h 30m' ou '1d'".into());
+    }
+    duration_seconds(duration)
+}
+
+/// Writes a condition back in the syntax read by `parse_condition`.
+pub fn format_condition(condition: &AlertCondition) -> String {
+    let unit = if condition.metric.is_percentage() {
+        "%"
+    } else {
+        " MiB"
+    };
+    let mut expression = format!(
+        "{} {} {}{}",
+        condition.metric.as_str(),
+        condition.comparison.as_str(),
+        condition.threshold,
+        unit
+    );
+    if condition.duration > 0 {
+        expression.push_str(" for ");
+        expression.push_str(&format_duration(condition.duration.into()));
+    }
+    expression
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_accepts_combined_units() {
+        assert_eq!(parse_duration("90s"), Ok(90));
+        assert_eq!(parse_duration("1h 30m"), Ok(5400));
+        assert_eq!(parse_duration("1D2h"), Ok(93600));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("10").is_err());
+        assert!(parse_duration("1h e 30m").is_err());
+        assert_eq!(
+            parse_duration("50000d"),
+            Err("Duração muito longa".to_string())
+        );
+    }
+
+    #[test]
+    fn formatted_condition_parses_back() {
+        for expression in [
+            "total_cpu_usage > 90 for 90s",
+            "memory_usage >= 80% for 1h 30m",
+            "free_memory < 500 MiB",
+        ] {
+            let condition = parse_condition(expression).unwrap();
+            let formatted = format_condition(&condition);
+            let parsed = parse_condition(&formatted).unwrap();
+            assert_eq!(parsed.duration, condition.duration, "{}", formatted);
+            assert_eq!(parsed.threshold, condition.threshold, "{}", formatted);
+        }
+        let condition = parse_condition("total_cpu_usage > 90 for 90s").unwrap();
+        assert_eq!(
+            format_condition(&condition),
+            "total_cpu_usage > 90% for 1m 30s"
+        );
+    }
+}
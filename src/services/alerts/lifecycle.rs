@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    database::{
+        models::{AlertRule, AlertSource, UrlData},
+        storage::SharedStorage,
+    },
+    services::{
+        alerts::escalation,
+        notifications::{notification::Notification, notifier::Notifier},
+    },
+};
+
+/// Labels every alert gets from its source, so user labels cannot take these names.
+pub const RESERVED_LABELS: [&str; 3] = ["source", "rule", "url"];
+
+pub fn rule_labels(rule: &AlertRule) -> BTreeMap<String, String> {
+    let mut labels = rule.labels.clone();
+    labels.insert("source".into(), "rule".into());
+    labels.insert("rule".into(), rule.name.clone());
+    labels
+}
+
+pub fn monitor_labels(url_data: &UrlData) -> BTreeMap<String, String> {
+    let mut labels = url_data.settings.labels.clone();
+    labels.insert("source".into(), "monitor".into());
+    labels.insert("url".into(), url_data.url.clone());
+    labels
+}
+
+/// Starts an alert for the source and notifies about it, unless one is already active.
+/// Without a notification the alert is opened quietly, and is not escalated either. The
+/// message of the notification starts the timeline of the incident.
+pub async fn raise(
+    storage: &SharedStorage,
+    notifier: &Notifier,
+    source: AlertSource,
+    name: String,
+    labels: BTreeMap<String, String>,
+    notification: Option<Notification>,
+) {
+    let detail = notification
+        .as_ref()
+        .map(|notification| notification.message.clone())
+        .unwrap_or_else(|| format!("Alerta aberto: {}", name));
+    match storage
+        .open_alert(source, name.clone(), labels, detail)
+        .await
+    {
+        Ok(Some(alert)) => {
+            if let Some(notification) = notification {
+                escalation::start(storage, &alert, &notification).await;
+                notifier.notify_alert(alert, notification);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("Error opening alert for {}: {}", name, e),
+    }
+}
+
+/// Resolves the active alert of the source and notifies about it, if there was one and a
+/// notification is given.
+pub async fn resolve(
+    storage: &SharedStorage,
+    notifier: &Notifier,
+    source: AlertSource,
+    notification: Option<Notification>,
+) {
+    let detail = notification
+        .as_ref()
+        .map(|notification| notification.message.clone())
+        .unwrap_or_else(|| "Alerta resolvido".into());
+    match storage.resolve_alert(source, detail).await {
+        Ok(Some(alert)) => {
+            if let Some(notification) = notification {
+                notifier.notify_alert(alert, notification);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("Error resolving {} alert: {}", source.as_str(), e),
+    }
+}
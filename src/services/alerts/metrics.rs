@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+
+use sysinfo::System;
+
+use crate::database::models::Metric;
+
+/// Keeps a `System` between samples, CPU usage is measured between two refreshes.
+pub struct MetricSampler {
+    sys: System,
+}
+
+impl MetricSampler {
+    pub fn new() -> MetricSampler {
+        let mut sys = System::new();
+        sys.refresh_cpu_usage();
+        sys.refresh_memory();
+        MetricSampler { sys }
+    }
+
+    /// Current value of every metric, memory in MiB like `/memory`.
+    pub fn sample(&mut self) -> HashMap<Metric, f64> {
+        self.sys.refresh_cpu_usage();
+        self.sys.refresh_memory();
+
+        let mib = |bytes: u64| bytes as f64 / 1024.0 / 1024.0;
+        let total = self.sys.total_memory();
+        let used = self.sys.used_memory();
+        let memory_usage = if total > 0 {
+            used as f64 * 100.0 / total as f64
+        } else {
+            0.0
+        };
+        HashMap::from([
+            (Metric::TotalCpuUsage, self.sys.global_cpu_usage() as f64),
+            (Metric::UsedMemory, mib(used)),
+            (Metric::FreeMemory, mib(self.sys.free_memory())),
+            (Metric::AvailableMemory, mib(self.sys.available_memory())),
+            (Metric::MemoryUsage, memory_usage),
+        ])
+    }
+}
@@ -0,0 +1,5 @@
+pub mod escalation;
+pub mod evaluation;
+pub mod expression;
+pub mod lifecycle;
+pub mod metrics;
@@ -1 +1,4 @@
-pub mod os;
\ No newline at end of file
+pub mod alerts;
+pub mod monitor;
+pub mod notifications;
+pub mod os;
@@ -0,0 +1,119 @@
+use reqwest::header::HeaderMap;
+
+use crate::database::models::Assertion;
+
+/// The parts of a response the assertions look at.
+pub struct ResponseData<'a> {
+    pub headers: &'a HeaderMap,
+    pub body: &'a [u8],
+    pub response_time_ms: i64,
+}
+
+/// Evaluates the assertions in order and describes the first one that fails.
+pub fn first_failure(assertions: &[Assertion], response: &ResponseData) -> Option<String> {
+    assertions
+        .iter()
+        .find_map(|assertion| check(assertion, response).err())
+}
+
+fn check(assertion: &Assertion, response: &ResponseData) -> Result<(), String> {
+    let body = String::from_utf8_lossy(response.body);
+    match assertion {
+        Assertion::BodyContains(text) => {
+            if body.contains(text.as_str()) {
+                Ok(())
+            } else {
+                Err(format!("body_contains: \"{}\" não encontrado", text))
+            }
+        }
+        Assertion::BodyNotContains(text) => {
+            if body.contains(text.as_str()) {
+                Err(format!("body_not_contains: \"{}\" encontrado", text))
+            } else {
+                Ok(())
+            }
+        }
+        Assertion::BodyMatches(regex) => {
+            if regex.is_match(&body) {
+                Ok(())
+            } else {
+                Err(format!("body_matches: /{}/ sem correspondência", regex))
+            }
+        }
+        Assertion::JsonPointerEquals { pointer, value } => {
+            let json = serde_json::from_slice::<serde_json::Value>(response.body)
+                .map_err(|_| format!("json_pointer_equals {}: corpo não é JSON", pointer))?;
+            match json.pointer(pointer) {
+                Some(found) if found == value => Ok(()),
+                Some(found) => Err(format!(
+                    "json_pointer_equals {}: esperado {}, recebido {}",
+                    pointer, value, found
+                )),
+                None => Err(format!("json_pointer_equals {}: campo ausente", pointer)),
+            }
+        }
+        Assertion::HeaderPresent(name) => {
+            if response.headers.contains_key(name.as_str()) {
+                Ok(())
+            } else {
+                Err(format!("header_present: cabeçalho {} ausente", name))
+            }
+        }
+        Assertion::HeaderEquals { name, value } => match response.headers.get(name.as_str()) {
+            Some(found) if found.as_bytes() == value.as_bytes() => Ok(()),
+            Some(found) => Err(format!(
+                "header_equals {}: esperado \"{}\", recebido \"{}\"",
+                name,
+                value,
+                String::from_utf8_lossy(found.as_bytes())
+            )),
+            None => Err(format!("header_equals {}: cabeçalho ausente", name)),
+        },
+        Assertion::MaxResponseTime(limit) => {
+            if response.response_time_ms <= *limit as i64 {
+                Ok(())
+            } else {
+                Err(format!(
+                    "max_response_time: {} ms acima do limite de {} ms",
+                    response.response_time_ms, limit
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body_matches(pattern: &str) -> Result<Assertion, String> {
+        Assertion::from_json(&serde_json::json!({ "type": "body_matches", "value": pattern }))
+    }
+
+    #[test]
+    fn body_matches_is_compiled_when_read() {
+        let assertion = body_matches(r#""status":\s*"ok""#).unwrap();
+        assert_eq!(assertion.to_json()["value"], r#""status":\s*"ok""#);
+        assert!(body_matches("(unclosed").is_err());
+    }
+
+    #[test]
+    fn first_failure_describes_the_unmatched_pattern() {
+        let headers = HeaderMap::new();
+        let response = ResponseData {
+            headers: &headers,
+            body: br#"{"status": "ok"}"#,
+            response_time_ms: 10,
+        };
+        let matching = [body_matches(r#""status":\s*"ok""#).unwrap()];
+        assert_eq!(first_failure(&matching, &response), None);
+        let failing = [
+            Assertion::BodyContains("status".into()),
+            body_matches("^erro").unwrap(),
+        ];
+        assert_eq!(
+            first_failure(&failing, &response).as_deref(),
+            Some("body_matches: /^erro/ sem correspondência")
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use std::{env, sync::Arc, time::Duration};
+
+use chrono::{DateTime, NaiveDateTime};
+use tokio::net::TcpStream;
+use tokio_rustls::{
+    rustls::{
+        self,
+        client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        crypto::{self, CryptoProvider},
+        pki_types::{CertificateDer, ServerName, UnixTime},
+        ClientConfig, DigitallySignedStruct, SignatureScheme,
+    },
+    TlsConnector,
+};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use crate::database::{
+    models::{CertificateInfo, UrlCertificate},
+    time::{format_timestamp, now, parse_timestamp},
+};
+
+/// Accepts any certificate. The HTTP check already validates the chain; this handshake only
+/// reads it, so that expired or self-signed certificates, which fail the HTTP check itself,
+/// can still be reported.
+#[derive(Debug)]
+struct AcceptAnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// Days before `not_after` at which a certificate is flagged, from `CERT_EXPIRY_WARNING_DAYS`.
+pub fn expiry_warning_days() -> i64 {
+    env::var("CERT_EXPIRY_WARNING_DAYS")
+        .ok()
+        .and_then(|days| days.parse::<i64>().ok())
+        .filter(|days| *days >= 0)
+        .unwrap_or(14)
+}
+
+/// Whole days left until the leaf certificate expires, negative once it has expired.
+pub fn days_until_expiry(certificate: &UrlCertificate) -> Option<i64> {
+    let leaf = certificate.chain.first()?;
+    let not_after = parse_timestamp(&leaf.not_after)?;
+    Some((not_after - now()).num_days())
+}
+
+pub fn expiring_soon(certificate: &UrlCertificate) -> bool {
+    days_until_expiry(certificate).is_some_and(|days| days <= expiry_warning_days())
+}
+
+/// `Some(true)` when `current` entered the warning period since `previous` was read, or is
+/// already in it on the first read, `Some(false)` when a renewal took it out again.
+pub fn expiry_change(previous: Option<&UrlCertificate>, current: &UrlCertificate) -> Option<bool> {
+    let was_expiring = previous.is_some_and(expiring_soon);
+    let is_expiring = expiring_soon(current);
+    (was_expiring != is_expiring).then_some(is_expiring)
+}
+
+/// Reads the certificate chain presented by `https://` URLs. Chains are only fetched again
+/// once `CERT_CHECK_INTERVAL` seconds have passed, certificates rarely change.
+///
+/// The chain is read in a separate TLS handshake to the same host and port, right after the
+/// HTTP check, not from the check's own connection: reqwest only exposes the leaf of a
+/// verified connection, and nothing at all when verification fails. A server behind a load
+/// balancer with different certificates per node may therefore report another node's chain
+/// than the one the check talked to.
+#[derive(Clone)]
+pub struct CertificateChecker {
+    connector: TlsConnector,
+    every: Duration,
+}
+
+impl CertificateChecker {
+    pub fn new() -> Result<CertificateChecker, rustls::Error> {
+        let provider = Arc::new(crypto::ring::default_provider());
+        let config = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate(provider)))
+            .with_no_client_auth();
+        let every = env::var("CERT_CHECK_INTERVAL")
+            .ok()
+            .and_then(|seconds| seconds.parse::<u64>().ok())
+            .unwrap_or(3600);
+        Ok(CertificateChecker {
+            connector: TlsConnector::from(Arc::new(config)),
+            every: Duration::from_secs(every),
+        })
+    }
+
+    /// Whether `url` is an `https://` URL whose chain was never read or is older than the
+    /// check interval.
+    pub fn is_due(&self, url: &str, certificate: Option<&UrlCertificate>) -> bool {
+        if !url.to_ascii_lowercase().starts_with("https://") {
+            return false;
+        }
+        certificate
+            .and_then(|certificate| parse_timestamp(&certificate.checked_at))
+            .is_none_or(|checked_at| {
+                (now() - checked_at).to_std().unwrap_or_default() >= self.every
+            })
+    }
+
+    pub async fn fetch(&self, url: &str, timeout: Duration) -> Result<UrlCertificate, String> {
+        let url = url::Url::parse(url).map_err(|e| e.to_string())?;
+        let host = url
+            .host_str()
+            .ok_or("URL sem host")?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+        let port = url.port_or_known_default().unwrap_or(443);
+        let server_name = ServerName::try_from(host.clone()).map_err(|e| e.to_string())?;
+
+        let handshake = async {
+            let stream = TcpStream::connect((host.as_str(), port)).await?;
+            self.connector.connect(server_name, stream).await
+        };
+        let stream = tokio::time::timeout(timeout, handshake)
+            .await
+            .map_err(|_| "Tempo esgotado na conexão TLS".to_string())?
+            .map_err(|e| e.to_string())?;
+
+        let (_, connection) = stream.get_ref();
+        let chain = connection
+            .peer_certificates()
+            .unwrap_or_default()
+            .iter()
+            .map(|der| certificate_info(der))
+            .collect::<Result<Vec<_>, _>>()?;
+        if chain.is_empty() {
+            return Err("Servidor não apresentou certificado".into());
+        }
+        Ok(UrlCertificate {
+            chain,
+            checked_at: format_timestamp(&now()),
+        })
+    }
+}
+
+fn certificate_info(der: &CertificateDer<'_>) -> Result<CertificateInfo, String> {
+    let (_, certificate) = X509Certificate::from_der(der).map_err(|e| e.to_string())?;
+    let validity = certificate.validity();
+    Ok(CertificateInfo {
+        subject: certificate.subject().to_string(),
+        issuer: certificate.issuer().to_string(),
+        not_before: timestamp(validity.not_before.timestamp()),
+        not_after: timestamp(validity.not_after.timestamp()),
+    })
+}
+
+fn timestamp(seconds: i64) -> String {
+    DateTime::from_timestamp(seconds, 0)
+        .map(|timestamp| timestamp.naive_utc())
+        .map(|timestamp: NaiveDateTime| format_timestamp(&timestamp))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, NaiveDate, TimeDelta, Utc};
+    use tokio::{io::AsyncReadExt, net::TcpListener};
+    use tokio_rustls::{
+        rustls::{pki_types::PrivateKeyDer, ServerConfig},
+        TlsAcceptor,
+    };
+
+    use super::*;
+
+    /// TLS server on a random local port presenting a self-signed certificate for
+    /// 127.0.0.1 that expires at the start of `not_after`.
+    async fn tls_stand_in(not_after: NaiveDate) -> u16 {
+        let key = rcgen::KeyPair::generate().unwrap();
+        let mut params = rcgen::CertificateParams::new(vec!["127.0.0.1".to_string()]).unwrap();
+        params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, "monitor test");
+        params.not_before = rcgen::date_time_ymd(2020, 1, 1);
+        params.not_after = rcgen::date_time_ymd(
+            not_after.year(),
+            not_after.month() as u8,
+            not_after.day() as u8,
+        );
+        let certificate = params.self_signed(&key).unwrap();
+
+        let config =
+            ServerConfig::builder_with_provider(Arc::new(crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .unwrap()
+                .with_no_client_auth()
+                .with_single_cert(
+                    vec![certificate.der().clone()],
+                    PrivateKeyDer::Pkcs8(key.serialize_der().into()),
+                )
+                .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    if let Ok(mut stream) = acceptor.accept(stream).await {
+                        let _ = stream.read(&mut [0; 16]).await;
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    fn certificate(not_after: NaiveDate) -> UrlCertificate {
+        UrlCertificate {
+            chain: vec![CertificateInfo {
+                subject: "CN=monitor test".into(),
+                issuer: "CN=monitor test".into(),
+                not_before: "2020-01-01 00:00:00".into(),
+                not_after: format!("{} 00:00:00", not_after),
+            }],
+            checked_at: format_timestamp(&now()),
+        }
+    }
+
+    fn in_days(days: i64) -> NaiveDate {
+        (Utc::now() + TimeDelta::days(days)).date_naive()
+    }
+
+    #[tokio::test]
+    async fn fetch_reads_chain_of_local_tls_server() {
+        let not_after = in_days(5);
+        let port = tls_stand_in(not_after).await;
+        let checker = CertificateChecker::new().unwrap();
+
+        let certificate = checker
+            .fetch(
+                &format!("https://127.0.0.1:{}/health", port),
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(certificate.chain.len(), 1);
+        let leaf = &certificate.chain[0];
+        assert_eq!(leaf.subject, "CN=monitor test");
+        assert_eq!(leaf.issuer, "CN=monitor test");
+        assert_eq!(leaf.not_before, "2020-01-01 00:00:00");
+        assert_eq!(leaf.not_after, format!("{} 00:00:00", not_after));
+        assert!(matches!(days_until_expiry(&certificate), Some(4 | 5)));
+        assert!(expiring_soon(&certificate));
+    }
+
+    #[tokio::test]
+    async fn fetch_fails_without_tls_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        let checker = CertificateChecker::new().unwrap();
+
+        let fetched = checker
+            .fetch(
+                &format!("https://127.0.0.1:{}", port),
+                Duration::from_secs(5),
+            )
+            .await;
+
+        assert!(fetched.is_err());
+    }
+
+    #[test]
+    fn is_due_only_for_https_urls_after_the_interval() {
+        let checker = CertificateChecker::new().unwrap();
+        let fresh = certificate(in_days(90));
+        let mut stale = certificate(in_days(90));
+        stale.checked_at = format_timestamp(&(now() - TimeDelta::days(2)));
+
+        assert!(!checker.is_due("http://example.com", None));
+        assert!(!checker.is_due("tcp://example.com:443", None));
+        assert!(checker.is_due("HTTPS://example.com", None));
+        assert!(!checker.is_due("https://example.com", Some(&fresh)));
+        assert!(checker.is_due("https://example.com", Some(&stale)));
+    }
+
+    #[test]
+    fn expiry_change_reports_entering_and_leaving_the_warning_period() {
+        let valid = certificate(in_days(90));
+        let expiring = certificate(in_days(5));
+        let expired = certificate(in_days(-1));
+
+        assert_eq!(expiry_change(None, &valid), None);
+        assert_eq!(expiry_change(None, &expiring), Some(true));
+        assert_eq!(expiry_change(Some(&valid), &expiring), Some(true));
+        assert_eq!(expiry_change(Some(&expiring), &expiring), None);
+        assert_eq!(expiry_change(Some(&expiring), &expired), None);
+        assert_eq!(expiry_change(Some(&expired), &valid), Some(false));
+        assert_eq!(expiry_change(Some(&valid), &valid), None);
+    }
+}
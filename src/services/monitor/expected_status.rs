@@ -0,0 +1,33 @@
+/// Accepted HTTP status codes, parsed from a list such as `200-299,301,404`.
+pub struct ExpectedStatus {
+    ranges: Vec<(u16, u16)>,
+}
+
+impl ExpectedStatus {
+    pub fn parse(value: &str) -> Result<ExpectedStatus, String> {
+        let mut ranges = Vec::new();
+        for part in value.split(',').map(str::trim) {
+            let (start, end) = part.split_once('-').unwrap_or((part, part));
+            let start = parse_code(start)?;
+            let end = parse_code(end)?;
+            if start > end {
+                return Err(format!("Faixa de status inválida: {}", part));
+            }
+            ranges.push((start, end));
+        }
+        Ok(ExpectedStatus { ranges })
+    }
+
+    pub fn matches(&self, status: u16) -> bool {
+        self.ranges
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&status))
+    }
+}
+
+fn parse_code(value: &str) -> Result<u16, String> {
+    match value.trim().parse::<u16>() {
+        Ok(code) if (100..=599).contains(&code) => Ok(code),
+        _ => Err(format!("Código de status inválido: {}", value)),
+    }
+}
@@ -0,0 +1,171 @@
+use chrono::{NaiveDateTime, TimeDelta};
+
+use crate::database::{
+    models::{CheckOutcome, CheckResult, Heartbeat, UrlData, UrlSettings},
+    time::{format_timestamp, parse_timestamp},
+};
+
+/// A failure message longer than this is cut, the job may send its whole log.
+const MAX_MESSAGE_LENGTH: usize = 1000;
+
+/// What a monitored job tells about its run.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Ping {
+    Start,
+    Success,
+    Fail,
+}
+
+impl Ping {
+    /// The last segment of the ping URL, `/heartbeat/{token}` alone being a success.
+    pub fn parse(segment: Option<&str>) -> Option<Ping> {
+        match segment {
+            None => Some(Ping::Success),
+            Some("start") => Some(Ping::Start),
+            Some("fail") => Some(Ping::Fail),
+            _ => None,
+        }
+    }
+}
+
+/// Ping state of a monitor that just became a heartbeat monitor, with a new random token.
+/// The first ping is expected within one interval and the grace time.
+pub fn new(settings: &UrlSettings, at: NaiveDateTime) -> Result<Heartbeat, getrandom::Error> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes)?;
+    Ok(Heartbeat {
+        token: bytes.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        last_ping_at: None,
+        started_at: None,
+        expected_at: format_timestamp(&expected_after(settings, at)),
+    })
+}
+
+fn expected_after(settings: &UrlSettings, at: NaiveDateTime) -> NaiveDateTime {
+    at + TimeDelta::seconds(settings.interval as i64 + settings.grace as i64)
+}
+
+/// Recomputes the deadline after the settings of the monitor changed, from the last ping or
+/// from `at` when it was never pinged. A started run keeps its deadline.
+pub fn rescheduled(heartbeat: &Heartbeat, settings: &UrlSettings, at: NaiveDateTime) -> Heartbeat {
+    let mut heartbeat = heartbeat.clone();
+    if heartbeat.started_at.is_none() {
+        let since = heartbeat
+            .last_ping_at
+            .as_deref()
+            .and_then(parse_timestamp)
+            .unwrap_or(at);
+        heartbeat.expected_at = format_timestamp(&expected_after(settings, since));
+    }
+    heartbeat
+}
+
+pub fn is_late(heartbeat: &Heartbeat, at: NaiveDateTime) -> bool {
+    parse_timestamp(&heartbeat.expected_at).is_some_and(|expected_at| at > expected_at)
+}
+
+/// The heartbeat once a missed ping was recorded at `at`, the next miss being one interval
+/// later.
+pub fn missed(heartbeat: &Heartbeat, settings: &UrlSettings, at: NaiveDateTime) -> Heartbeat {
+    Heartbeat {
+        started_at: None,
+        expected_at: format_timestamp(&(at + TimeDelta::seconds(settings.interval as i64))),
+        ..heartbeat.clone()
+    }
+}
+
+/// The heartbeat once `ping` was received at `at`. A started run must end within the grace
+/// time, a finished one be followed by the next run within the interval and grace time.
+pub fn pinged(
+    heartbeat: &Heartbeat,
+    settings: &UrlSettings,
+    ping: Ping,
+    at: NaiveDateTime,
+) -> Heartbeat {
+    let timestamp = format_timestamp(&at);
+    match ping {
+        Ping::Start => Heartbeat {
+            started_at: Some(timestamp),
+            expected_at: format_timestamp(&(at + TimeDelta::seconds(settings.grace as i64))),
+            ..heartbeat.clone()
+        },
+        Ping::Success | Ping::Fail => Heartbeat {
+            last_ping_at: Some(timestamp),
+            started_at: None,
+            expected_at: format_timestamp(&expected_after(settings, at)),
+            ..heartbeat.clone()
+        },
+    }
+}
+
+/// Recorded when the deadline passed without a ping. A started run that did not end is a
+/// timeout rather than a missed run.
+pub fn late_result(url_data: &UrlData) -> CheckResult {
+    let heartbeat = url_data.heartbeat.as_ref();
+    let (outcome, error) = match heartbeat {
+        Some(Heartbeat {
+            started_at: Some(started_at),
+            ..
+        }) => (
+            CheckOutcome::Timeout,
+            format!("Run started at {} did not finish", started_at),
+        ),
+        Some(Heartbeat {
+            last_ping_at: Some(last_ping_at),
+            ..
+        }) => (
+            CheckOutcome::Late,
+            format!("No ping since {}", last_ping_at),
+        ),
+        _ => (CheckOutcome::Late, "No ping received yet".into()),
+    };
+    CheckResult {
+        status_code: None,
+        up: false,
+        outcome,
+        response_time_ms: 0,
+        response_size: None,
+        error: Some(error),
+        failed_assertion: None,
+        maintenance: false,
+    }
+}
+
+/// Result of a success or fail ping received at `at`. The response time is the duration of
+/// the run when it was announced with a start ping, whose time is only stored to the second.
+/// `message` is the body of a fail ping.
+pub fn ping_result(
+    heartbeat: &Heartbeat,
+    ping: Ping,
+    at: NaiveDateTime,
+    message: Option<String>,
+) -> CheckResult {
+    let duration = heartbeat
+        .started_at
+        .as_deref()
+        .and_then(parse_timestamp)
+        .map(|started_at| (at - started_at).num_milliseconds().max(0))
+        .unwrap_or(0);
+    let failed = ping == Ping::Fail;
+    let error = failed.then(|| {
+        let message = message
+            .map(|message| message.trim().to_string())
+            .filter(|message| !message.is_empty())
+            .unwrap_or_else(|| "Failure reported by the job".into());
+        message.chars().take(MAX_MESSAGE_LENGTH).collect()
+    });
+    CheckResult {
+        status_code: None,
+        up: !failed,
+        outcome: if failed {
+            CheckOutcome::Failed
+        } else {
+            CheckOutcome::Ok
+        },
+        response_time_ms: duration,
+        response_size: None,
+        error,
+        failed_assertion: None,
+        maintenance: false,
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    env,
+    error::Error,
+    time::{Duration, Instant},
+};
+
+use reqwest::{redirect, Client, Method, Response};
+
+use crate::{
+    database::models::{CheckOutcome, CheckResult, UrlData},
+    services::monitor::{
+        assertions::{self, ResponseData},
+        expected_status::ExpectedStatus,
+    },
+};
+
+/// Keeps connections around long enough to be reused by the default 60 s interval.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Runs HTTP checks according to each monitor's settings. Holds one client per redirect
+/// policy, since reqwest only configures redirects per client; clones share the clients
+/// and their connection pools.
+#[derive(Clone)]
+pub struct HttpChecker {
+    client: Client,
+    client_no_redirect: Client,
+    /// Bytes of the body kept for assertions, from `CHECK_MAX_BODY_BYTES`. The rest is only
+    /// counted, or left unread when the response tells its length.
+    max_body_size: usize,
+}
+
+impl HttpChecker {
+    pub fn new() -> reqwest::Result<HttpChecker> {
+        let max_body_size = env::var("CHECK_MAX_BODY_BYTES")
+            .ok()
+            .and_then(|bytes| bytes.parse::<usize>().ok())
+            .unwrap_or(1024 * 1024);
+        Ok(HttpChecker {
+            client: Client::builder()
+                .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+                .build()?,
+            client_no_redirect: Client::builder()
+                .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+                .redirect(redirect::Policy::none())
+                .build()?,
+            max_body_size,
+        })
+    }
+
+    pub async fn check(&self, url_data: &UrlData) -> CheckResult {
+        let settings = &url_data.settings;
+        let client = if settings.follow_redirects {
+            &self.client
+        } else {
+            &self.client_no_redirect
+        };
+        let method = Method::from_bytes(settings.method.as_bytes()).unwrap_or(Method::GET);
+
+        let mut request = client
+            .request(method, &url_data.url)
+            .timeout(Duration::from_secs(settings.timeout as u64));
+        for (name, value) in &settings.headers {
+            request = request.header(name, value);
+        }
+        if let Some(body) = &settings.body {
+            request = request.body(body.clone());
+        }
+
+        let started = Instant::now();
+        let mut response = match request.send().await {
+            Ok(response) => response,
+            Err(err) => {
+                eprintln!("Error making request to {}: {}", url_data.url, err);
+                return CheckResult {
+                    status_code: None,
+                    up: false,
+                    outcome: classify_error(&err),
+                    response_time_ms: started.elapsed().as_millis() as i64,
+                    response_size: None,
+                    error: Some(error_chain(&err)),
+                    failed_assertion: None,
+                    maintenance: false,
+                };
+            }
+        };
+
+        let status = response.status().as_u16();
+        let headers = response.headers().clone();
+        // Response time covers the whole body, not just the headers
+        let (body, size, error) = match read_body(&mut response, self.max_body_size).await {
+            Ok((body, size)) => (Some(body), Some(size), None),
+            Err(err) => (None, None, Some((err, "Error reading body"))),
+        };
+        let response_time_ms = started.elapsed().as_millis() as i64;
+
+        let expected = ExpectedStatus::parse(&settings.expected_status)
+            .map(|expected| expected.matches(status))
+            .unwrap_or(false);
+        let failed_assertion = match &body {
+            Some(body) if expected => assertions::first_failure(
+                &settings.assertions,
+                &ResponseData {
+                    headers: &headers,
+                    body,
+                    response_time_ms,
+                },
+            ),
+            _ => None,
+        };
+        let outcome = match (&error, &failed_assertion) {
+            (Some((err, _)), _) => classify_error(err),
+            _ if !expected => CheckOutcome::HttpError,
+            (None, Some(_)) => CheckOutcome::AssertionFailed,
+            (None, None) => CheckOutcome::Ok,
+        };
+        println!(
+            "URL: {}, Status Code: {}, {} ms",
+            url_data.url, status, response_time_ms
+        );
+        CheckResult {
+            status_code: Some(status as i32),
+            up: outcome == CheckOutcome::Ok,
+            outcome,
+            response_time_ms,
+            response_size: size.map(|size| size as i64),
+            error: error.map(|(err, context)| format!("{}: {}", context, error_chain(&err))),
+            failed_assertion,
+            maintenance: false,
+        }
+    }
+}
+
+/// Keeps the body up to `limit` bytes, so a huge or endless response cannot exhaust memory,
+/// along with the size of the whole body. Assertions only see the part kept. Past the limit
+/// the rest is counted without being kept, unless the response announced its length.
+async fn read_body(response: &mut Response, limit: usize) -> reqwest::Result<(Vec<u8>, u64)> {
+    let content_length = response.content_length();
+    let mut body = Vec::new();
+    let mut size = 0;
+    while let Some(chunk) = response.chunk().await? {
+        size += chunk.len() as u64;
+        let room = limit.saturating_sub(body.len());
+        body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        if body.len() >= limit {
+            if let Some(length) = content_length {
+                return Ok((body, length));
+            }
+        }
+    }
+    Ok((body, size))
+}
+
+/// reqwest's own message only names the URL, the cause is further down the chain.
+fn error_chain(err: &reqwest::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(current) = source {
+        message.push_str(": ");
+        message.push_str(&current.to_string());
+        source = current.source();
+    }
+    message
+}
+
+/// Maps a reqwest error to the failure kind. reqwest does not expose DNS and TLS failures
+/// as such, so the messages of the underlying errors are inspected.
+fn classify_error(err: &reqwest::Error) -> CheckOutcome {
+    if err.is_timeout() {
+        return CheckOutcome::Timeout;
+    }
+    if err.is_builder() {
+        return CheckOutcome::InvalidUrl;
+    }
+
+    let mut source: Option<&dyn Error> = Some(err);
+    while let Some(current) = source {
+        let message = current.to_string().to_lowercase();
+        if message.contains("dns error") || message.contains("failed to lookup address") {
+            return CheckOutcome::Dns;
+        }
+        if message.contains("certificate")
+            || message.contains("tls")
+            || message.contains("ssl")
+            || message.contains("handshake")
+        {
+            return CheckOutcome::Tls;
+        }
+        if let Some(io) = current.downcast_ref::<std::io::Error>() {
+            if io.kind() == std::io::ErrorKind::TimedOut {
+                return CheckOutcome::Timeout;
+            }
+        }
+        source = current.source();
+    }
+    CheckOutcome::Connect
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::models::{Assertion, UrlSettings},
+        test_support::HttpStandIn,
+    };
+
+    fn monitor(url: String, assertions: Vec<Assertion>) -> UrlData {
+        UrlData {
+            id: 1,
+            url,
+            settings: UrlSettings {
+                assertions,
+                ..UrlSettings::default()
+            },
+            status_code: None,
+            up: None,
+            outcome: None,
+            unconfirmed_checks: 0,
+            flapping: false,
+            certificate: None,
+            heartbeat: None,
+            created_at: "2024-05-01 00:00:00".into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn body_is_read_up_to_the_limit() {
+        let server = HttpStandIn::start(&[]).await;
+        let assertions = vec![Assertion::BodyContains("true".into())];
+        let url_data = monitor(server.url("/health"), assertions);
+        let mut checker = HttpChecker::new().unwrap();
+
+        let result = checker.check(&url_data).await;
+        assert_eq!(result.outcome, CheckOutcome::Ok);
+        assert_eq!(result.response_size, Some(12));
+
+        checker.max_body_size = 5;
+        let result = checker.check(&url_data).await;
+        assert_eq!(result.status_code, Some(200));
+        assert_eq!(result.outcome, CheckOutcome::AssertionFailed);
+        // The size is the whole body's, from its Content-Length
+        assert_eq!(result.response_size, Some(12));
+    }
+
+    #[tokio::test]
+    async fn refused_port_is_a_connect_failure() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+
+        let result = HttpChecker::new()
+            .unwrap()
+            .check(&monitor(url, Vec::new()))
+            .await;
+        assert!(!result.up);
+        assert_eq!(result.status_code, None);
+        assert_eq!(result.outcome, CheckOutcome::Connect);
+        assert!(result.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn unresolvable_host_is_a_dns_failure() {
+        // The .invalid top level domain never resolves
+        let url = "http://monitor.invalid/".to_string();
+        let result = HttpChecker::new()
+            .unwrap()
+            .check(&monitor(url, Vec::new()))
+            .await;
+        assert!(!result.up);
+        assert_eq!(result.outcome, CheckOutcome::Dns);
+    }
+
+    #[tokio::test]
+    async fn stalled_server_times_out() {
+        let server = HttpStandIn::start_delayed(&[], Duration::from_secs(5)).await;
+        let mut url_data = monitor(server.url("/slow"), Vec::new());
+        url_data.settings.timeout = 1;
+
+        let result = HttpChecker::new().unwrap().check(&url_data).await;
+        assert!(!result.up);
+        assert_eq!(result.outcome, CheckOutcome::Timeout);
+        assert!(result.response_time_ms >= 1000 && result.response_time_ms < 5000);
+        assert_eq!(server.take_requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn body_past_the_limit_is_counted_without_a_length() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/stream", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await.unwrap();
+            stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n",
+                )
+                .await
+                .unwrap();
+            for _ in 0..3 {
+                stream.write_all(b"4\r\nabcd\r\n").await.unwrap();
+            }
+            stream.write_all(b"0\r\n\r\n").await.unwrap();
+        });
+        let mut checker = HttpChecker::new().unwrap();
+        checker.max_body_size = 5;
+
+        let result = checker.check(&monitor(url, Vec::new())).await;
+        assert_eq!(result.outcome, CheckOutcome::Ok);
+        assert_eq!(result.response_size, Some(12));
+    }
+}
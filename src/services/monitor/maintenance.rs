@@ -0,0 +1,303 @@
+use chrono::{NaiveDateTime, TimeDelta};
+use croner::Cron;
+
+use crate::database::{
+    models::{
+        MaintenanceMode, MaintenanceSchedule, MaintenanceSettings, MaintenanceWindow, UrlData,
+    },
+    time::parse_timestamp,
+};
+
+/// Occurrences of a recurring window listed for a single range, so a `* * * * *` window
+/// over a year stays cheap.
+const MAX_INTERVALS: usize = 10_000;
+
+pub fn parse_cron(expression: &str) -> Result<Cron, String> {
+    Cron::new(expression)
+        .parse()
+        .map_err(|e| format!("Expressão cron inválida: {}", e))
+}
+
+/// The `[start, end)` periods of the schedule that overlap `[from, to)`, oldest first.
+pub fn intervals(
+    schedule: &MaintenanceSchedule,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    match schedule {
+        MaintenanceSchedule::Once { starts_at, ends_at } => {
+            match (parse_timestamp(starts_at), parse_timestamp(ends_at)) {
+                (Some(start), Some(end)) if start < to && end > from => vec![(start, end)],
+                _ => Vec::new(),
+            }
+        }
+        MaintenanceSchedule::Recurring { cron, duration } => {
+            let Ok(cron) = parse_cron(cron) else {
+                return Vec::new();
+            };
+            let duration = TimeDelta::seconds(*duration as i64);
+            // Occurrences up to `duration` before `from` are still open at `from`
+            cron.iter_after((from - duration).and_utc())
+                .map(|start| start.naive_utc())
+                .take_while(|start| *start < to)
+                .take(MAX_INTERVALS)
+                .map(|start| (start, start + duration))
+                .collect()
+        }
+    }
+}
+
+pub fn is_active(schedule: &MaintenanceSchedule, at: NaiveDateTime) -> bool {
+    intervals(schedule, at, at + TimeDelta::seconds(1))
+        .iter()
+        .any(|(start, end)| *start <= at && at < *end)
+}
+
+/// Start of the next period after `after`, `None` once a one-off window has started.
+pub fn next_start(schedule: &MaintenanceSchedule, after: NaiveDateTime) -> Option<NaiveDateTime> {
+    match schedule {
+        MaintenanceSchedule::Once { starts_at, .. } => {
+            parse_timestamp(starts_at).filter(|start| *start > after)
+        }
+        MaintenanceSchedule::Recurring { cron, .. } => parse_cron(cron)
+            .ok()?
+            .find_next_occurrence(&after.and_utc(), false)
+            .ok()
+            .map(|start| start.naive_utc()),
+    }
+}
+
+/// Whether the window covers the monitor, by id or by the host of its URL.
+pub fn applies_to(settings: &MaintenanceSettings, url_data: &UrlData) -> bool {
+    if settings.url_ids.contains(&url_data.id) {
+        return true;
+    }
+    let Some(host) = url::Url::parse(&url_data.url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_ascii_lowercase))
+    else {
+        return false;
+    };
+    settings.hosts.contains(&host)
+}
+
+/// The window the monitor is in at `at`. When several overlap, a pausing one wins.
+pub fn active_window<'a>(
+    windows: &'a [MaintenanceWindow],
+    url_data: &UrlData,
+    at: NaiveDateTime,
+) -> Option<&'a MaintenanceWindow> {
+    covering(&active_windows(windows, at), url_data)
+}
+
+/// The windows active at `at`, so many monitors can be looked up with `covering` while each
+/// schedule is expanded once.
+pub fn active_windows(windows: &[MaintenanceWindow], at: NaiveDateTime) -> Vec<&MaintenanceWindow> {
+    windows
+        .iter()
+        .filter(|window| is_active(&window.settings.schedule, at))
+        .collect()
+}
+
+/// The window among the `active` ones that the monitor is in. When several overlap, a
+/// pausing one wins.
+pub fn covering<'a>(
+    active: &[&'a MaintenanceWindow],
+    url_data: &UrlData,
+) -> Option<&'a MaintenanceWindow> {
+    active
+        .iter()
+        .copied()
+        .filter(|window| applies_to(&window.settings, url_data))
+        .min_by_key(|window| window.settings.mode != MaintenanceMode::Pause)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::UrlSettings;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn once(starts_at: &str, ends_at: &str) -> MaintenanceSchedule {
+        MaintenanceSchedule::Once {
+            starts_at: starts_at.into(),
+            ends_at: ends_at.into(),
+        }
+    }
+
+    fn nightly() -> MaintenanceSchedule {
+        MaintenanceSchedule::Recurring {
+            cron: "0 2 * * *".into(),
+            duration: 2 * 3600,
+        }
+    }
+
+    fn monitor(id: i32, url: &str) -> UrlData {
+        UrlData {
+            id,
+            url: url.into(),
+            settings: UrlSettings::default(),
+            status_code: None,
+            up: None,
+            outcome: None,
+            unconfirmed_checks: 0,
+            flapping: false,
+            certificate: None,
+            heartbeat: None,
+            created_at: "2024-05-01 00:00:00".into(),
+        }
+    }
+
+    fn window(
+        id: i64,
+        mode: MaintenanceMode,
+        url_ids: Vec<i32>,
+        hosts: Vec<&str>,
+    ) -> MaintenanceWindow {
+        MaintenanceWindow {
+            id,
+            settings: MaintenanceSettings {
+                name: format!("janela {}", id),
+                mode,
+                schedule: once("2024-05-01 10:00:00", "2024-05-01 12:00:00"),
+                url_ids,
+                hosts: hosts.into_iter().map(String::from).collect(),
+            },
+            created_at: "2024-05-01 00:00:00".into(),
+        }
+    }
+
+    #[test]
+    fn once_overlapping_the_range_is_listed() {
+        let schedule = once("2024-05-01 10:00:00", "2024-05-01 12:00:00");
+        assert_eq!(
+            intervals(&schedule, at(1, 11, 0), at(1, 13, 0)),
+            [(at(1, 10, 0), at(1, 12, 0))]
+        );
+    }
+
+    #[test]
+    fn once_touching_the_range_is_not_listed() {
+        let schedule = once("2024-05-01 10:00:00", "2024-05-01 12:00:00");
+        // Intervals are half-open, so a window ending at `from` or starting at `to` is out
+        assert!(intervals(&schedule, at(1, 12, 0), at(1, 14, 0)).is_empty());
+        assert!(intervals(&schedule, at(1, 8, 0), at(1, 10, 0)).is_empty());
+    }
+
+    #[test]
+    fn once_with_invalid_timestamps_is_never_active() {
+        let schedule = once("amanhã", "2024-05-01 12:00:00");
+        assert!(intervals(&schedule, at(1, 0, 0), at(2, 0, 0)).is_empty());
+    }
+
+    #[test]
+    fn recurring_lists_each_occurrence_in_the_range() {
+        assert_eq!(
+            intervals(&nightly(), at(1, 0, 0), at(3, 0, 0)),
+            [(at(1, 2, 0), at(1, 4, 0)), (at(2, 2, 0), at(2, 4, 0))]
+        );
+    }
+
+    #[test]
+    fn recurring_occurrence_open_at_the_range_start_is_listed() {
+        // Started at 02:00, still open when the range starts at 03:00
+        assert_eq!(
+            intervals(&nightly(), at(1, 3, 0), at(1, 12, 0)),
+            [(at(1, 2, 0), at(1, 4, 0))]
+        );
+        // Ended right at the start of the range
+        assert!(intervals(&nightly(), at(1, 4, 0), at(1, 12, 0)).is_empty());
+    }
+
+    #[test]
+    fn recurring_occurrence_starting_at_the_range_end_is_not_listed() {
+        assert!(intervals(&nightly(), at(1, 0, 0), at(1, 2, 0)).is_empty());
+    }
+
+    #[test]
+    fn recurring_window_crossing_midnight() {
+        let schedule = MaintenanceSchedule::Recurring {
+            cron: "30 23 * * *".into(),
+            duration: 3600,
+        };
+        assert_eq!(
+            intervals(&schedule, at(2, 0, 0), at(2, 12, 0)),
+            [(at(1, 23, 30), at(2, 0, 30))]
+        );
+        assert!(is_active(&schedule, at(2, 0, 15)));
+        assert!(!is_active(&schedule, at(2, 0, 30)));
+    }
+
+    #[test]
+    fn recurring_expansion_is_capped() {
+        let schedule = MaintenanceSchedule::Recurring {
+            cron: "* * * * *".into(),
+            duration: 30,
+        };
+        assert_eq!(
+            intervals(&schedule, at(1, 0, 0), at(31, 0, 0)).len(),
+            MAX_INTERVALS
+        );
+    }
+
+    #[test]
+    fn invalid_cron_is_rejected_and_never_active() {
+        assert!(parse_cron("not a cron").is_err());
+        let schedule = MaintenanceSchedule::Recurring {
+            cron: "not a cron".into(),
+            duration: 60,
+        };
+        assert!(intervals(&schedule, at(1, 0, 0), at(2, 0, 0)).is_empty());
+        assert_eq!(next_start(&schedule, at(1, 0, 0)), None);
+    }
+
+    #[test]
+    fn active_from_start_until_just_before_end() {
+        assert!(!is_active(&nightly(), at(1, 1, 59)));
+        assert!(is_active(&nightly(), at(1, 2, 0)));
+        assert!(is_active(&nightly(), at(1, 3, 59)));
+        assert!(!is_active(&nightly(), at(1, 4, 0)));
+    }
+
+    #[test]
+    fn next_start_of_each_schedule() {
+        assert_eq!(next_start(&nightly(), at(1, 3, 0)), Some(at(2, 2, 0)));
+        assert_eq!(next_start(&nightly(), at(1, 1, 0)), Some(at(1, 2, 0)));
+        let schedule = once("2024-05-01 10:00:00", "2024-05-01 12:00:00");
+        assert_eq!(next_start(&schedule, at(1, 9, 0)), Some(at(1, 10, 0)));
+        assert_eq!(next_start(&schedule, at(1, 10, 0)), None);
+    }
+
+    #[test]
+    fn applies_by_id_or_host() {
+        let by_id = window(1, MaintenanceMode::Pause, vec![7], vec![]);
+        let by_host = window(2, MaintenanceMode::Pause, vec![], vec!["api.example.com"]);
+        let covered = monitor(7, "https://API.example.com:8443/health");
+        assert!(applies_to(&by_id.settings, &covered));
+        assert!(applies_to(&by_host.settings, &covered));
+        let other = monitor(8, "https://www.example.com/");
+        assert!(!applies_to(&by_id.settings, &other));
+        assert!(!applies_to(&by_host.settings, &other));
+    }
+
+    #[test]
+    fn pausing_window_wins_over_marking_one() {
+        let windows = [
+            window(1, MaintenanceMode::Mark, vec![7], vec![]),
+            window(2, MaintenanceMode::Pause, vec![7], vec![]),
+            window(3, MaintenanceMode::Pause, vec![8], vec![]),
+        ];
+        let monitor = monitor(7, "https://example.com/");
+        let active = active_window(&windows, &monitor, at(1, 11, 0)).unwrap();
+        assert_eq!(active.id, 2);
+        assert!(active_window(&windows[..1], &monitor, at(1, 11, 0)).is_some());
+        assert!(active_window(&windows, &monitor, at(1, 12, 0)).is_none());
+    }
+}
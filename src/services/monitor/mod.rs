@@ -0,0 +1,10 @@
+pub mod assertions;
+pub mod certificate;
+pub mod expected_status;
+pub mod heartbeat;
+pub mod http_check;
+pub mod maintenance;
+pub mod probe;
+pub mod status;
+pub mod tcp_check;
+pub mod uptime;
@@ -0,0 +1,14 @@
+use crate::{
+    database::models::{CheckResult, MonitorType, UrlData},
+    services::monitor::{heartbeat, http_check::HttpChecker, tcp_check},
+};
+
+/// Runs one check of the monitor with the checker matching its type. Heartbeat monitors are
+/// only checked once their ping is late.
+pub async fn probe(checker: &HttpChecker, url_data: &UrlData) -> CheckResult {
+    match url_data.settings.monitor_type {
+        MonitorType::Http => checker.check(url_data).await,
+        MonitorType::Tcp => tcp_check::check(url_data).await,
+        MonitorType::Heartbeat => heartbeat::late_result(url_data),
+    }
+}
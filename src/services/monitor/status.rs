@@ -0,0 +1,337 @@
+use std::env;
+
+use chrono::{NaiveDateTime, TimeDelta};
+
+use crate::{
+    database::{
+        models::{AlertSource, CheckResult, MonitorStatus, UrlData},
+        storage::SharedStorage,
+        time::now,
+    },
+    services::{
+        alerts::lifecycle,
+        notifications::{notification::Notification, notifier::Notifier},
+    },
+};
+
+/// The confirmed status of the monitor once a check returned `up`, with the count of checks
+/// still waiting for confirmation. A first successful check is trusted right away, any other
+/// change needs the configured number of consecutive agreeing checks.
+pub fn confirm(url_data: &UrlData, up: bool) -> (Option<bool>, u32) {
+    let settings = &url_data.settings;
+    match url_data.up {
+        Some(current) if current == up => (Some(up), 0),
+        None if up => (Some(true), 0),
+        current => {
+            let needed = if up {
+                settings.confirmations_up
+            } else {
+                settings.confirmations_down
+            };
+            let unconfirmed = url_data.unconfirmed_checks + 1;
+            if unconfirmed >= needed {
+                (Some(up), 0)
+            } else {
+                (current, unconfirmed)
+            }
+        }
+    }
+}
+
+/// Number of times consecutive results switch between up and down.
+pub fn state_changes(results: impl IntoIterator<Item = bool>) -> usize {
+    let mut changes = 0;
+    let mut previous = None;
+    for up in results {
+        if previous.is_some_and(|previous| previous != up) {
+            changes += 1;
+        }
+        previous = Some(up);
+    }
+    changes
+}
+
+/// A monitor is flapping once its checks changed status `FLAP_THRESHOLD` times within the
+/// last `FLAP_WINDOW` seconds, and stops once the changes fall to half of that, so it does
+/// not go back and forth around the threshold.
+#[derive(Clone, Copy)]
+pub struct FlapDetection {
+    window: TimeDelta,
+    threshold: usize,
+}
+
+impl FlapDetection {
+    pub fn from_env() -> FlapDetection {
+        let window = env::var("FLAP_WINDOW")
+            .ok()
+            .and_then(|seconds| seconds.parse::<i64>().ok())
+            .filter(|seconds| *seconds > 0)
+            .unwrap_or(3600);
+        let threshold = env::var("FLAP_THRESHOLD")
+            .ok()
+            .and_then(|threshold| threshold.parse::<usize>().ok())
+            .unwrap_or(6);
+        FlapDetection {
+            window: TimeDelta::seconds(window),
+            threshold,
+        }
+    }
+
+    /// Start of the checks to count at `at`, `None` when detection is disabled.
+    pub fn since(&self, at: NaiveDateTime) -> Option<NaiveDateTime> {
+        (self.threshold > 0).then(|| at - self.window)
+    }
+
+    pub fn is_flapping(&self, was_flapping: bool, changes: usize) -> bool {
+        if self.threshold == 0 {
+            return false;
+        }
+        if was_flapping {
+            changes > self.threshold / 2
+        } else {
+            changes >= self.threshold
+        }
+    }
+}
+
+/// Confirms the status change `result` may bring, raising or resolving the monitor's alert
+/// once it is confirmed, and detects flapping. Down and up notifications are held back while
+/// the monitor flaps, a single one tells when it starts and stops flapping.
+pub async fn update_status(
+    storage: &SharedStorage,
+    notifier: &Notifier,
+    flap_detection: FlapDetection,
+    url_data: &UrlData,
+    result: &CheckResult,
+) -> MonitorStatus {
+    let (up, unconfirmed_checks) = confirm(url_data, result.up);
+
+    let mut flapping = false;
+    if let Some(since) = flap_detection.since(now()) {
+        match storage
+            .get_url_checks(url_data.id, Some(since), None, u32::MAX)
+            .await
+        {
+            Ok(checks) => {
+                let changes = state_changes(
+                    checks
+                        .iter()
+                        .rev()
+                        .filter(|check| !check.result.maintenance)
+                        .map(|check| check.result.up)
+                        .chain([result.up]),
+                );
+                flapping = flap_detection.is_flapping(url_data.flapping, changes);
+                if flapping != url_data.flapping {
+                    println!(
+                        "URL {} {} flapping, {} status changes",
+                        url_data.url,
+                        if flapping { "started" } else { "stopped" },
+                        changes
+                    );
+                    notifier.notify(Notification::url_flapping(
+                        url_data, result, flapping, changes,
+                    ));
+                }
+            }
+            Err(e) => {
+                eprintln!("Error loading checks of {}: {}", url_data.url, e);
+                flapping = url_data.flapping;
+            }
+        }
+    }
+
+    let source = AlertSource::Monitor(url_data.id);
+    match (url_data.up, up) {
+        (Some(true) | None, Some(false)) => {
+            lifecycle::raise(
+                storage,
+                notifier,
+                source,
+                url_data.url.clone(),
+                lifecycle::monitor_labels(url_data),
+                (!flapping).then(|| Notification::url_down(url_data, result)),
+            )
+            .await
+        }
+        (Some(false), Some(true)) => {
+            lifecycle::resolve(
+                storage,
+                notifier,
+                source,
+                (!flapping).then(|| Notification::url_up(url_data, result)),
+            )
+            .await
+        }
+        _ => {}
+    }
+
+    MonitorStatus {
+        up,
+        unconfirmed_checks,
+        flapping,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::UrlSettings;
+    use chrono::NaiveDate;
+
+    fn monitor(up: Option<bool>, unconfirmed_checks: u32) -> UrlData {
+        UrlData {
+            id: 1,
+            url: "https://example.com/".into(),
+            settings: UrlSettings {
+                confirmations_down: 3,
+                confirmations_up: 2,
+                ..UrlSettings::default()
+            },
+            status_code: None,
+            up,
+            outcome: None,
+            unconfirmed_checks,
+            flapping: false,
+            certificate: None,
+            heartbeat: None,
+            created_at: "2024-05-01 00:00:00".into(),
+        }
+    }
+
+    /// Feeds the results to `confirm` as the monitor would store them.
+    fn run(mut url_data: UrlData, results: &[bool]) -> Vec<(Option<bool>, u32)> {
+        results
+            .iter()
+            .map(|up| {
+                let status = confirm(&url_data, *up);
+                (url_data.up, url_data.unconfirmed_checks) = status;
+                status
+            })
+            .collect()
+    }
+
+    #[test]
+    fn first_successful_check_is_trusted() {
+        assert_eq!(confirm(&monitor(None, 0), true), (Some(true), 0));
+    }
+
+    #[test]
+    fn first_failures_need_confirmation_too() {
+        let statuses = run(monitor(None, 0), &[false, false, false]);
+        assert_eq!(statuses, [(None, 1), (None, 2), (Some(false), 0)]);
+    }
+
+    #[test]
+    fn down_is_confirmed_after_consecutive_failures() {
+        let statuses = run(monitor(Some(true), 0), &[false, false, false, false]);
+        assert_eq!(
+            statuses,
+            [
+                (Some(true), 1),
+                (Some(true), 2),
+                (Some(false), 0),
+                (Some(false), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn agreeing_check_resets_the_confirmation() {
+        let statuses = run(monitor(Some(true), 0), &[false, false, true, false, false]);
+        assert_eq!(
+            statuses,
+            [
+                (Some(true), 1),
+                (Some(true), 2),
+                (Some(true), 0),
+                (Some(true), 1),
+                (Some(true), 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn up_uses_its_own_confirmation_count() {
+        let statuses = run(monitor(Some(false), 0), &[true, true]);
+        assert_eq!(statuses, [(Some(false), 1), (Some(true), 0)]);
+    }
+
+    #[test]
+    fn single_confirmation_changes_right_away() {
+        let mut url_data = monitor(Some(true), 0);
+        url_data.settings.confirmations_down = 1;
+        assert_eq!(confirm(&url_data, false), (Some(false), 0));
+    }
+
+    #[test]
+    fn state_changes_counts_switches_only() {
+        assert_eq!(state_changes([]), 0);
+        assert_eq!(state_changes([true, true, true]), 0);
+        assert_eq!(state_changes([true, false, false, true]), 2);
+        assert_eq!(state_changes([false, true, false, true, false]), 4);
+    }
+
+    fn detection(threshold: usize) -> FlapDetection {
+        FlapDetection {
+            window: TimeDelta::seconds(3600),
+            threshold,
+        }
+    }
+
+    #[test]
+    fn flapping_starts_at_the_threshold() {
+        let detection = detection(6);
+        assert!(!detection.is_flapping(false, 5));
+        assert!(detection.is_flapping(false, 6));
+        assert!(detection.is_flapping(false, 7));
+    }
+
+    #[test]
+    fn flapping_stops_once_changes_fall_to_half_the_threshold() {
+        let detection = detection(6);
+        assert!(detection.is_flapping(true, 5));
+        assert!(detection.is_flapping(true, 4));
+        assert!(!detection.is_flapping(true, 3));
+        assert!(!detection.is_flapping(true, 0));
+    }
+
+    #[test]
+    fn zero_threshold_disables_detection() {
+        let detection = detection(0);
+        assert!(!detection.is_flapping(false, 100));
+        assert!(!detection.is_flapping(true, 100));
+        assert_eq!(detection.since(now()), None);
+    }
+
+    #[test]
+    fn checks_are_counted_from_the_start_of_the_window() {
+        let at = NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let since = detection(6).since(at).unwrap();
+        assert_eq!(since, at - TimeDelta::hours(1));
+
+        // Checks are loaded from `since` inclusive: the one right on the edge counts, the one
+        // just before it would have reached the threshold
+        let checks = [
+            (since - TimeDelta::seconds(1), true),
+            (since, false),
+            (since + TimeDelta::minutes(10), true),
+            (since + TimeDelta::minutes(20), false),
+            (since + TimeDelta::minutes(30), true),
+            (since + TimeDelta::minutes(40), false),
+        ];
+        let in_window = checks
+            .iter()
+            .filter(|(checked_at, _)| *checked_at >= since)
+            .map(|(_, up)| *up);
+        let changes = state_changes(in_window.chain([true]));
+        assert_eq!(changes, 5);
+        assert!(!detection(6).is_flapping(false, changes));
+        let changes = state_changes(checks.iter().map(|(_, up)| *up).chain([true]));
+        assert_eq!(changes, 6);
+        assert!(detection(6).is_flapping(false, changes));
+    }
+}
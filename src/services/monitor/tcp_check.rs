@@ -0,0 +1,274 @@
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use reqwest::header::HeaderMap;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{lookup_host, TcpStream},
+    time::timeout_at,
+};
+
+use crate::{
+    database::models::{Assertion, CheckOutcome, CheckResult, UrlData},
+    services::monitor::assertions::{self, ResponseData},
+};
+
+/// Stop reading a banner past this size, assertions are evaluated on what was received.
+const MAX_BANNER_SIZE: usize = 64 * 1024;
+
+/// Connects to a `tcp://host:port` monitor. The response time is the connect latency. When
+/// the monitor has a payload it is written right after connecting, and when it has
+/// assertions on the content the received bytes are read until they pass, the peer closes
+/// the connection or the timeout expires. A `body_not_contains` assertion could still fail
+/// on bytes yet to come, so with one the banner is read until the peer closes, the size cap
+/// or the timeout.
+pub async fn check(url_data: &UrlData) -> CheckResult {
+    let settings = &url_data.settings;
+    let started = Instant::now();
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(settings.timeout as u64);
+    let failure = |outcome, error: String| CheckResult {
+        status_code: None,
+        up: false,
+        outcome,
+        response_time_ms: started.elapsed().as_millis() as i64,
+        response_size: None,
+        error: Some(error),
+        failed_assertion: None,
+        maintenance: false,
+    };
+
+    let Some((host, port)) = host_and_port(&url_data.url) else {
+        return failure(
+            CheckOutcome::InvalidUrl,
+            "Expected a tcp://host:port URL".into(),
+        );
+    };
+
+    let addresses = match timeout_at(deadline, lookup_host((host.as_str(), port))).await {
+        Ok(Ok(addresses)) => addresses.collect::<Vec<_>>(),
+        Ok(Err(err)) => return failure(CheckOutcome::Dns, err.to_string()),
+        Err(_) => return failure(CheckOutcome::Timeout, "Timed out".into()),
+    };
+    let mut stream = match timeout_at(deadline, TcpStream::connect(addresses.as_slice())).await {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(err)) => return failure(io_outcome(&err), err.to_string()),
+        Err(_) => return failure(CheckOutcome::Timeout, "Timed out".into()),
+    };
+    let response_time_ms = started.elapsed().as_millis() as i64;
+
+    if let Some(payload) = &settings.body {
+        match timeout_at(deadline, stream.write_all(payload.as_bytes())).await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => return failure(io_outcome(&err), err.to_string()),
+            Err(_) => return failure(CheckOutcome::Timeout, "Timed out".into()),
+        }
+    }
+
+    let headers = HeaderMap::new();
+    let mut banner = Vec::new();
+    let mut error = None;
+    let reads_content = settings
+        .assertions
+        .iter()
+        .any(|assertion| !matches!(assertion, Assertion::MaxResponseTime(_)));
+    let reads_to_end = settings
+        .assertions
+        .iter()
+        .any(|assertion| matches!(assertion, Assertion::BodyNotContains(_)));
+    let mut failed_assertion = first_failure(url_data, &headers, &banner, response_time_ms);
+    while reads_content
+        && (reads_to_end || failed_assertion.is_some())
+        && banner.len() < MAX_BANNER_SIZE
+    {
+        let mut chunk = [0; 4096];
+        match timeout_at(deadline, stream.read(&mut chunk)).await {
+            Ok(Ok(0)) | Err(_) => break,
+            Ok(Ok(read)) => banner.extend_from_slice(&chunk[..read]),
+            Ok(Err(err)) => {
+                error = Some(err);
+                break;
+            }
+        }
+        failed_assertion = first_failure(url_data, &headers, &banner, response_time_ms);
+    }
+
+    let outcome = match (&error, &failed_assertion) {
+        (Some(err), _) => io_outcome(err),
+        (None, Some(_)) => CheckOutcome::AssertionFailed,
+        (None, None) => CheckOutcome::Ok,
+    };
+    println!(
+        "TCP: {}, {}, {} ms",
+        url_data.url,
+        outcome.as_str(),
+        response_time_ms
+    );
+    CheckResult {
+        status_code: None,
+        up: outcome == CheckOutcome::Ok,
+        outcome,
+        response_time_ms,
+        response_size: reads_content.then_some(banner.len() as i64),
+        error: error.map(|err| format!("Error reading banner: {}", err)),
+        failed_assertion,
+        maintenance: false,
+    }
+}
+
+/// Host and port of a `tcp://host:port` URL, the port being mandatory.
+pub fn host_and_port(url: &str) -> Option<(String, u16)> {
+    let url = url::Url::parse(url).ok()?;
+    if url.scheme() != "tcp" {
+        return None;
+    }
+    let host = url
+        .host_str()?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+    Some((host, url.port()?))
+}
+
+fn first_failure(
+    url_data: &UrlData,
+    headers: &HeaderMap,
+    banner: &[u8],
+    response_time_ms: i64,
+) -> Option<String> {
+    assertions::first_failure(
+        &url_data.settings.assertions,
+        &ResponseData {
+            headers,
+            body: banner,
+            response_time_ms,
+        },
+    )
+}
+
+fn io_outcome(err: &io::Error) -> CheckOutcome {
+    match err.kind() {
+        io::ErrorKind::TimedOut => CheckOutcome::Timeout,
+        _ => CheckOutcome::Connect,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::UrlSettings;
+    use tokio::net::TcpListener;
+
+    fn monitor(port: u16, body: Option<&str>, assertions: Vec<Assertion>) -> UrlData {
+        UrlData {
+            id: 1,
+            url: format!("tcp://127.0.0.1:{}", port),
+            settings: UrlSettings {
+                timeout: 2,
+                body: body.map(String::from),
+                assertions,
+                ..UrlSettings::default()
+            },
+            status_code: None,
+            up: None,
+            outcome: None,
+            unconfirmed_checks: 0,
+            flapping: false,
+            certificate: None,
+            heartbeat: None,
+            created_at: "2024-05-01 00:00:00".into(),
+        }
+    }
+
+    /// Accepts one connection, reads what the check sends when `reads` is set, then writes
+    /// `replies` in order and closes. Returns the port and what was received.
+    async fn server(
+        reads: bool,
+        replies: &'static [&'static [u8]],
+    ) -> (u16, tokio::task::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut received = vec![0; 1024];
+            let read = if reads {
+                stream.read(&mut received).await.unwrap()
+            } else {
+                0
+            };
+            received.truncate(read);
+            for reply in replies {
+                stream.write_all(reply).await.unwrap();
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            received
+        });
+        (port, handle)
+    }
+
+    #[tokio::test]
+    async fn connect_records_the_latency() {
+        let (port, handle) = server(false, &[]).await;
+        let result = check(&monitor(port, None, Vec::new())).await;
+        handle.await.unwrap();
+        assert!(result.up);
+        assert_eq!(result.outcome, CheckOutcome::Ok);
+        assert!(result.response_time_ms >= 0 && result.response_time_ms < 2000);
+        // Nothing asserted on the content, so nothing is read
+        assert_eq!(result.response_size, None);
+        assert_eq!(result.error, None);
+    }
+
+    #[tokio::test]
+    async fn payload_is_sent_and_the_banner_matched() {
+        let (port, handle) = server(true, &[b"+PO", b"NG\r\n"]).await;
+        let url_data = monitor(
+            port,
+            Some("PING\r\n"),
+            vec![Assertion::BodyContains("+PONG".into())],
+        );
+        let result = check(&url_data).await;
+        assert_eq!(handle.await.unwrap(), b"PING\r\n");
+        assert!(result.up);
+        assert_eq!(result.outcome, CheckOutcome::Ok);
+        assert_eq!(result.response_size, Some(7));
+        assert_eq!(result.failed_assertion, None);
+    }
+
+    #[tokio::test]
+    async fn banner_mismatch_fails_the_assertion() {
+        let (port, handle) = server(false, &[b"220 smtp.example ESMTP\r\n"]).await;
+        let url_data = monitor(port, None, vec![Assertion::BodyContains("SSH-".into())]);
+        let result = check(&url_data).await;
+        handle.await.unwrap();
+        assert!(!result.up);
+        assert_eq!(result.outcome, CheckOutcome::AssertionFailed);
+        assert!(result
+            .failed_assertion
+            .unwrap()
+            .starts_with("body_contains"));
+    }
+
+    #[tokio::test]
+    async fn negative_assertions_read_the_whole_banner() {
+        let (port, handle) = server(false, &[b"OK\r\n", b"ERROR\r\n"]).await;
+        let url_data = monitor(port, None, vec![Assertion::BodyNotContains("ERROR".into())]);
+        let result = check(&url_data).await;
+        handle.await.unwrap();
+        assert!(!result.up);
+        assert_eq!(result.outcome, CheckOutcome::AssertionFailed);
+        assert_eq!(result.response_size, Some(11));
+    }
+
+    #[tokio::test]
+    async fn refused_port_is_a_connect_failure() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        let result = check(&monitor(port, None, Vec::new())).await;
+        assert!(!result.up);
+        assert_eq!(result.outcome, CheckOutcome::Connect);
+        assert!(result.error.is_some());
+    }
+}
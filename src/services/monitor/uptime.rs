@@ -0,0 +1,233 @@
+use chrono::{NaiveDateTime, TimeDelta};
+
+/// Availability of a monitor over a time range, computed from its checks.
+pub struct UptimeReport {
+    pub checks: usize,
+    /// Time between the first check in the range and its end.
+    pub monitored: TimeDelta,
+    pub downtime: TimeDelta,
+    /// Up to down transitions, a range starting down counts as one.
+    pub incidents: usize,
+    /// Mean duration of the incidents that recovered within the range.
+    pub mean_time_to_recovery: Option<TimeDelta>,
+}
+
+impl UptimeReport {
+    /// Percentage of the monitored time spent up, `None` without any check.
+    pub fn availability(&self) -> Option<f64> {
+        let monitored = self.monitored.num_milliseconds();
+        if self.checks == 0 || monitored <= 0 {
+            return None;
+        }
+        let up = monitored - self.downtime.num_milliseconds();
+        Some(up as f64 * 100.0 / monitored as f64)
+    }
+}
+
+/// Sorts and merges overlapping intervals.
+fn merge(intervals: &[(NaiveDateTime, NaiveDateTime)]) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let mut sorted = intervals.to_vec();
+    sorted.sort();
+    let mut merged: Vec<(NaiveDateTime, NaiveDateTime)> = Vec::new();
+    for (start, end) in sorted {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Length of `[from, to)` outside of the merged `excluded` intervals.
+fn counted(
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    excluded: &[(NaiveDateTime, NaiveDateTime)],
+) -> TimeDelta {
+    let mut length = (to - from).max(TimeDelta::zero());
+    for (start, end) in excluded {
+        let overlap = (*end).min(to) - (*start).max(from);
+        if overlap > TimeDelta::zero() {
+            length -= overlap;
+        }
+    }
+    length
+}
+
+/// Each check's state is assumed to hold until the next check, the last one until `end`.
+/// `checks` must be sorted by time, oldest first. Time inside `excluded` (maintenance) is
+/// neither monitored nor down, and the checks taken in it are ignored.
+pub fn uptime_report(
+    checks: &[(NaiveDateTime, bool)],
+    end: NaiveDateTime,
+    excluded: &[(NaiveDateTime, NaiveDateTime)],
+) -> UptimeReport {
+    let excluded = merge(excluded);
+    let checks: Vec<(NaiveDateTime, bool)> = checks
+        .iter()
+        .filter(|(checked_at, _)| {
+            !excluded
+                .iter()
+                .any(|(start, end)| start <= checked_at && checked_at < end)
+        })
+        .copied()
+        .collect();
+    let mut downtime = TimeDelta::zero();
+    let mut incidents = 0;
+    let mut recoveries = Vec::new();
+    let mut down_since: Option<NaiveDateTime> = None;
+
+    for (index, (checked_at, up)) in checks.iter().enumerate() {
+        let next = checks
+            .get(index + 1)
+            .map(|(next, _)| *next)
+            .unwrap_or(end)
+            .min(end);
+        if !up {
+            downtime += counted(*checked_at, next, &excluded);
+        }
+
+        match (down_since, up) {
+            (None, false) => {
+                incidents += 1;
+                down_since = Some(*checked_at);
+            }
+            (Some(since), true) => {
+                recoveries.push(counted(since, *checked_at, &excluded));
+                down_since = None;
+            }
+            _ => {}
+        }
+    }
+
+    let mean_time_to_recovery = if recoveries.is_empty() {
+        None
+    } else {
+        let total: TimeDelta = recoveries.iter().sum();
+        Some(total / recoveries.len() as i32)
+    };
+
+    UptimeReport {
+        checks: checks.len(),
+        monitored: checks
+            .first()
+            .map(|(first, _)| counted(*first, end, &excluded))
+            .unwrap_or(TimeDelta::zero()),
+        downtime,
+        incidents,
+        mean_time_to_recovery,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(minutes: i64) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            + TimeDelta::minutes(minutes)
+    }
+
+    #[test]
+    fn report_without_checks_has_no_availability() {
+        let report = uptime_report(&[], at(60), &[]);
+        assert_eq!(report.checks, 0);
+        assert_eq!(report.monitored, TimeDelta::zero());
+        assert_eq!(report.availability(), None);
+        assert_eq!(report.mean_time_to_recovery, None);
+    }
+
+    #[test]
+    fn state_holds_through_gaps_until_the_next_check() {
+        // Down at 10, nothing checked until 40: the whole gap counts as downtime
+        let checks = [(at(0), true), (at(10), false), (at(40), true)];
+        let report = uptime_report(&checks, at(100), &[]);
+        assert_eq!(report.monitored, TimeDelta::minutes(100));
+        assert_eq!(report.downtime, TimeDelta::minutes(30));
+        assert_eq!(report.incidents, 1);
+        assert_eq!(report.mean_time_to_recovery, Some(TimeDelta::minutes(30)));
+        assert_eq!(report.availability(), Some(70.0));
+    }
+
+    #[test]
+    fn last_check_holds_until_the_end_of_the_range() {
+        let checks = [(at(0), true), (at(50), false)];
+        let report = uptime_report(&checks, at(100), &[]);
+        assert_eq!(report.downtime, TimeDelta::minutes(50));
+        assert_eq!(report.incidents, 1);
+        // Still down at the end, so nothing recovered yet
+        assert_eq!(report.mean_time_to_recovery, None);
+    }
+
+    #[test]
+    fn monitored_time_starts_at_the_first_check() {
+        let checks = [
+            (at(60), false),
+            (at(70), true),
+            (at(80), false),
+            (at(90), true),
+        ];
+        let report = uptime_report(&checks, at(100), &[]);
+        assert_eq!(report.monitored, TimeDelta::minutes(40));
+        // A range starting down counts as an incident
+        assert_eq!(report.incidents, 2);
+        assert_eq!(report.downtime, TimeDelta::minutes(20));
+        assert_eq!(report.mean_time_to_recovery, Some(TimeDelta::minutes(10)));
+        assert_eq!(report.availability(), Some(50.0));
+    }
+
+    #[test]
+    fn checks_after_the_end_are_not_counted() {
+        let checks = [(at(0), true), (at(100), false)];
+        let report = uptime_report(&checks, at(100), &[]);
+        assert_eq!(report.downtime, TimeDelta::zero());
+        assert_eq!(report.availability(), Some(100.0));
+    }
+
+    #[test]
+    fn excluded_time_is_neither_monitored_nor_down() {
+        let checks = [(at(0), false), (at(60), true)];
+        let report = uptime_report(&checks, at(100), &[(at(20), at(40))]);
+        assert_eq!(report.monitored, TimeDelta::minutes(80));
+        assert_eq!(report.downtime, TimeDelta::minutes(40));
+        assert_eq!(report.mean_time_to_recovery, Some(TimeDelta::minutes(40)));
+        assert_eq!(report.availability(), Some(50.0));
+    }
+
+    #[test]
+    fn checks_on_the_window_start_are_ignored_and_on_its_end_kept() {
+        let checks = [
+            (at(0), true),
+            (at(20), false),
+            (at(40), false),
+            (at(50), true),
+        ];
+        let report = uptime_report(&checks, at(100), &[(at(20), at(40))]);
+        assert_eq!(report.checks, 3);
+        assert_eq!(report.downtime, TimeDelta::minutes(10));
+        assert_eq!(report.incidents, 1);
+    }
+
+    #[test]
+    fn overlapping_windows_are_excluded_once() {
+        let checks = [(at(0), false)];
+        let excluded = [(at(10), at(30)), (at(20), at(40)), (at(40), at(50))];
+        let report = uptime_report(&checks, at(100), &excluded);
+        assert_eq!(report.monitored, TimeDelta::minutes(60));
+        assert_eq!(report.downtime, TimeDelta::minutes(60));
+        assert_eq!(report.availability(), Some(0.0));
+    }
+
+    #[test]
+    fn windows_outside_the_range_change_nothing() {
+        let checks = [(at(10), true), (at(30), false), (at(40), true)];
+        let excluded = [(at(-60), at(5)), (at(100), at(200))];
+        let report = uptime_report(&checks, at(100), &excluded);
+        assert_eq!(report.monitored, TimeDelta::minutes(90));
+        assert_eq!(report.downtime, TimeDelta::minutes(10));
+    }
+}
@@ -0,0 +1,479 @@
+use reqwest::Client;
+
+use crate::{
+    database::{
+        models::{ChatWebhookConfig, EventKind, TelegramConfig},
+        time::{format_duration, parse_timestamp},
+    },
+    services::notifications::{
+        email::{escape_html, field_text},
+        notification::Notification,
+        notifier::SendError,
+        webhook::{deliver, WEBHOOK_TIMEOUT},
+    },
+};
+
+/// How a message is highlighted: down and firing are bad news, up and resolved good news.
+#[derive(Clone, Copy)]
+enum Tone {
+    Bad,
+    Good,
+    Warning,
+    Info,
+}
+
+impl Tone {
+    fn of(notification: &Notification) -> Tone {
+        match notification.event {
+            EventKind::UrlDown | EventKind::AlertFiring => Tone::Bad,
+            EventKind::UrlUp | EventKind::AlertResolved => Tone::Good,
+            EventKind::UrlFlapping => match notification.fields.get("flapping") {
+                Some(serde_json::Value::Bool(false)) => Tone::Good,
+                _ => Tone::Warning,
+            },
+            EventKind::CertificateExpiring => match (
+                notification.fields.get("expiring"),
+                notification
+                    .fields
+                    .get("days_until_expiry")
+                    .and_then(|days| days.as_i64()),
+            ) {
+                (Some(serde_json::Value::Bool(false)), _) => Tone::Good,
+                (_, Some(days)) if days < 0 => Tone::Bad,
+                _ => Tone::Warning,
+            },
+            EventKind::Test => Tone::Info,
+        }
+    }
+
+    fn color(&self) -> u32 {
+        match self {
+            Tone::Bad => 0xD00000,
+            Tone::Good => 0x2EB886,
+            Tone::Warning => 0xF2A900,
+            Tone::Info => 0x439FE0,
+        }
+    }
+
+    /// Telegram has no colors, the message starts with a colored circle instead.
+    fn emoji(&self) -> &'static str {
+        match self {
+            Tone::Bad => "🔴",
+            Tone::Good => "🟢",
+            Tone::Warning => "🟠",
+            Tone::Info => "🔵",
+        }
+    }
+
+    /// Adaptive card text colors.
+    fn card_color(&self) -> &'static str {
+        match self {
+            Tone::Bad => "Attention",
+            Tone::Good => "Good",
+            Tone::Warning => "Warning",
+            Tone::Info => "Accent",
+        }
+    }
+}
+
+/// The event fields worth showing, labelled and in display order.
+fn facts(notification: &Notification) -> Vec<(&'static str, String)> {
+    let field = |name: &str| {
+        notification
+            .fields
+            .get(name)
+            .filter(|value| !value.is_null())
+    };
+    let mut facts = Vec::new();
+    if let Some(url) = field("url") {
+        facts.push(("URL", field_text(url)));
+    }
+    match (field("status_code"), field("outcome")) {
+        (Some(status_code), Some(outcome)) => facts.push((
+            "Status",
+            format!("{} ({})", status_code, field_text(outcome)),
+        )),
+        (None, Some(outcome)) => facts.push(("Status", field_text(outcome))),
+        _ => {}
+    }
+    if let Some(response_time) = field("response_time_ms") {
+        facts.push(("Latência", format!("{} ms", response_time)));
+    }
+    if let Some(rule) = field("rule") {
+        facts.push(("Regra", field_text(rule)));
+    }
+    if let Some(expression) = field("expression") {
+        facts.push(("Condição", field_text(expression)));
+    }
+    if let Some(value) = field("value").and_then(|value| value.as_f64()) {
+        facts.push(("Valor", format!("{:.1}", value)));
+    }
+    if let Some(changes) = field("state_changes") {
+        facts.push(("Mudanças de status", field_text(changes)));
+    }
+    if let Some(not_after) = field("not_after") {
+        facts.push(("Validade", field_text(not_after)));
+    }
+    if let Some(issuer) = field("certificate_issuer") {
+        facts.push(("Emissor", field_text(issuer)));
+    }
+    if let Some(duration) = field("duration_seconds").and_then(|value| value.as_i64()) {
+        facts.push(("Duração", format_duration(duration)));
+    }
+    if let Some(detail) = field("failed_assertion").or(field("error")) {
+        facts.push(("Detalhe", field_text(detail)));
+    }
+    facts
+}
+
+/// Short facts are laid out side by side where the platform allows it.
+fn is_short(label: &str) -> bool {
+    !matches!(label, "URL" | "Detalhe" | "Condição" | "Emissor")
+}
+
+fn timestamp(notification: &Notification) -> Option<chrono::DateTime<chrono::Utc>> {
+    notification
+        .fields
+        .get("timestamp")
+        .and_then(|timestamp| timestamp.as_str())
+        .and_then(parse_timestamp)
+        .map(|timestamp| timestamp.and_utc())
+}
+
+/// Legacy attachment, still the only way to color a Slack incoming webhook message.
+pub fn slack_body(notification: &Notification) -> serde_json::Value {
+    let facts = facts(notification);
+    let mut attachment = serde_json::json!({
+        "color": format!("#{:06X}", Tone::of(notification).color()),
+        "fallback": notification.message,
+        "fields": facts
+            .iter()
+            .map(|(label, value)| serde_json::json!({
+                "title": label,
+                "value": value,
+                "short": is_short(label),
+            }))
+            .collect::<Vec<serde_json::Value>>(),
+        "ts": timestamp(notification).map(|timestamp| timestamp.timestamp()),
+    });
+    if facts.is_empty() && notification.message != notification.subject {
+        attachment["text"] = notification.message.clone().into();
+    }
+    serde_json::json!({
+        "text": notification.subject,
+        "attachments": [attachment],
+    })
+}
+
+pub fn discord_body(notification: &Notification) -> serde_json::Value {
+    let facts = facts(notification);
+    let mut embed = serde_json::json!({
+        "title": notification.subject,
+        "color": Tone::of(notification).color(),
+        "fields": facts
+            .iter()
+            .map(|(label, value)| serde_json::json!({
+                "name": label,
+                "value": value,
+                "inline": is_short(label),
+            }))
+            .collect::<Vec<serde_json::Value>>(),
+        "timestamp": timestamp(notification).map(|timestamp| timestamp.to_rfc3339()),
+    });
+    if facts.is_empty() && notification.message != notification.subject {
+        embed["description"] = notification.message.clone().into();
+    }
+    serde_json::json!({ "embeds": [embed] })
+}
+
+/// Adaptive card, accepted by Teams workflows and incoming webhooks.
+pub fn teams_body(notification: &Notification) -> serde_json::Value {
+    let facts = facts(notification);
+    let mut body = vec![serde_json::json!({
+        "type": "TextBlock",
+        "text": notification.subject,
+        "weight": "Bolder",
+        "size": "Medium",
+        "color": Tone::of(notification).card_color(),
+        "wrap": true,
+    })];
+    if !facts.is_empty() {
+        body.push(serde_json::json!({
+            "type": "FactSet",
+            "facts": facts
+                .iter()
+                .map(|(label, value)| serde_json::json!({ "title": label, "value": value }))
+                .collect::<Vec<serde_json::Value>>(),
+        }));
+    } else if notification.message != notification.subject {
+        body.push(serde_json::json!({
+            "type": "TextBlock",
+            "text": notification.message,
+            "wrap": true,
+        }));
+    }
+    serde_json::json!({
+        "type": "message",
+        "attachments": [{
+            "contentType": "application/vnd.microsoft.card.adaptive",
+            "content": {
+                "type": "AdaptiveCard",
+                "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                "version": "1.4",
+                "body": body,
+            },
+        }],
+    })
+}
+
+pub fn telegram_body(config: &TelegramConfig, notification: &Notification) -> serde_json::Value {
+    let facts = facts(notification);
+    let mut text = format!(
+        "{} <b>{}</b>",
+        Tone::of(notification).emoji(),
+        escape_html(&notification.subject)
+    );
+    if facts.is_empty() && notification.message != notification.subject {
+        text.push_str(&format!("\n{}", escape_html(&notification.message)));
+    }
+    for (label, value) in facts {
+        text.push_str(&format!("\n<b>{}:</b> {}", label, escape_html(&value)));
+    }
+    serde_json::json!({
+        "chat_id": config.chat_id,
+        "text": text,
+        "parse_mode": "HTML",
+        "disable_web_page_preview": true,
+    })
+}
+
+pub async fn send_slack(
+    client: &Client,
+    config: &ChatWebhookConfig,
+    notification: &Notification,
+) -> Result<Option<i32>, SendError> {
+    post(client, &config.url, &slack_body(notification)).await
+}
+
+pub async fn send_discord(
+    client: &Client,
+    config: &ChatWebhookConfig,
+    notification: &Notification,
+) -> Result<Option<i32>, SendError> {
+    post(client, &config.url, &discord_body(notification)).await
+}
+
+pub async fn send_teams(
+    client: &Client,
+    config: &ChatWebhookConfig,
+    notification: &Notification,
+) -> Result<Option<i32>, SendError> {
+    post(client, &config.url, &teams_body(notification)).await
+}
+
+/// Calls the bot API `sendMessage` method.
+pub async fn send_telegram(
+    client: &Client,
+    config: &TelegramConfig,
+    notification: &Notification,
+) -> Result<Option<i32>, SendError> {
+    let url = format!(
+        "{}/bot{}/sendMessage",
+        config.api_url.trim_end_matches('/'),
+        config.bot_token
+    );
+    post(client, &url, &telegram_body(config, notification)).await
+}
+
+async fn post(
+    client: &Client,
+    url: &str,
+    body: &serde_json::Value,
+) -> Result<Option<i32>, SendError> {
+    deliver(client.post(url).timeout(WEBHOOK_TIMEOUT).json(body)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::models::{CheckOutcome, CheckResult, UrlData, UrlSettings},
+        test_support::HttpStandIn,
+    };
+
+    fn url_data() -> UrlData {
+        UrlData {
+            id: 7,
+            url: "https://example.com/health".into(),
+            settings: UrlSettings::default(),
+            status_code: None,
+            up: None,
+            outcome: None,
+            unconfirmed_checks: 0,
+            flapping: false,
+            certificate: None,
+            heartbeat: None,
+            created_at: "2026-01-01 00:00:00".into(),
+        }
+    }
+
+    fn result(up: bool) -> CheckResult {
+        CheckResult {
+            status_code: Some(if up { 200 } else { 503 }),
+            up,
+            outcome: if up {
+                CheckOutcome::Ok
+            } else {
+                CheckOutcome::HttpError
+            },
+            response_time_ms: 120,
+            response_size: None,
+            error: None,
+            failed_assertion: None,
+            maintenance: false,
+        }
+    }
+
+    fn down() -> Notification {
+        Notification::url_down(&url_data(), &result(false))
+    }
+
+    /// Recovery after an outage of 1h 2m 5s, like `Notifier::notify_alert` sends it.
+    fn up() -> Notification {
+        let mut notification = Notification::url_up(&url_data(), &result(true));
+        notification
+            .fields
+            .insert("duration_seconds".into(), 3725.into());
+        notification
+    }
+
+    fn chat_config(stand_in: &HttpStandIn) -> ChatWebhookConfig {
+        ChatWebhookConfig {
+            url: stand_in.url("/chat"),
+        }
+    }
+
+    #[tokio::test]
+    async fn slack_posts_colored_attachment() {
+        let stand_in = HttpStandIn::start(&[]).await;
+        let config = chat_config(&stand_in);
+
+        send_slack(&Client::new(), &config, &down()).await.ok();
+        send_slack(&Client::new(), &config, &up()).await.ok();
+
+        let requests = stand_in.take_requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/chat");
+        let down_body = requests[0].json();
+        assert_eq!(
+            down_body["text"],
+            "URL fora do ar: https://example.com/health"
+        );
+        let attachment = &down_body["attachments"][0];
+        assert_eq!(attachment["color"], "#D00000");
+        assert_eq!(
+            attachment["fields"][0],
+            serde_json::json!({"title": "URL", "value": "https://example.com/health", "short": false})
+        );
+        assert_eq!(
+            attachment["fields"][1],
+            serde_json::json!({"title": "Status", "value": "503 (http_error)", "short": true})
+        );
+        assert!(attachment["ts"].is_i64());
+
+        let up_attachment = &requests[1].json()["attachments"][0];
+        assert_eq!(up_attachment["color"], "#2EB886");
+        assert!(up_attachment["fields"].as_array().unwrap().contains(
+            &serde_json::json!({"title": "Duração", "value": "1h 2m 5s", "short": true})
+        ));
+    }
+
+    #[tokio::test]
+    async fn discord_posts_embed() {
+        let stand_in = HttpStandIn::start(&[]).await;
+        let config = chat_config(&stand_in);
+
+        send_discord(&Client::new(), &config, &down()).await.ok();
+        send_discord(&Client::new(), &config, &up()).await.ok();
+
+        let requests = stand_in.take_requests();
+        let embed = &requests[0].json()["embeds"][0];
+        assert_eq!(embed["title"], "URL fora do ar: https://example.com/health");
+        assert_eq!(embed["color"], 0xD00000);
+        assert_eq!(
+            embed["fields"][2],
+            serde_json::json!({"name": "Latência", "value": "120 ms", "inline": true})
+        );
+        assert!(embed["timestamp"].as_str().unwrap().ends_with("+00:00"));
+
+        let up_embed = &requests[1].json()["embeds"][0];
+        assert_eq!(up_embed["color"], 0x2EB886);
+        assert!(up_embed["fields"].as_array().unwrap().contains(
+            &serde_json::json!({"name": "Duração", "value": "1h 2m 5s", "inline": true})
+        ));
+    }
+
+    #[tokio::test]
+    async fn teams_posts_adaptive_card() {
+        let stand_in = HttpStandIn::start(&[]).await;
+        let config = chat_config(&stand_in);
+
+        send_teams(&Client::new(), &config, &down()).await.ok();
+        send_teams(&Client::new(), &config, &up()).await.ok();
+
+        let requests = stand_in.take_requests();
+        let body = requests[0].json();
+        assert_eq!(body["type"], "message");
+        let attachment = &body["attachments"][0];
+        assert_eq!(
+            attachment["contentType"],
+            "application/vnd.microsoft.card.adaptive"
+        );
+        assert_eq!(attachment["content"]["type"], "AdaptiveCard");
+        let card = &attachment["content"]["body"];
+        assert_eq!(card[0]["type"], "TextBlock");
+        assert_eq!(card[0]["color"], "Attention");
+        assert_eq!(card[1]["type"], "FactSet");
+        assert_eq!(
+            card[1]["facts"][0],
+            serde_json::json!({"title": "URL", "value": "https://example.com/health"})
+        );
+
+        let up_card = &requests[1].json()["attachments"][0]["content"]["body"];
+        assert_eq!(up_card[0]["color"], "Good");
+        assert!(up_card[1]["facts"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!({"title": "Duração", "value": "1h 2m 5s"})));
+    }
+
+    #[tokio::test]
+    async fn telegram_calls_send_message() {
+        let stand_in = HttpStandIn::start(&[]).await;
+        let config = TelegramConfig {
+            bot_token: "123456:ABC-DEF".into(),
+            chat_id: "-1001234567890".into(),
+            api_url: stand_in.url("/"),
+        };
+
+        let sent = send_telegram(&Client::new(), &config, &down()).await;
+        send_telegram(&Client::new(), &config, &up()).await.ok();
+
+        assert_eq!(sent.ok(), Some(Some(200)));
+        let requests = stand_in.take_requests();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/bot123456:ABC-DEF/sendMessage");
+        let body = requests[0].json();
+        assert_eq!(body["chat_id"], "-1001234567890");
+        assert_eq!(body["parse_mode"], "HTML");
+        assert_eq!(body["disable_web_page_preview"], true);
+        let text = body["text"].as_str().unwrap();
+        assert!(text.starts_with("🔴 <b>URL fora do ar: https://example.com/health</b>"));
+        assert!(text.contains("\n<b>Status:</b> 503 (http_error)"));
+
+        let up_text = requests[1].json()["text"].as_str().unwrap().to_string();
+        assert!(up_text.starts_with("🟢 "));
+        assert!(up_text.contains("\n<b>Duração:</b> 1h 2m 5s"));
+    }
+}
@@ -0,0 +1,274 @@
+use std::time::Duration;
+
+use lettre::{
+    message::{Mailbox, MultiPart},
+    transport::smtp::{authentication::Credentials, AsyncSmtpTransport},
+    AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::{
+    database::models::{EmailConfig, SmtpSecurity},
+    services::notifications::{notification::Notification, notifier::SendError},
+};
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn error(message: String) -> SendError {
+    SendError {
+        status_code: None,
+        message,
+    }
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn field_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// The message followed by one `field: value` line per event field that has a value.
+fn plain_body(notification: &Notification) -> String {
+    let mut body = format!("{}\n\n", notification.message);
+    for (name, value) in &notification.fields {
+        if !value.is_null() {
+            body.push_str(&format!("{}: {}\n", name, field_text(value)));
+        }
+    }
+    body
+}
+
+/// Same content as `plain_body`, with the fields in a table.
+fn html_body(notification: &Notification) -> String {
+    let mut rows = String::new();
+    for (name, value) in &notification.fields {
+        if !value.is_null() {
+            rows.push_str(&format!(
+                "<tr><th align=\"left\">{}</th><td>{}</td></tr>",
+                escape_html(name),
+                escape_html(&field_text(value))
+            ));
+        }
+    }
+    format!(
+        "<html><body><h2>{}</h2><p>{}</p><table cellpadding=\"4\">{}</table></body></html>",
+        escape_html(&notification.subject),
+        escape_html(&notification.message),
+        rows
+    )
+}
+
+pub fn parse_mailbox(address: &str) -> Result<Mailbox, String> {
+    address
+        .parse::<Mailbox>()
+        .map_err(|_| format!("Endereço de email inválido: {}", address))
+}
+
+fn message(config: &EmailConfig, notification: &Notification) -> Result<Message, String> {
+    let mut builder = Message::builder()
+        .from(parse_mailbox(&config.from)?)
+        .subject(&notification.subject);
+    for address in &config.to {
+        builder = builder.to(parse_mailbox(address)?);
+    }
+    builder
+        .multipart(MultiPart::alternative_plain_html(
+            plain_body(notification),
+            html_body(notification),
+        ))
+        .map_err(|e| e.to_string())
+}
+
+fn transport(config: &EmailConfig) -> Result<AsyncSmtpTransport<Tokio1Executor>, String> {
+    let builder = match config.security {
+        SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+        SmtpSecurity::StartTls => {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+                .map_err(|e| e.to_string())?
+        }
+        SmtpSecurity::Tls => {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host).map_err(|e| e.to_string())?
+        }
+    };
+    let mut builder = builder.port(config.port).timeout(Some(SMTP_TIMEOUT));
+    if let Some(username) = &config.username {
+        builder = builder.credentials(Credentials::new(
+            username.clone(),
+            config.password.clone().unwrap_or_default(),
+        ));
+    }
+    Ok(builder.build())
+}
+
+/// Sends the notification as a plaintext and HTML email to every recipient, `Ok` holds the
+/// server's reply code.
+pub async fn send(
+    config: &EmailConfig,
+    notification: &Notification,
+) -> Result<Option<i32>, SendError> {
+    let message = message(config, notification).map_err(error)?;
+    let transport = transport(config).map_err(error)?;
+    match transport.send(message).await {
+        Ok(response) => Ok(Some(u16::from(response.code()) as i32)),
+        Err(e) => Err(SendError {
+            status_code: e.status().map(|code| u16::from(code) as i32),
+            message: e.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::models::{
+            AlertCondition, AlertRule, AlertState, CheckOutcome, CheckResult, UrlData, UrlSettings,
+        },
+        services::alerts::expression::parse_condition,
+        test_support::SmtpStandIn,
+    };
+
+    fn config(port: u16) -> EmailConfig {
+        EmailConfig {
+            host: "127.0.0.1".into(),
+            port,
+            security: SmtpSecurity::None,
+            username: None,
+            password: None,
+            from: "Monitor <monitor@example.com>".into(),
+            to: vec![
+                "ops@example.com".into(),
+                "Plantao <oncall@example.com>".into(),
+            ],
+        }
+    }
+
+    fn url_down() -> Notification {
+        let url_data = UrlData {
+            id: 7,
+            url: "https://example.com/health?a=1&b=<2>".into(),
+            settings: UrlSettings::default(),
+            status_code: None,
+            up: None,
+            outcome: None,
+            unconfirmed_checks: 0,
+            flapping: false,
+            certificate: None,
+            heartbeat: None,
+            created_at: "2026-01-01 00:00:00".into(),
+        };
+        let result = CheckResult {
+            status_code: Some(503),
+            up: false,
+            outcome: CheckOutcome::HttpError,
+            response_time_ms: 120,
+            response_size: None,
+            error: None,
+            failed_assertion: None,
+            maintenance: false,
+        };
+        Notification::url_down(&url_data, &result)
+    }
+
+    fn alert_firing() -> Notification {
+        let condition: AlertCondition = parse_condition("total_cpu_usage > 90 for 5m").unwrap();
+        let rule = AlertRule {
+            id: 3,
+            name: "cpu alta".into(),
+            condition,
+            labels: Default::default(),
+            state: AlertState::Firing,
+            state_changed_at: None,
+            value: Some(95.0),
+            created_at: "2026-01-01 00:00:00".into(),
+        };
+        Notification::alert(&rule, AlertState::Firing, 95.0)
+    }
+
+    #[test]
+    fn plain_body_lists_fields_with_values() {
+        let body = plain_body(&url_down());
+
+        assert!(body.starts_with(
+            "URL fora do ar: https://example.com/health?a=1&b=<2>: http_error, status 503\n\n"
+        ));
+        assert!(body.contains("status_code: 503\n"));
+        assert!(body.contains("up: false\n"));
+        assert!(body.contains("url: https://example.com/health?a=1&b=<2>\n"));
+        assert!(!body.contains("error:"));
+    }
+
+    #[test]
+    fn html_body_escapes_fields_in_a_table() {
+        let body = html_body(&url_down());
+
+        assert!(body.starts_with(
+            "<html><body><h2>URL fora do ar: https://example.com/health?a=1&amp;b=&lt;2&gt;</h2>"
+        ));
+        assert!(body.contains(
+            "<tr><th align=\"left\">url</th><td>https://example.com/health?a=1&amp;b=&lt;2&gt;</td></tr>"
+        ));
+        assert!(!body.contains("<th align=\"left\">error</th>"));
+    }
+
+    #[test]
+    fn message_has_sender_recipients_subject_and_both_parts() {
+        for (notification, subject) in [
+            (
+                url_down(),
+                "Subject: URL fora do ar: https://example.com/health?a=1&b=<2>",
+            ),
+            (alert_firing(), "Subject: Alerta disparado: cpu alta"),
+        ] {
+            let formatted = message(&config(25), &notification).unwrap().formatted();
+            let formatted = String::from_utf8(formatted).unwrap();
+
+            assert!(formatted.contains("From: Monitor <monitor@example.com>\r\n"));
+            assert!(formatted.contains("To: ops@example.com, Plantao <oncall@example.com>\r\n"));
+            assert!(formatted.contains(&format!("{}\r\n", subject)));
+            assert!(formatted.contains("Content-Type: multipart/alternative;"));
+            assert!(formatted.contains("Content-Type: text/plain; charset=utf-8"));
+            assert!(formatted.contains("Content-Type: text/html; charset=utf-8"));
+        }
+    }
+
+    #[test]
+    fn message_rejects_invalid_recipient() {
+        let mut config = config(25);
+        config.to.push("not an address".into());
+
+        assert_eq!(
+            message(&config, &url_down()).unwrap_err(),
+            "Endereço de email inválido: not an address"
+        );
+    }
+
+    #[tokio::test]
+    async fn send_delivers_to_local_smtp_server_without_tls() {
+        let stand_in = SmtpStandIn::start().await;
+
+        let sent = send(&config(stand_in.port), &alert_firing()).await;
+
+        assert_eq!(sent.ok(), Some(Some(250)));
+        let mails = stand_in.take_mails();
+        assert_eq!(mails.len(), 1);
+        assert_eq!(mails[0].from, "<monitor@example.com>");
+        assert_eq!(
+            mails[0].recipients,
+            vec!["<ops@example.com>", "<oncall@example.com>"]
+        );
+        assert!(mails[0]
+            .data
+            .contains("Subject: Alerta disparado: cpu alta\r\n"));
+        assert!(mails[0]
+            .data
+            .contains("Alerta disparado: cpu alta: total_cpu_usage > 90"));
+    }
+}
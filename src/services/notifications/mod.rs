@@ -0,0 +1,5 @@
+pub mod chat;
+pub mod email;
+pub mod notification;
+pub mod notifier;
+pub mod webhook;
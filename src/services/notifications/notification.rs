@@ -0,0 +1,190 @@
+use crate::{
+    database::{
+        models::{AlertRule, AlertState, CheckResult, EventKind, UrlCertificate, UrlData},
+        time::{format_timestamp, now},
+    },
+    services::{alerts::expression::format_condition, monitor::certificate},
+};
+
+/// A state change to tell the notification channels about.
+#[derive(Clone)]
+pub struct Notification {
+    pub event: EventKind,
+    pub subject: String,
+    pub message: String,
+    /// Details of the event, sent along with the subject and available to body templates.
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Notification {
+    fn new(event: EventKind, subject: String, message: String, fields: serde_json::Value) -> Self {
+        let mut fields = match fields {
+            serde_json::Value::Object(fields) => fields,
+            _ => serde_json::Map::new(),
+        };
+        fields.insert("timestamp".into(), format_timestamp(&now()).into());
+        Notification {
+            event,
+            subject,
+            message,
+            fields,
+        }
+    }
+
+    fn url(event: EventKind, subject: String, url_data: &UrlData, result: &CheckResult) -> Self {
+        let mut message = format!("{}: {}", subject, result.outcome.as_str());
+        if let Some(status_code) = result.status_code {
+            message.push_str(&format!(", status {}", status_code));
+        }
+        if let Some(detail) = result.failed_assertion.as_ref().or(result.error.as_ref()) {
+            message.push_str(&format!(" ({})", detail));
+        }
+        Notification::new(
+            event,
+            subject,
+            message,
+            serde_json::json!({
+                "url_id": url_data.id,
+                "url": url_data.url,
+                "up": result.up,
+                "status_code": result.status_code,
+                "outcome": result.outcome.as_str(),
+                "response_time_ms": result.response_time_ms,
+                "error": result.error,
+                "failed_assertion": result.failed_assertion,
+            }),
+        )
+    }
+
+    pub fn url_down(url_data: &UrlData, result: &CheckResult) -> Self {
+        let subject = format!("URL fora do ar: {}", url_data.url);
+        Notification::url(EventKind::UrlDown, subject, url_data, result)
+    }
+
+    pub fn url_up(url_data: &UrlData, result: &CheckResult) -> Self {
+        let subject = format!("URL de volta: {}", url_data.url);
+        Notification::url(EventKind::UrlUp, subject, url_data, result)
+    }
+
+    /// `flapping` is whether the monitor started or stopped flapping, `changes` the status
+    /// changes counted in the detection window.
+    pub fn url_flapping(
+        url_data: &UrlData,
+        result: &CheckResult,
+        flapping: bool,
+        changes: usize,
+    ) -> Self {
+        let subject = if flapping {
+            format!("URL instável: {}", url_data.url)
+        } else {
+            format!("URL estável novamente: {}", url_data.url)
+        };
+        let mut notification = Notification::url(EventKind::UrlFlapping, subject, url_data, result);
+        notification
+            .fields
+            .insert("flapping".into(), flapping.into());
+        notification
+            .fields
+            .insert("state_changes".into(), changes.into());
+        notification
+    }
+
+    /// `state` is the new state of the rule, firing or resolved.
+    pub fn alert(rule: &AlertRule, state: AlertState, value: f64) -> Self {
+        let (event, subject) = if state == AlertState::Firing {
+            (
+                EventKind::AlertFiring,
+                format!("Alerta disparado: {}", rule.name),
+            )
+        } else {
+            (
+                EventKind::AlertResolved,
+                format!("Alerta resolvido: {}", rule.name),
+            )
+        };
+        let expression = format_condition(&rule.condition);
+        Notification::new(
+            event,
+            subject.clone(),
+            format!("{}: {} (valor atual {:.1})", subject, expression, value),
+            serde_json::json!({
+                "rule_id": rule.id,
+                "rule": rule.name,
+                "expression": expression,
+                "metric": rule.condition.metric.as_str(),
+                "threshold": rule.condition.threshold,
+                "value": value,
+                "state": state.as_str(),
+            }),
+        )
+    }
+
+    /// `expiring` is whether the certificate entered the warning period or left it after a
+    /// renewal.
+    pub fn certificate_expiring(
+        url_data: &UrlData,
+        certificate: &UrlCertificate,
+        expiring: bool,
+    ) -> Self {
+        let days_left = certificate::days_until_expiry(certificate);
+        let subject = match days_left {
+            _ if !expiring => format!("Certificado renovado: {}", url_data.url),
+            Some(days) if days < 0 => format!("Certificado expirado: {}", url_data.url),
+            Some(days) => format!("Certificado expira em {} dias: {}", days, url_data.url),
+            None => format!("Certificado expirando: {}", url_data.url),
+        };
+        let leaf = certificate.chain.first();
+        let not_after = leaf.map(|leaf| leaf.not_after.clone());
+        Notification::new(
+            EventKind::CertificateExpiring,
+            subject.clone(),
+            format!(
+                "{} (válido até {})",
+                subject,
+                not_after.as_deref().unwrap_or("?")
+            ),
+            serde_json::json!({
+                "url_id": url_data.id,
+                "url": url_data.url,
+                "expiring": expiring,
+                "days_until_expiry": days_left,
+                "not_after": not_after,
+                "certificate_subject": leaf.map(|leaf| &leaf.subject),
+                "certificate_issuer": leaf.map(|leaf| &leaf.issuer),
+            }),
+        )
+    }
+
+    pub fn test(channel_name: &str) -> Self {
+        let subject = format!("Notificação de teste: {}", channel_name);
+        Notification::new(
+            EventKind::Test,
+            subject.clone(),
+            subject,
+            serde_json::json!({}),
+        )
+    }
+
+    /// Every field of the notification in one object, the default webhook body.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut json = self.fields.clone();
+        json.insert("event".into(), self.event.as_str().into());
+        json.insert("subject".into(), self.subject.clone().into());
+        json.insert("message".into(), self.message.clone().into());
+        serde_json::Value::Object(json)
+    }
+
+    /// Reads the object written by `to_json`.
+    pub fn from_json(json: &serde_json::Value) -> Option<Notification> {
+        let mut fields = json.as_object()?.clone();
+        let event = EventKind::parse(fields.remove("event")?.as_str()?)?;
+        let subject = fields.remove("subject")?.as_str()?.to_string();
+        let message = fields.remove("message")?.as_str()?.to_string();
+        Some(Notification {
+            event,
+            subject,
+            message,
+            fields,
+        })
+    }
+}
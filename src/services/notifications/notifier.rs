@@ -0,0 +1,328 @@
+use std::{env, time::Duration};
+
+use reqwest::Client;
+
+use crate::{
+    database::{
+        models::{Alert, ChannelConfig, DeliveryResult, IncidentEventKind, NotificationChannel},
+        storage::SharedStorage,
+        time::{now, parse_timestamp},
+    },
+    services::notifications::{chat, email, notification::Notification, webhook},
+};
+
+/// Why a channel did not accept a notification.
+pub struct SendError {
+    pub status_code: Option<i32>,
+    pub message: String,
+}
+
+/// Delivers notifications to the channels subscribed to them, retrying failed deliveries with
+/// a doubling delay and logging every attempt. Clones share the HTTP client.
+#[derive(Clone)]
+pub struct Notifier {
+    storage: SharedStorage,
+    client: Client,
+    max_attempts: u32,
+    retry_delay: Duration,
+}
+
+impl Notifier {
+    pub fn new(storage: SharedStorage) -> reqwest::Result<Notifier> {
+        let max_attempts = env::var("NOTIFICATION_MAX_ATTEMPTS")
+            .ok()
+            .and_then(|attempts| attempts.parse::<u32>().ok())
+            .filter(|attempts| *attempts > 0)
+            .unwrap_or(5);
+        let retry_delay = env::var("NOTIFICATION_RETRY_DELAY")
+            .ok()
+            .and_then(|seconds| seconds.parse::<u64>().ok())
+            .unwrap_or(2);
+        Ok(Notifier {
+            storage,
+            client: Client::builder().build()?,
+            max_attempts,
+            retry_delay: Duration::from_secs(retry_delay),
+        })
+    }
+
+    /// Delivers to every enabled channel subscribed to the event, in the background.
+    pub fn notify(&self, notification: Notification) {
+        let notifier = self.clone();
+        tokio::spawn(async move {
+            let channels = match notifier.storage.get_channels().await {
+                Ok(channels) => channels,
+                Err(e) => {
+                    eprintln!("Error loading notification channels: {}", e);
+                    return;
+                }
+            };
+            for channel in channels {
+                if !channel.accepts(notification.event) {
+                    continue;
+                }
+                let notifier = notifier.clone();
+                let notification = notification.clone();
+                tokio::spawn(async move { notifier.deliver(&channel, &notification).await });
+            }
+        });
+    }
+
+    /// Like `notify`, skipped while a silence matches the alert. Once the alert is resolved,
+    /// `duration_seconds` tells how long it lasted.
+    pub fn notify_alert(&self, alert: Alert, mut notification: Notification) {
+        notification
+            .fields
+            .insert("alert_id".into(), alert.id.into());
+        if let (Some(started_at), Some(resolved_at)) = (
+            parse_timestamp(&alert.started_at),
+            alert.resolved_at.as_deref().and_then(parse_timestamp),
+        ) {
+            notification.fields.insert(
+                "duration_seconds".into(),
+                (resolved_at - started_at).num_seconds().into(),
+            );
+        }
+        let notifier = self.clone();
+        tokio::spawn(async move {
+            let silences = match notifier.storage.get_silences(Some(now())).await {
+                Ok(silences) => silences,
+                Err(e) => {
+                    eprintln!("Error loading silences: {}", e);
+                    Vec::new()
+                }
+            };
+            if let Some(silence) = silences
+                .iter()
+                .find(|silence| silence.matcher.matches(&alert))
+            {
+                println!(
+                    "Notification '{}' silenced by silence {}",
+                    notification.subject, silence.id
+                );
+                return;
+            }
+            notifier.notify(notification);
+        });
+    }
+
+    /// Tries until the channel accepts the notification or the attempts run out. The outcome
+    /// of an alert notification goes to the timeline of its incident.
+    pub async fn deliver(
+        &self,
+        channel: &NotificationChannel,
+        notification: &Notification,
+    ) -> DeliveryResult {
+        let mut delay = self.retry_delay;
+        let mut attempt = 1;
+        loop {
+            let result = self.send(channel, notification, attempt).await;
+            if result.success || attempt >= self.max_attempts {
+                if !result.success {
+                    eprintln!(
+                        "Giving up on notifying channel '{}' after {} attempts",
+                        channel.name, attempt
+                    );
+                }
+                self.record_incident_event(channel, notification, &result)
+                    .await;
+                return result;
+            }
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+
+    async fn record_incident_event(
+        &self,
+        channel: &NotificationChannel,
+        notification: &Notification,
+        result: &DeliveryResult,
+    ) {
+        let Some(alert_id) = notification
+            .fields
+            .get("alert_id")
+            .and_then(|alert_id| alert_id.as_i64())
+        else {
+            return;
+        };
+        let incident = match self.storage.get_alert_incident(alert_id).await {
+            Ok(Some(incident)) => incident,
+            Ok(None) => return,
+            Err(e) => {
+                eprintln!("Error loading incident of alert {}: {}", alert_id, e);
+                return;
+            }
+        };
+        let message = if result.success {
+            format!(
+                "Canal '{}' notificado: {}",
+                channel.name, notification.subject
+            )
+        } else {
+            format!(
+                "Falha ao notificar o canal '{}' após {} tentativas: {}",
+                channel.name,
+                result.attempt,
+                result.error.as_deref().unwrap_or_default()
+            )
+        };
+        if let Err(e) = self
+            .storage
+            .add_incident_event(incident.id, IncidentEventKind::Notified, message)
+            .await
+        {
+            eprintln!("Error saving event of incident {}: {}", incident.id, e);
+        }
+    }
+
+    /// One delivery attempt, recorded in the delivery log.
+    pub async fn send(
+        &self,
+        channel: &NotificationChannel,
+        notification: &Notification,
+        attempt: u32,
+    ) -> DeliveryResult {
+        let sent = match &channel.config {
+            ChannelConfig::Webhook(config) => {
+                webhook::send(&self.client, config, notification).await
+            }
+            ChannelConfig::Email(config) => email::send(config, notification).await,
+            ChannelConfig::Slack(config) => {
+                chat::send_slack(&self.client, config, notification).await
+            }
+            ChannelConfig::Discord(config) => {
+                chat::send_discord(&self.client, config, notification).await
+            }
+            ChannelConfig::Teams(config) => {
+                chat::send_teams(&self.client, config, notification).await
+            }
+            ChannelConfig::Telegram(config) => {
+                chat::send_telegram(&self.client, config, notification).await
+            }
+        };
+        let result = match sent {
+            Ok(status_code) => DeliveryResult {
+                event: notification.event,
+                subject: notification.subject.clone(),
+                attempt,
+                success: true,
+                status_code,
+                error: None,
+            },
+            Err(e) => {
+                eprintln!(
+                    "Error notifying channel '{}' (attempt {}): {}",
+                    channel.name, attempt, e.message
+                );
+                DeliveryResult {
+                    event: notification.event,
+                    subject: notification.subject.clone(),
+                    attempt,
+                    success: false,
+                    status_code: e.status_code,
+                    error: Some(e.message),
+                }
+            }
+        };
+        if let Err(e) = self.storage.save_delivery(channel.id, result.clone()).await {
+            eprintln!("Error saving delivery to '{}': {}", channel.name, e);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use super::*;
+    use crate::{
+        database::{memory::storage::MemoryStorage, models::WebhookConfig},
+        test_support::HttpStandIn,
+    };
+
+    const RETRY_DELAY: Duration = Duration::from_millis(50);
+
+    async fn webhook_channel(storage: &SharedStorage, url: String) -> NotificationChannel {
+        let config = ChannelConfig::Webhook(WebhookConfig {
+            url,
+            method: "POST".into(),
+            headers: BTreeMap::new(),
+            body_template: None,
+        });
+        storage
+            .create_channel("ops".into(), config, Vec::new(), true)
+            .await
+            .unwrap()
+    }
+
+    fn notifier(storage: SharedStorage, max_attempts: u32) -> Notifier {
+        Notifier {
+            storage,
+            client: Client::new(),
+            max_attempts,
+            retry_delay: RETRY_DELAY,
+        }
+    }
+
+    #[tokio::test]
+    async fn deliver_retries_with_backoff_and_logs_every_attempt() {
+        let stand_in = HttpStandIn::start(&[500, 503]).await;
+        let storage: SharedStorage = Arc::new(MemoryStorage::new());
+        let channel = webhook_channel(&storage, stand_in.url("/hook")).await;
+
+        let result = notifier(storage.clone(), 5)
+            .deliver(&channel, &Notification::test("ops"))
+            .await;
+
+        assert!(result.success);
+        assert_eq!(result.attempt, 3);
+        let requests = stand_in.take_requests();
+        assert_eq!(requests.len(), 3);
+        let first_wait = requests[1].received_at - requests[0].received_at;
+        let second_wait = requests[2].received_at - requests[1].received_at;
+        assert!(first_wait >= RETRY_DELAY);
+        assert!(second_wait >= RETRY_DELAY * 2);
+
+        let deliveries = storage.get_deliveries(Some(channel.id), 10).await.unwrap();
+        let attempts = deliveries
+            .iter()
+            .rev()
+            .map(|delivery| {
+                (
+                    delivery.result.attempt,
+                    delivery.result.success,
+                    delivery.result.status_code,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            attempts,
+            vec![
+                (1, false, Some(500)),
+                (2, false, Some(503)),
+                (3, true, Some(200))
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn deliver_gives_up_after_max_attempts() {
+        let stand_in = HttpStandIn::start(&[500, 500, 500]).await;
+        let storage: SharedStorage = Arc::new(MemoryStorage::new());
+        let channel = webhook_channel(&storage, stand_in.url("/hook")).await;
+
+        let result = notifier(storage.clone(), 2)
+            .deliver(&channel, &Notification::test("ops"))
+            .await;
+
+        assert!(!result.success);
+        assert_eq!(result.attempt, 2);
+        assert_eq!(stand_in.take_requests().len(), 2);
+        let deliveries = storage.get_deliveries(Some(channel.id), 10).await.unwrap();
+        assert_eq!(deliveries.len(), 2);
+        assert!(deliveries.iter().all(|delivery| !delivery.result.success));
+    }
+}
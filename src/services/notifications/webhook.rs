@@ -0,0 +1,196 @@
+use std::{sync::OnceLock, time::Duration};
+
+use regex::{Captures, Regex};
+use reqwest::{Client, Method, RequestBuilder};
+
+use crate::{
+    database::models::WebhookConfig,
+    services::notifications::{notification::Notification, notifier::SendError},
+};
+
+pub const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Replaces `{{field}}` placeholders in the string values of `template`. A string that is
+/// only a placeholder takes the field's JSON value, so numbers and booleans keep their type.
+pub fn render(template: &serde_json::Value, values: &serde_json::Value) -> serde_json::Value {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    let placeholder = PLACEHOLDER.get_or_init(|| Regex::new(r"\{\{\s*([a-z_]+)\s*\}\}").unwrap());
+    render_value(template, values, placeholder)
+}
+
+fn render_value(
+    template: &serde_json::Value,
+    values: &serde_json::Value,
+    placeholder: &Regex,
+) -> serde_json::Value {
+    match template {
+        serde_json::Value::String(text) => {
+            if let Some(captures) = placeholder.captures(text) {
+                if captures[0].len() == text.len() {
+                    return values
+                        .get(&captures[1])
+                        .cloned()
+                        .unwrap_or(serde_json::Value::Null);
+                }
+            }
+            let rendered = placeholder.replace_all(text, |captures: &Captures| {
+                match values.get(&captures[1]) {
+                    Some(serde_json::Value::String(value)) => value.clone(),
+                    Some(serde_json::Value::Null) | None => String::new(),
+                    Some(value) => value.to_string(),
+                }
+            });
+            serde_json::Value::String(rendered.into_owned())
+        }
+        serde_json::Value::Array(items) => serde_json::Value::Array(
+            items
+                .iter()
+                .map(|item| render_value(item, values, placeholder))
+                .collect(),
+        ),
+        serde_json::Value::Object(fields) => serde_json::Value::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (name.clone(), render_value(value, values, placeholder)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Sends the notification as JSON, `Ok` holds the status of a 2xx response.
+pub async fn send(
+    client: &Client,
+    config: &WebhookConfig,
+    notification: &Notification,
+) -> Result<Option<i32>, SendError> {
+    let values = notification.to_json();
+    let body = match &config.body_template {
+        Some(template) => render(template, &values),
+        None => values,
+    };
+    let method = Method::from_bytes(config.method.as_bytes()).unwrap_or(Method::POST);
+
+    let mut request = client
+        .request(method, &config.url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(&body);
+    for (name, value) in &config.headers {
+        request = request.header(name, value);
+    }
+    deliver(request).await
+}
+
+/// Sends the request, `Ok` holds the status of a 2xx response. Errors leave the URL out,
+/// chat webhook URLs and bot tokens are secrets.
+pub async fn deliver(request: RequestBuilder) -> Result<Option<i32>, SendError> {
+    let response = request.send().await.map_err(|e| SendError {
+        status_code: None,
+        message: e.without_url().to_string(),
+    })?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(Some(status.as_u16() as i32))
+    } else {
+        Err(SendError {
+            status_code: Some(status.as_u16() as i32),
+            message: format!("Unexpected status {}", status),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::test_support::HttpStandIn;
+
+    #[test]
+    fn render_replaces_placeholders() {
+        let values = serde_json::json!({
+            "subject": "URL fora do ar",
+            "status_code": 503,
+            "up": false,
+            "error": null,
+        });
+        let template = serde_json::json!({
+            "text": "{{subject}} ({{ status_code }})",
+            "code": "{{status_code}}",
+            "up": "{{up}}",
+            "nested": [{"error": "erro: {{error}}"}, "{{missing}}", 1],
+            "plain": "sem campos",
+        });
+
+        assert_eq!(
+            render(&template, &values),
+            serde_json::json!({
+                "text": "URL fora do ar (503)",
+                "code": 503,
+                "up": false,
+                "nested": [{"error": "erro: "}, null, 1],
+                "plain": "sem campos",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn send_uses_configured_method_headers_and_body() {
+        let stand_in = HttpStandIn::start(&[]).await;
+        let config = WebhookConfig {
+            url: stand_in.url("/hook"),
+            method: "PUT".into(),
+            headers: BTreeMap::from([("X-Token".to_string(), "secret".to_string())]),
+            body_template: Some(serde_json::json!({"text": "{{subject}}", "kind": "{{event}}"})),
+        };
+
+        let sent = send(&Client::new(), &config, &Notification::test("ops")).await;
+
+        assert_eq!(sent.ok(), Some(Some(200)));
+        let requests = stand_in.take_requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "PUT");
+        assert_eq!(requests[0].path, "/hook");
+        assert_eq!(requests[0].headers["x-token"], "secret");
+        assert_eq!(requests[0].headers["content-type"], "application/json");
+        assert_eq!(
+            requests[0].json(),
+            serde_json::json!({"text": "Notificação de teste: ops", "kind": "test"})
+        );
+    }
+
+    #[tokio::test]
+    async fn send_without_template_posts_the_notification() {
+        let stand_in = HttpStandIn::start(&[]).await;
+        let config = WebhookConfig {
+            url: stand_in.url("/hook"),
+            method: "POST".into(),
+            headers: BTreeMap::new(),
+            body_template: None,
+        };
+        let notification = Notification::test("ops");
+
+        send(&Client::new(), &config, &notification).await.ok();
+
+        let requests = stand_in.take_requests();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].json(), notification.to_json());
+    }
+
+    #[tokio::test]
+    async fn send_fails_on_error_status() {
+        let stand_in = HttpStandIn::start(&[502]).await;
+        let config = WebhookConfig {
+            url: stand_in.url("/hook"),
+            method: "POST".into(),
+            headers: BTreeMap::new(),
+            body_template: None,
+        };
+
+        let error = send(&Client::new(), &config, &Notification::test("ops"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.status_code, Some(502));
+    }
+}
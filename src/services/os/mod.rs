@@ -1,3 +1,3 @@
 pub mod cpu;
 pub mod memory;
-pub mod uptime;
\ No newline at end of file
+pub mod uptime;
@@ -1,4 +1,3 @@
-
 pub fn get_uptime() -> Result<String, Box<dyn std::error::Error>> {
     let is_linux = cfg!(target_os = "linux");
 
@@ -15,7 +14,7 @@ pub fn get_uptime() -> Result<String, Box<dyn std::error::Error>> {
             .arg("(Get-CimInstance Win32_OperatingSystem).LastBootUpTime.ToString('yyyy-MM-dd HH:mm:ss')")
             .output()?;
         let uptime = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        
+
         Ok(uptime)
     }
 }
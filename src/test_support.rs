@@ -0,0 +1,206 @@
+//! Local stand-ins for the services the monitor talks to, so tests run without the network.
+
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use http::{HeaderMap, Request, Response, StatusCode};
+use http_body_util::{BodyExt, Full};
+use hyper::{body::Bytes, server::conn::http1, service::service_fn};
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpListener;
+
+/// A request received by an `HttpStandIn`.
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path and query string.
+    pub path: String,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+    pub received_at: Instant,
+}
+
+impl RecordedRequest {
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).expect("request body is not JSON")
+    }
+}
+
+/// HTTP server on a random local port that records every request and answers with the
+/// given statuses in order, then 200.
+pub struct HttpStandIn {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl HttpStandIn {
+    pub async fn start(statuses: &[u16]) -> HttpStandIn {
+        HttpStandIn::start_delayed(statuses, Duration::ZERO).await
+    }
+
+    /// Like `start`, waiting `delay` before answering each request, to stall clients.
+    pub async fn start_delayed(statuses: &[u16], delay: Duration) -> HttpStandIn {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let statuses = Arc::new(Mutex::new(
+            statuses.iter().copied().collect::<VecDeque<_>>(),
+        ));
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let recorded = recorded.clone();
+                let statuses = statuses.clone();
+                tokio::spawn(async move {
+                    let service = service_fn(move |request: Request<hyper::body::Incoming>| {
+                        let recorded = recorded.clone();
+                        let statuses = statuses.clone();
+                        async move {
+                            let received_at = Instant::now();
+                            let (parts, body) = request.into_parts();
+                            let body = body.collect().await.unwrap().to_bytes().to_vec();
+                            recorded.lock().unwrap().push(RecordedRequest {
+                                method: parts.method.to_string(),
+                                path: parts
+                                    .uri
+                                    .path_and_query()
+                                    .map(|path| path.to_string())
+                                    .unwrap_or_default(),
+                                headers: parts.headers,
+                                body,
+                                received_at,
+                            });
+                            let status = statuses.lock().unwrap().pop_front().unwrap_or(200);
+                            tokio::time::sleep(delay).await;
+                            Ok::<_, Infallible>(
+                                Response::builder()
+                                    .status(StatusCode::from_u16(status).unwrap())
+                                    .body(Full::new(Bytes::from_static(b"{\"ok\": true}")))
+                                    .unwrap(),
+                            )
+                        }
+                    });
+                    let _ = http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        });
+
+        HttpStandIn { addr, requests }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    /// Takes the requests received so far, oldest first.
+    pub fn take_requests(&self) -> Vec<RecordedRequest> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+}
+
+/// A message received by an `SmtpStandIn`.
+pub struct RecordedMail {
+    pub from: String,
+    pub recipients: Vec<String>,
+    /// Headers and body as sent after `DATA`, without the final dot.
+    pub data: String,
+}
+
+/// Plaintext SMTP server on a random local port that accepts every message, like the local
+/// test servers the email channel supports with `security: none`.
+pub struct SmtpStandIn {
+    pub port: u16,
+    mails: Arc<Mutex<Vec<RecordedMail>>>,
+}
+
+impl SmtpStandIn {
+    pub async fn start() -> SmtpStandIn {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mails = Arc::new(Mutex::new(Vec::new()));
+
+        let received = mails.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let received = received.clone();
+                tokio::spawn(async move {
+                    let _ = smtp_session(stream, received).await;
+                });
+            }
+        });
+
+        SmtpStandIn { port, mails }
+    }
+
+    /// Takes the messages received so far, oldest first.
+    pub fn take_mails(&self) -> Vec<RecordedMail> {
+        std::mem::take(&mut *self.mails.lock().unwrap())
+    }
+}
+
+async fn smtp_session(
+    stream: tokio::net::TcpStream,
+    mails: Arc<Mutex<Vec<RecordedMail>>>,
+) -> std::io::Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    writer
+        .write_all(b"220 localhost ESMTP stand-in\r\n")
+        .await?;
+    let mut from = String::new();
+    let mut recipients = Vec::new();
+    while let Some(line) = lines.next_line().await? {
+        let command = line.to_uppercase();
+        let reply: &[u8] = if command.starts_with("EHLO") || command.starts_with("HELO") {
+            b"250 localhost\r\n"
+        } else if command.starts_with("MAIL FROM:") {
+            from = line[10..].trim().to_string();
+            b"250 OK\r\n"
+        } else if command.starts_with("RCPT TO:") {
+            recipients.push(line[8..].trim().to_string());
+            b"250 OK\r\n"
+        } else if command == "DATA" {
+            writer
+                .write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n")
+                .await?;
+            let mut data = String::new();
+            while let Some(line) = lines.next_line().await? {
+                if line == "." {
+                    break;
+                }
+                data.push_str(line.strip_prefix('.').unwrap_or(&line));
+                data.push_str("\r\n");
+            }
+            mails.lock().unwrap().push(RecordedMail {
+                from: std::mem::take(&mut from),
+                recipients: std::mem::take(&mut recipients),
+                data,
+            });
+            b"250 Queued\r\n"
+        } else if command == "QUIT" {
+            writer.write_all(b"221 Bye\r\n").await?;
+            return Ok(());
+        } else if command == "RSET" || command == "NOOP" {
+            b"250 OK\r\n"
+        } else {
+            b"502 Command not implemented\r\n"
+        };
+        writer.write_all(reply).await?;
+    }
+    Ok(())
+}